@@ -1,14 +1,20 @@
 //! Colouring of cyme output
+use clap::ValueEnum;
 use colored::*;
+use schemars::JsonSchema;
 use serde::ser::SerializeSeq;
 use serde::{Deserialize, Deserializer, Serialize};
 use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::usb::Speed;
 
 /// Colours [`crate::display::Block`] fields based on loose typing of field type
 ///
 /// Considered using HashMap with Colouring Enum like IconTheme but this seemed to suit better, it is less flexiable though...
-#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
-#[serde(deny_unknown_fields)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, JsonSchema)]
 pub struct ColourTheme {
     /// Colour to use for name from descriptor
     #[serde(
@@ -16,6 +22,7 @@ pub struct ColourTheme {
         serialize_with = "color_serializer",
         deserialize_with = "deserialize_option_color_from_string"
     )]
+    #[schemars(with = "Option<ColourSchema>")]
     pub name: Option<Color>,
     /// Colour to use for serial from descriptor
     #[serde(
@@ -23,13 +30,23 @@ pub struct ColourTheme {
         serialize_with = "color_serializer",
         deserialize_with = "deserialize_option_color_from_string"
     )]
+    #[schemars(with = "Option<ColourSchema>")]
     pub serial: Option<Color>,
+    /// Colour to use for serial that has been masked
+    #[serde(
+        default,
+        serialize_with = "color_serializer",
+        deserialize_with = "deserialize_option_color_from_string"
+    )]
+    #[schemars(with = "Option<ColourSchema>")]
+    pub masked_serial: Option<Color>,
     /// Colour to use for manufacturer from descriptor
     #[serde(
         default,
         serialize_with = "color_serializer",
         deserialize_with = "deserialize_option_color_from_string"
     )]
+    #[schemars(with = "Option<ColourSchema>")]
     pub manufacturer: Option<Color>,
     /// Colour to use for driver from udev
     #[serde(
@@ -37,6 +54,7 @@ pub struct ColourTheme {
         serialize_with = "color_serializer",
         deserialize_with = "deserialize_option_color_from_string"
     )]
+    #[schemars(with = "Option<ColourSchema>")]
     pub driver: Option<Color>,
     /// Colour to use for general String data
     #[serde(
@@ -44,6 +62,7 @@ pub struct ColourTheme {
         serialize_with = "color_serializer",
         deserialize_with = "deserialize_option_color_from_string"
     )]
+    #[schemars(with = "Option<ColourSchema>")]
     pub string: Option<Color>,
     /// Colour to use for icons
     #[serde(
@@ -51,6 +70,7 @@ pub struct ColourTheme {
         serialize_with = "color_serializer",
         deserialize_with = "deserialize_option_color_from_string"
     )]
+    #[schemars(with = "Option<ColourSchema>")]
     pub icon: Option<Color>,
     /// Colour to use for location data
     #[serde(
@@ -58,6 +78,7 @@ pub struct ColourTheme {
         serialize_with = "color_serializer",
         deserialize_with = "deserialize_option_color_from_string"
     )]
+    #[schemars(with = "Option<ColourSchema>")]
     pub location: Option<Color>,
     /// Colour to use for path data
     #[serde(
@@ -65,6 +86,7 @@ pub struct ColourTheme {
         serialize_with = "color_serializer",
         deserialize_with = "deserialize_option_color_from_string"
     )]
+    #[schemars(with = "Option<ColourSchema>")]
     pub path: Option<Color>,
     /// Colour to use for general number values
     #[serde(
@@ -72,6 +94,7 @@ pub struct ColourTheme {
         serialize_with = "color_serializer",
         deserialize_with = "deserialize_option_color_from_string"
     )]
+    #[schemars(with = "Option<ColourSchema>")]
     pub number: Option<Color>,
     /// Colour to use for speed
     #[serde(
@@ -79,13 +102,23 @@ pub struct ColourTheme {
         serialize_with = "color_serializer",
         deserialize_with = "deserialize_option_color_from_string"
     )]
+    #[schemars(with = "Option<ColourSchema>")]
     pub speed: Option<Color>,
+    /// Colour to use for speed when negotiated is less than device is capable of
+    #[serde(
+        default,
+        serialize_with = "color_serializer",
+        deserialize_with = "deserialize_option_color_from_string"
+    )]
+    #[schemars(with = "Option<ColourSchema>")]
+    pub speed_mismatch: Option<Color>,
     /// Colour to use for Vendor ID
     #[serde(
         default,
         serialize_with = "color_serializer",
         deserialize_with = "deserialize_option_color_from_string"
     )]
+    #[schemars(with = "Option<ColourSchema>")]
     pub vid: Option<Color>,
     /// Colour to use for Product ID
     #[serde(
@@ -93,6 +126,7 @@ pub struct ColourTheme {
         serialize_with = "color_serializer",
         deserialize_with = "deserialize_option_color_from_string"
     )]
+    #[schemars(with = "Option<ColourSchema>")]
     pub pid: Option<Color>,
     /// Colour to use for generic ClassCode
     #[serde(
@@ -100,6 +134,7 @@ pub struct ColourTheme {
         serialize_with = "color_serializer",
         deserialize_with = "deserialize_option_color_from_string"
     )]
+    #[schemars(with = "Option<ColourSchema>")]
     pub class_code: Option<Color>,
     /// Colour to use for SubCodes
     #[serde(
@@ -107,6 +142,7 @@ pub struct ColourTheme {
         serialize_with = "color_serializer",
         deserialize_with = "deserialize_option_color_from_string"
     )]
+    #[schemars(with = "Option<ColourSchema>")]
     pub sub_code: Option<Color>,
     /// Colour to use for protocol
     #[serde(
@@ -114,6 +150,7 @@ pub struct ColourTheme {
         serialize_with = "color_serializer",
         deserialize_with = "deserialize_option_color_from_string"
     )]
+    #[schemars(with = "Option<ColourSchema>")]
     pub protocol: Option<Color>,
     /// Colour to use for info/enum type
     #[serde(
@@ -121,6 +158,7 @@ pub struct ColourTheme {
         serialize_with = "color_serializer",
         deserialize_with = "deserialize_option_color_from_string"
     )]
+    #[schemars(with = "Option<ColourSchema>")]
     pub attributes: Option<Color>,
     /// Colour to use for power information
     #[serde(
@@ -128,6 +166,7 @@ pub struct ColourTheme {
         serialize_with = "color_serializer",
         deserialize_with = "deserialize_option_color_from_string"
     )]
+    #[schemars(with = "Option<ColourSchema>")]
     pub power: Option<Color>,
     /// Tree colour
     #[serde(
@@ -135,6 +174,7 @@ pub struct ColourTheme {
         serialize_with = "color_serializer",
         deserialize_with = "deserialize_option_color_from_string"
     )]
+    #[schemars(with = "Option<ColourSchema>")]
     pub tree: Option<Color>,
     /// Colour at prepended before printing `USBBus`
     #[serde(
@@ -142,6 +182,7 @@ pub struct ColourTheme {
         serialize_with = "color_serializer",
         deserialize_with = "deserialize_option_color_from_string"
     )]
+    #[schemars(with = "Option<ColourSchema>")]
     pub tree_bus_start: Option<Color>,
     /// Colour printed at end of tree before printing `USBDevice`
     #[serde(
@@ -149,6 +190,7 @@ pub struct ColourTheme {
         serialize_with = "color_serializer",
         deserialize_with = "deserialize_option_color_from_string"
     )]
+    #[schemars(with = "Option<ColourSchema>")]
     pub tree_bus_terminator: Option<Color>,
     /// Colour printed at end of tree before printing configuration
     #[serde(
@@ -156,6 +198,7 @@ pub struct ColourTheme {
         serialize_with = "color_serializer",
         deserialize_with = "deserialize_option_color_from_string"
     )]
+    #[schemars(with = "Option<ColourSchema>")]
     pub tree_configuration_terminator: Option<Color>,
     /// Colour printed at end of tree before printing interface
     #[serde(
@@ -163,6 +206,7 @@ pub struct ColourTheme {
         serialize_with = "color_serializer",
         deserialize_with = "deserialize_option_color_from_string"
     )]
+    #[schemars(with = "Option<ColourSchema>")]
     pub tree_interface_terminator: Option<Color>,
     /// Colour for endpoint in before print
     #[serde(
@@ -170,6 +214,7 @@ pub struct ColourTheme {
         serialize_with = "color_serializer",
         deserialize_with = "deserialize_option_color_from_string"
     )]
+    #[schemars(with = "Option<ColourSchema>")]
     pub tree_endpoint_in: Option<Color>,
     /// Colour for endpoint out before print
     #[serde(
@@ -177,7 +222,24 @@ pub struct ColourTheme {
         serialize_with = "color_serializer",
         deserialize_with = "deserialize_option_color_from_string"
     )]
+    #[schemars(with = "Option<ColourSchema>")]
     pub tree_endpoint_out: Option<Color>,
+    /// Colour for devices added when showing differences between dumps
+    #[serde(
+        default,
+        serialize_with = "color_serializer",
+        deserialize_with = "deserialize_option_color_from_string"
+    )]
+    #[schemars(with = "Option<ColourSchema>")]
+    pub diff_added: Option<Color>,
+    /// Colour for devices removed when showing differences between dumps
+    #[serde(
+        default,
+        serialize_with = "color_serializer",
+        deserialize_with = "deserialize_option_color_from_string"
+    )]
+    #[schemars(with = "Option<ColourSchema>")]
+    pub diff_removed: Option<Color>,
 }
 
 fn deserialize_option_color_from_string<'de, D>(deserializer: D) -> Result<Option<Color>, D::Error>
@@ -186,25 +248,102 @@ where
 {
     #[derive(Deserialize)]
     #[serde(untagged)]
-    enum ColorOrNull<'a> {
-        Str(&'a str),
+    enum ColorOrNull {
+        Str(String),
         #[serde(deserialize_with = "deserialize_color")]
         FromStr(Color),
+        Index(u8),
         Null,
     }
 
     match ColorOrNull::deserialize(deserializer)? {
-        ColorOrNull::Str(s) => match s {
+        ColorOrNull::Str(s) => match s.as_str() {
             "" => Ok(None),
-            _ => Color::try_from(s)
+            _ => color_from_str(&s)
                 .map(Some)
                 .map_err(serde::de::Error::custom),
         },
         ColorOrNull::FromStr(i) => Ok(Some(i)),
+        ColorOrNull::Index(i) => Ok(Some(color_from_index(i))),
         ColorOrNull::Null => Ok(None),
     }
 }
 
+/// Parses a colour name like "bright-blue" or "bright blue", a 256 colour index like "33" or a truecolor "#rrggbb" into a [`Color`]
+///
+/// ```
+/// use colored::Color;
+/// use cyme::colour::color_from_str;
+///
+/// assert_eq!(color_from_str("bright-blue").unwrap(), Color::BrightBlue);
+/// assert_eq!(color_from_str("bright_blue").unwrap(), Color::BrightBlue);
+/// assert_eq!(color_from_str("9").unwrap(), Color::BrightRed);
+/// assert_eq!(color_from_str("#ff8000").unwrap(), Color::TrueColor { r: 255, g: 128, b: 0 });
+/// assert!(color_from_str("#ff80").is_err());
+/// ```
+pub fn color_from_str(s: &str) -> Result<Color, String> {
+    let s = s.trim();
+    if let Some(hex) = s.strip_prefix('#') {
+        if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!("Invalid truecolor '{}', should be #rrggbb", s));
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap_or(0);
+        Ok(Color::TrueColor {
+            r: channel(0),
+            g: channel(2),
+            b: channel(4),
+        })
+    } else if let Ok(i) = s.parse::<u8>() {
+        Ok(color_from_index(i))
+    } else {
+        Color::try_from(s.replace(['-', '_'], " ").as_str())
+            .map_err(|_| format!("Invalid colour '{}'", s))
+    }
+}
+
+/// Maps a 256 colour palette index onto a [`Color`]; the 16 system colours map to named colours and the rest to their xterm truecolor
+pub fn color_from_index(i: u8) -> Color {
+    const NAMED: [Color; 16] = [
+        Color::Black,
+        Color::Red,
+        Color::Green,
+        Color::Yellow,
+        Color::Blue,
+        Color::Magenta,
+        Color::Cyan,
+        Color::White,
+        Color::BrightBlack,
+        Color::BrightRed,
+        Color::BrightGreen,
+        Color::BrightYellow,
+        Color::BrightBlue,
+        Color::BrightMagenta,
+        Color::BrightCyan,
+        Color::BrightWhite,
+    ];
+    const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+    match i {
+        0..=15 => NAMED[i as usize],
+        16..=231 => {
+            let c = i - 16;
+            Color::TrueColor {
+                r: LEVELS[(c / 36) as usize],
+                g: LEVELS[((c / 6) % 6) as usize],
+                b: LEVELS[(c % 6) as usize],
+            }
+        }
+        _ => {
+            let grey = 8 + 10 * (i - 232);
+            Color::TrueColor {
+                r: grey,
+                g: grey,
+                b: grey,
+            }
+        }
+    }
+}
+
 // Custom color deserialize, adapted from: https://github.com/Peltoche/lsd/blob/master/src/theme/color.rs
 fn deserialize_color<'de, D>(deserializer: D) -> Result<Color, D::Error>
 where
@@ -222,7 +361,7 @@ where
         where
             E: serde::de::Error,
         {
-            Color::try_from(value)
+            color_from_str(value)
                 .map_err(|_| E::invalid_value(serde::de::Unexpected::Str(value), &self))
         }
 
@@ -290,6 +429,92 @@ fn color_to_string(color: Color) -> String {
     }
 }
 
+/// CSS colour value for `color`; named colours use the xterm palette so that they match a typical terminal
+///
+/// ```
+/// use colored::Color;
+/// use cyme::colour::color_to_css;
+///
+/// assert_eq!(color_to_css(Color::Red), "#cd0000");
+/// assert_eq!(color_to_css(Color::TrueColor { r: 16, g: 32, b: 255 }), "#1020ff");
+/// ```
+pub fn color_to_css(color: Color) -> String {
+    match color {
+        Color::Black => "#000000".into(),
+        Color::Red => "#cd0000".into(),
+        Color::Green => "#00cd00".into(),
+        Color::Yellow => "#cdcd00".into(),
+        Color::Blue => "#0000ee".into(),
+        Color::Magenta => "#cd00cd".into(),
+        Color::Cyan => "#00cdcd".into(),
+        Color::White => "#e5e5e5".into(),
+        Color::BrightBlack => "#7f7f7f".into(),
+        Color::BrightRed => "#ff0000".into(),
+        Color::BrightGreen => "#00ff00".into(),
+        Color::BrightYellow => "#ffff00".into(),
+        Color::BrightBlue => "#5c5cff".into(),
+        Color::BrightMagenta => "#ff00ff".into(),
+        Color::BrightCyan => "#00ffff".into(),
+        Color::BrightWhite => "#ffffff".into(),
+        Color::TrueColor { r, g, b } => format!("#{:02x}{:02x}{:02x}", r, g, b),
+    }
+}
+
+/// Gradient colour for a link `speed` from red for low-speed to green for super-speed+; None if [`Speed::Unknown`]
+///
+/// ```
+/// use colored::Color;
+/// use cyme::colour::speed_gradient;
+/// use cyme::usb::Speed;
+///
+/// assert_eq!(speed_gradient(&Speed::LowSpeed), Some(Color::Red));
+/// assert_eq!(speed_gradient(&Speed::SuperSpeedPlus), Some(Color::Green));
+/// assert_eq!(speed_gradient(&Speed::Unknown), None);
+/// ```
+pub fn speed_gradient(speed: &Speed) -> Option<Color> {
+    match speed {
+        Speed::Unknown => None,
+        Speed::LowSpeed => Some(Color::Red),
+        Speed::FullSpeed => Some(Color::Yellow),
+        Speed::HighSpeed | Speed::HighBandwidth => Some(Color::BrightYellow),
+        Speed::SuperSpeed => Some(Color::BrightGreen),
+        Speed::SuperSpeedPlus => Some(Color::Green),
+    }
+}
+
+/// Gradient colour for a current draw of `ma` from green for low power devices to red for those drawing more than a USB 3 port supplies
+///
+/// ```
+/// use colored::Color;
+/// use cyme::colour::power_gradient;
+///
+/// assert_eq!(power_gradient(100), Color::Green);
+/// assert_eq!(power_gradient(500), Color::Yellow);
+/// assert_eq!(power_gradient(900), Color::BrightRed);
+/// assert_eq!(power_gradient(1500), Color::Red);
+/// ```
+pub fn power_gradient(ma: u32) -> Color {
+    match ma {
+        0..=100 => Color::Green,
+        101..=500 => Color::Yellow,
+        501..=900 => Color::BrightRed,
+        _ => Color::Red,
+    }
+}
+
+/// Forms a colour can take in a [`ColourTheme`] for the JSON Schema, see [`color_from_str`]
+#[allow(dead_code)]
+#[derive(JsonSchema)]
+#[serde(untagged)]
+enum ColourSchema {
+    /// Name like "bright-blue", 256 colour index string or truecolor "#rrggbb"
+    Name(String),
+    /// 256 colour index
+    Index(u8),
+    /// Truecolor [r, g, b]
+    Rgb([u8; 3]),
+}
+
 /// Have to make this because external crate does not impl Display
 fn color_serializer<'a, S>(color: &'a Option<Color>, s: S) -> Result<S::Ok, S::Error>
 where
@@ -310,6 +535,21 @@ where
     }
 }
 
+/// Built-in [`ColourTheme`] presets for [`ColourTheme::preset`]
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum ColourPreset {
+    /// Default theme for dark terminal backgrounds
+    #[default]
+    Dark,
+    /// Theme for light terminal backgrounds; avoids white and yellow colours
+    Light,
+    /// No colours
+    Mono,
+}
+
 impl Default for ColourTheme {
     fn default() -> Self {
         ColourTheme::new()
@@ -317,11 +557,108 @@ impl Default for ColourTheme {
 }
 
 impl ColourTheme {
+    /// Each colour of the theme as `(config key, colour, what it colours)` for a legend
+    pub fn legend(&self) -> Vec<(&'static str, Option<Color>, &'static str)> {
+        vec![
+            ("name", self.name, "Name from descriptor"),
+            ("serial", self.serial, "Serial from descriptor"),
+            (
+                "masked_serial",
+                self.masked_serial,
+                "Serial that has been masked",
+            ),
+            (
+                "manufacturer",
+                self.manufacturer,
+                "Manufacturer from descriptor",
+            ),
+            ("driver", self.driver, "Driver from udev"),
+            ("string", self.string, "General string data"),
+            ("icon", self.icon, "Icons"),
+            ("location", self.location, "Bus number and tree positions"),
+            ("path", self.path, "Port and sys paths"),
+            ("number", self.number, "General number values"),
+            ("speed", self.speed, "Speed"),
+            (
+                "speed_mismatch",
+                self.speed_mismatch,
+                "Negotiated speed less than device is capable of",
+            ),
+            ("vid", self.vid, "Vendor ID"),
+            ("pid", self.pid, "Product ID"),
+            ("class_code", self.class_code, "Class code"),
+            ("sub_code", self.sub_code, "Sub-class code"),
+            ("protocol", self.protocol, "Protocol code"),
+            ("attributes", self.attributes, "Attributes and enum values"),
+            ("power", self.power, "Power information"),
+            ("tree", self.tree, "Tree lines"),
+            ("tree_bus_start", self.tree_bus_start, "Tree start of a bus"),
+            (
+                "tree_bus_terminator",
+                self.tree_bus_terminator,
+                "Tree device",
+            ),
+            (
+                "tree_configuration_terminator",
+                self.tree_configuration_terminator,
+                "Tree configuration",
+            ),
+            (
+                "tree_interface_terminator",
+                self.tree_interface_terminator,
+                "Tree interface",
+            ),
+            (
+                "tree_endpoint_in",
+                self.tree_endpoint_in,
+                "Tree IN endpoint",
+            ),
+            (
+                "tree_endpoint_out",
+                self.tree_endpoint_out,
+                "Tree OUT endpoint",
+            ),
+            ("diff_added", self.diff_added, "Device added with --diff"),
+            (
+                "diff_removed",
+                self.diff_removed,
+                "Device removed with --diff",
+            ),
+        ]
+    }
+
+    /// Colours of the theme rotated through for each depth of a path with `--rainbow-path`; those which are None are skipped
+    ///
+    /// ```
+    /// use colored::Color;
+    /// use cyme::colour::{ColourPreset, ColourTheme};
+    ///
+    /// let mut ct = ColourTheme::preset(ColourPreset::Mono);
+    /// assert!(ct.depth_colours().is_empty());
+    /// ct.path = Some(Color::Red);
+    /// ct.vid = Some(Color::Blue);
+    /// assert_eq!(ct.depth_colours(), vec![Color::Red, Color::Blue]);
+    /// ```
+    pub fn depth_colours(&self) -> Vec<Color> {
+        [
+            self.path,
+            self.location,
+            self.vid,
+            self.pid,
+            self.name,
+            self.serial,
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
     /// New theme with defaults
     pub fn new() -> Self {
         ColourTheme {
             name: Some(Color::BrightBlue),
             serial: Some(Color::Green),
+            masked_serial: Some(Color::BrightBlack),
             manufacturer: Some(Color::Blue),
             driver: Some(Color::Cyan),
             string: Some(Color::Blue),
@@ -330,6 +667,7 @@ impl ColourTheme {
             path: Some(Color::Cyan),
             number: Some(Color::Cyan),
             speed: Some(Color::Magenta),
+            speed_mismatch: Some(Color::BrightRed),
             vid: Some(Color::BrightYellow),
             pid: Some(Color::Yellow),
             class_code: Some(Color::BrightYellow),
@@ -344,8 +682,100 @@ impl ColourTheme {
             tree_interface_terminator: Some(Color::BrightBlack),
             tree_endpoint_in: Some(Color::Yellow),
             tree_endpoint_out: Some(Color::Magenta),
+            diff_added: Some(Color::Green),
+            diff_removed: Some(Color::Red),
+        }
+    }
+
+    /// Theme from built-in [`ColourPreset`] `p`
+    ///
+    /// ```
+    /// use cyme::colour::{ColourPreset, ColourTheme};
+    ///
+    /// assert_eq!(ColourTheme::preset(ColourPreset::Dark), ColourTheme::new());
+    /// assert_eq!(ColourTheme::preset(ColourPreset::Mono).name, None);
+    /// ```
+    pub fn preset(p: ColourPreset) -> Self {
+        match p {
+            ColourPreset::Dark => ColourTheme::new(),
+            ColourPreset::Light => ColourTheme {
+                name: Some(Color::Blue),
+                serial: Some(Color::Green),
+                masked_serial: Some(Color::BrightBlack),
+                manufacturer: Some(Color::Blue),
+                driver: Some(Color::Cyan),
+                string: Some(Color::Blue),
+                icon: None,
+                location: Some(Color::Magenta),
+                path: Some(Color::Cyan),
+                number: Some(Color::Cyan),
+                speed: Some(Color::Magenta),
+                speed_mismatch: Some(Color::Red),
+                vid: Some(Color::Red),
+                pid: Some(Color::Magenta),
+                class_code: Some(Color::Red),
+                sub_code: Some(Color::Magenta),
+                protocol: Some(Color::Magenta),
+                attributes: Some(Color::Magenta),
+                power: Some(Color::Red),
+                tree: Some(Color::BrightBlack),
+                tree_bus_start: Some(Color::BrightBlack),
+                tree_bus_terminator: Some(Color::BrightBlack),
+                tree_configuration_terminator: Some(Color::BrightBlack),
+                tree_interface_terminator: Some(Color::BrightBlack),
+                tree_endpoint_in: Some(Color::Red),
+                tree_endpoint_out: Some(Color::Magenta),
+                diff_added: Some(Color::Green),
+                diff_removed: Some(Color::Red),
+            },
+            ColourPreset::Mono => ColourTheme {
+                name: None,
+                serial: None,
+                masked_serial: None,
+                manufacturer: None,
+                driver: None,
+                string: None,
+                icon: None,
+                location: None,
+                path: None,
+                number: None,
+                speed: None,
+                speed_mismatch: None,
+                vid: None,
+                pid: None,
+                class_code: None,
+                sub_code: None,
+                protocol: None,
+                attributes: None,
+                power: None,
+                tree: None,
+                tree_bus_start: None,
+                tree_bus_terminator: None,
+                tree_configuration_terminator: None,
+                tree_interface_terminator: None,
+                tree_endpoint_in: None,
+                tree_endpoint_out: None,
+                diff_added: None,
+                diff_removed: None,
+            },
         }
     }
+
+    /// Reads a standalone theme JSON at `file_path`; any field omitted falls back to the built-in [`ColourTheme::new`] colour whilst `null` or "" disables colour for that field
+    pub fn from_file<P: AsRef<Path>>(file_path: P) -> Result<Self, io::Error> {
+        let data = fs::read_to_string(file_path)?;
+        let user: serde_json::Map<String, serde_json::Value> = serde_json::from_str(&data)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        let mut theme = match serde_json::to_value(ColourTheme::new())? {
+            serde_json::Value::Object(m) => m,
+            _ => serde_json::Map::new(),
+        };
+        theme.extend(user);
+
+        serde_json::from_value(serde_json::Value::Object(theme))
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
 }
 
 #[cfg(test)]
@@ -364,6 +794,70 @@ mod tests {
         assert_eq!(ct.name, Some(Color::Blue));
     }
 
+    #[test]
+    fn test_deserialize_color_theme_formats() {
+        let ct: ColourTheme = serde_json::from_str(
+            r##"{"name": "bright-blue", "serial": 33, "vid": "#102030", "pid": "200"}"##,
+        )
+        .unwrap();
+        assert_eq!(ct.name, Some(Color::BrightBlue));
+        assert_eq!(
+            ct.serial,
+            Some(Color::TrueColor {
+                r: 0,
+                g: 135,
+                b: 255
+            })
+        );
+        assert_eq!(
+            ct.vid,
+            Some(Color::TrueColor {
+                r: 16,
+                g: 32,
+                b: 48
+            })
+        );
+        assert_eq!(
+            ct.pid,
+            Some(Color::TrueColor {
+                r: 255,
+                g: 0,
+                b: 215
+            })
+        );
+    }
+
+    #[test]
+    fn test_colour_theme_from_file() {
+        let ct = ColourTheme::from_file("./tests/data/theme_partial.json").unwrap();
+        let default = ColourTheme::new();
+        assert_eq!(ct.name, Some(Color::BrightGreen));
+        assert_eq!(ct.serial, None);
+        assert_eq!(ct.vid, default.vid);
+        assert_eq!(ct.tree, default.tree);
+    }
+
+    #[test]
+    fn test_light_preset_no_near_white() {
+        let ct = ColourTheme::preset(ColourPreset::Light);
+        let value = serde_json::to_value(&ct).unwrap();
+        for (field, colour) in value.as_object().unwrap() {
+            let colour: Option<Color> =
+                deserialize_option_color_from_string(colour.to_owned()).unwrap();
+            let near_white = match colour {
+                Some(Color::White | Color::BrightWhite | Color::Yellow | Color::BrightYellow) => {
+                    true
+                }
+                // relative luminance
+                Some(Color::TrueColor { r, g, b }) => {
+                    0.2126 * r as f32 + 0.7152 * g as f32 + 0.0722 * b as f32 > 200.0
+                }
+                _ => false,
+            };
+            assert!(!near_white, "{} is near white: {:?}", field, colour);
+        }
+    }
+
     #[test]
     fn test_serialize_deserialize_color_theme() {
         let ct: ColourTheme = ColourTheme::new();
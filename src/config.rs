@@ -1,5 +1,7 @@
 //! Config for cyme binary
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs::File;
 use std::io;
 use std::io::{BufReader, Read};
@@ -11,18 +13,29 @@ use crate::colour;
 use crate::icon;
 
 const CONF_DIR: &'static str = "cyme";
-const CONF_NAME: &'static str = "cyme.json";
+/// Config file names checked in order within [`Config::config_file_path`]
+const CONF_NAMES: [&'static str; 2] = ["cyme.json", "cyme.toml"];
+/// Directory within `CONF_DIR` containing colour theme files
+const THEMES_DIR: &'static str = "themes";
+/// Current [`Config::version`]; bump and add a step to [`Config::migrate`] when fields change meaning
+pub const CONFIG_VERSION: u32 = 1;
 
 /// Allows user supplied icons to replace or add to `DEFAULT_ICONS` and `DEFAULT_TREE`
-#[derive(Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
-#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+///
+/// Missing fields take their default and unknown fields are logged as warnings rather than rejected so that a config can be shared across cyme versions
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "kebab-case", default)]
 pub struct Config {
+    /// Version of the config format; 0 for configs written before versioning. Older versions are upgraded with [`Config::migrate`] on load
+    pub version: u32,
     /// User supplied [`crate::icon::IconTheme`] - will merge with default
     #[serde(default)]
     pub icons: icon::IconTheme,
     /// User supplied [`crate::colour::ColourTheme`] - overrides default
     #[serde(default)]
     pub colours: colour::ColourTheme,
+    /// Built-in [`crate::colour::ColourPreset`] to use instead of `colours`
+    pub colour_preset: Option<colour::ColourPreset>,
     /// Default [`crate::display::DeviceBlocks`] to use for displaying devices
     pub blocks: Option<Vec<display::DeviceBlocks>>,
     /// Default [`crate::display::BusBlocks`] to use for displaying buses
@@ -33,8 +46,20 @@ pub struct Config {
     pub interface_blocks: Option<Vec<display::InterfaceBlocks>>,
     /// Default [`crate::display::EndpointBlocks`] to use for device endpoints
     pub endpoint_blocks: Option<Vec<display::EndpointBlocks>>,
+    /// [`crate::display::Alignment`] overrides for [`crate::display::DeviceBlocks`]
+    pub device_alignment: Option<HashMap<display::DeviceBlocks, display::Alignment>>,
+    /// [`crate::display::Alignment`] overrides for [`crate::display::BusBlocks`]
+    pub bus_alignment: Option<HashMap<display::BusBlocks, display::Alignment>>,
+    /// [`crate::display::Alignment`] overrides for [`crate::display::ConfigurationBlocks`]
+    pub config_alignment: Option<HashMap<display::ConfigurationBlocks, display::Alignment>>,
+    /// [`crate::display::Alignment`] overrides for [`crate::display::InterfaceBlocks`]
+    pub interface_alignment: Option<HashMap<display::InterfaceBlocks, display::Alignment>>,
+    /// [`crate::display::Alignment`] overrides for [`crate::display::EndpointBlocks`]
+    pub endpoint_alignment: Option<HashMap<display::EndpointBlocks, display::Alignment>>,
     /// Wether to hide device serial numbers by default
     pub mask_serials: Option<display::MaskSerial>,
+    /// Number of chars kept at each end of serials with 'partial' mask_serials
+    pub mask_serials_visible: Option<usize>,
     // non-Options copied from Args
     /// Attempt to maintain compatibility with lsusb output
     pub lsusb: bool,
@@ -50,6 +75,8 @@ pub struct Config {
     pub hide_hubs: bool,
     /// Show base16 values as base10 decimal instead
     pub decimal: bool,
+    /// Only show these [`crate::display::NumberCategory`] as decimal with `decimal`; None for all
+    pub decimal_categories: Option<Vec<display::NumberCategory>>,
     /// Disable padding to align blocks
     pub no_padding: bool,
     /// Disables icons and utf-8 charactors
@@ -61,29 +88,34 @@ pub struct Config {
 }
 
 impl Config {
-    /// New based on defaults
+    /// New based on defaults at [`CONFIG_VERSION`]
     pub fn new() -> Config {
-        Default::default()
+        Config {
+            version: CONFIG_VERSION,
+            ..Default::default()
+        }
     }
 
     /// From system config if exists else default
     #[cfg(not(debug_assertions))]
     pub fn sys() -> Config {
         if let Some(p) = Self::config_file_path() {
-            let path = p.join(CONF_NAME);
-            log::info!("Looking for cyme system config {:?}", &path);
-            return match Self::from_file(&path) {
-                Ok(c) => { 
+            log::info!(
+                "Looking for cyme system config {:?} in {:?}",
+                CONF_NAMES,
+                &p
+            );
+            return match Self::from_dir(&p) {
+                Ok(Some(c)) => {
                     log::info!("Loaded cyme system config {:?}", c);
                     c
-                },
+                }
+                Ok(None) => Self::new(),
                 Err(e) => {
-                    if e.kind() != io::ErrorKind::NotFound {
-                        log::warn!("Failed to read cyme system config {:?}: Error({})", &path, e);
-                    }
+                    log::warn!("Failed to read cyme system config: Error({})", e);
                     Self::new()
                 }
-            }
+            };
         }
         Self::new()
     }
@@ -95,6 +127,11 @@ impl Config {
         Self::new()
     }
 
+    /// JSON Schema of the [`Config`] file for editor validation and autocomplete
+    pub fn schema() -> schemars::schema::RootSchema {
+        schemars::schema_for!(Config)
+    }
+
     /// Get example [`Config`]
     pub fn example() -> Config {
         Config {
@@ -104,18 +141,85 @@ impl Config {
             config_blocks: Some(display::ConfigurationBlocks::default_blocks(false)),
             interface_blocks: Some(display::InterfaceBlocks::default_blocks(false)),
             endpoint_blocks: Some(display::EndpointBlocks::default_blocks(false)),
-            ..Default::default()
+            ..Self::new()
         }
     }
 
-    /// Attempt to read from .json format confg at `file_path`
+    /// Stamps a config loaded at an older [`Config::version`] with [`CONFIG_VERSION`]
+    ///
+    /// No field has changed meaning yet so this is only a version stamp; fields added since `version` already have their defaults from deserialization. A step belongs here if a field's meaning changes. A config from a newer cyme is left as is with a warning.
+    pub fn migrate(mut self) -> Config {
+        if self.version > CONFIG_VERSION {
+            log::warn!(
+                "Config version {} is newer than supported version {}; unknown fields will be ignored",
+                self.version,
+                CONFIG_VERSION
+            );
+            return self;
+        }
+
+        if self.version < 1 {
+            log::info!("Stamping config version {} as 1", self.version);
+            // version 1 only adds the version field itself
+            self.version = 1;
+        }
+
+        self
+    }
+
+    /// Deserialize a [`Config`] from `deserializer`, logging unknown fields as warnings rather than failing
+    fn deserialize_lenient<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Config, D::Error> {
+        serde_ignored::deserialize(deserializer, |path| {
+            log::warn!("Ignoring unknown config field '{}'", path)
+        })
+    }
+
+    /// Attempt to read from confg at `file_path`; .toml extension is parsed as TOML, otherwise as JSON
     pub fn from_file<P: AsRef<Path>>(file_path: P) -> Result<Config, io::Error> {
+        let is_toml = file_path
+            .as_ref()
+            .extension()
+            .map_or(false, |e| e.eq_ignore_ascii_case("toml"));
         let f = File::open(file_path)?;
         let mut br = BufReader::new(f);
         let mut data = String::new();
 
         br.read_to_string(&mut data)?;
-        serde_json::from_str::<Config>(&data).map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+        let config = if is_toml {
+            Self::deserialize_lenient(&mut toml::Deserializer::new(&data))
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?
+        } else {
+            Self::deserialize_lenient(&mut serde_json::Deserializer::from_str(&data))
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?
+        }
+        .migrate();
+
+        for key in config.icons.validate() {
+            log::warn!(
+                "Icon '{}' in config has an unrecognised class code so will never match",
+                key
+            );
+        }
+
+        Ok(config)
+    }
+
+    /// Read the first of `CONF_NAMES` which exists within `dir`
+    ///
+    /// Returns `Ok(None)` if no config files exist. A file which exists but fails to read or parse is an error rather than falling through to the next name
+    pub fn from_dir<P: AsRef<Path>>(dir: P) -> Result<Option<Config>, io::Error> {
+        for name in CONF_NAMES {
+            let path = dir.as_ref().join(name);
+            match Self::from_file(&path) {
+                Ok(c) => return Ok(Some(c)),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => (),
+                Err(e) => return Err(io::Error::new(e.kind(), format!("{:?}: {}", path, e))),
+            }
+        }
+
+        Ok(None)
     }
 
     /// This provides the path for a configuration file, specific to OS
@@ -123,12 +227,35 @@ impl Config {
     pub fn config_file_path() -> Option<PathBuf> {
         dirs::config_dir().map(|x| x.join(CONF_DIR))
     }
+
+    /// Path to colour theme `name` within the 'themes' directory of [`Config::config_file_path`]
+    pub fn theme_file_path(name: &str) -> Option<PathBuf> {
+        Self::config_file_path().map(|x| x.join(THEMES_DIR).join(format!("{}.json", name)))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_schema() {
+        let schema = serde_json::to_value(Config::schema()).unwrap();
+        let properties = schema["properties"].as_object().unwrap();
+        for key in [
+            "icons",
+            "colours",
+            "blocks",
+            "device-alignment",
+            "mask-serials",
+        ] {
+            assert!(properties.contains_key(key), "missing {}", key);
+        }
+        // block names are the kebab-case values used in the config
+        let blocks = serde_json::to_string(&schema["definitions"]["DeviceBlocks"]).unwrap();
+        assert!(blocks.contains("\"vendor-id\""));
+    }
+
     #[test]
     fn test_deserialize_example_file() {
         let path = PathBuf::from("./doc").join("cyme_example_config.json");
@@ -140,4 +267,65 @@ mod tests {
         let path = PathBuf::from("./tests/data").join("config_no_theme.json");
         Config::from_file(path).unwrap();
     }
+
+    #[test]
+    fn test_deserialize_config_no_theme_toml() {
+        let json =
+            Config::from_file(PathBuf::from("./tests/data").join("config_no_theme.json")).unwrap();
+        let toml =
+            Config::from_file(PathBuf::from("./tests/data").join("config_no_theme.toml")).unwrap();
+        assert_eq!(json, toml);
+    }
+
+    #[test]
+    fn test_config_from_dir() {
+        // no cyme.json or cyme.toml in data
+        assert!(Config::from_dir("./tests/data").unwrap().is_none());
+
+        // an invalid cyme.json is an error rather than falling through to cyme.toml
+        let dir =
+            std::env::temp_dir().join(format!("cyme_test_config_from_dir_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("cyme.json"), "{ not json").unwrap();
+        std::fs::copy("./tests/data/config_no_theme.toml", dir.join("cyme.toml")).unwrap();
+        let err = Config::from_dir(&dir).unwrap_err();
+        assert!(err.to_string().contains("cyme.json"));
+
+        // only tries cyme.toml when cyme.json does not exist
+        std::fs::remove_file(dir.join("cyme.json")).unwrap();
+        assert!(Config::from_dir(&dir).unwrap().is_some());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_migrate_v0_config() {
+        // no version, missing fields and an unknown field
+        let path = PathBuf::from("./tests/data").join("config_v0.json");
+        let config = Config::from_file(path).unwrap();
+        assert_eq!(config.version, CONFIG_VERSION);
+        assert_eq!(config.blocks, Some(vec![display::DeviceBlocks::Name]));
+        assert!(config.tree);
+        assert!(!config.headings);
+        assert_eq!(config.verbose, 0);
+    }
+
+    #[test]
+    fn test_unknown_nested_fields_ignored() {
+        let config = Config::deserialize_lenient(&mut serde_json::Deserializer::from_str(
+            r#"{"colours": {"name": "blue", "from-the-future": "red"}, "icons": {"from-the-future": {}}}"#,
+        ))
+        .unwrap();
+        assert_eq!(config.colours.name, Some(colored::Color::Blue));
+        assert_eq!(config.icons, icon::IconTheme::default());
+    }
+
+    #[test]
+    fn test_newer_config_version_kept() {
+        let config = Config {
+            version: CONFIG_VERSION + 1,
+            ..Default::default()
+        }
+        .migrate();
+        assert_eq!(config.version, CONFIG_VERSION + 1);
+    }
 }
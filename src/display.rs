@@ -1,26 +1,43 @@
 //! Provides the main utilities to display USB types within this crate - primarily used by `cyme` binary.
 //!
 //! TODO: There is some repeat code that could probably be made into functions/generics
+use clap::builder::{PossibleValue, TypedValueParser, ValueParserFactory};
 use clap::ValueEnum;
 use colored::*;
+use is_terminal::IsTerminal;
 use itertools::Itertools;
-use serde::{Deserialize, Serialize};
-use std::cmp;
-use std::collections::HashMap;
 use rand::{distributions::Alphanumeric, seq::IteratorRandom, Rng};
+use schemars::JsonSchema;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cmp;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::env;
+use std::ffi::OsStr;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::marker::PhantomData;
+use std::path::PathBuf;
+use std::process;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 use crate::colour;
 use crate::icon;
 use crate::system_profiler;
 use crate::system_profiler::{USBBus, USBDevice};
-use crate::usb::{ConfigAttributes, Direction, USBConfiguration, USBEndpoint, USBInterface};
+use crate::usb::{
+    hex_dump_lines, ClassCode, ConfigAttributes, Direction, Speed, USBConfiguration, USBEndpoint,
+    USBInterface, Version, HEX_UNAVAILABLE,
+};
 
 const MAX_VERBOSITY: u8 = 4;
 const ICON_HEADING: &'static str = "I";
 
 /// Info that can be printed about a [`USBDevice`]
 #[non_exhaustive]
-#[derive(Debug, ValueEnum, Eq, PartialEq, Clone, Hash, Serialize, Deserialize)]
+#[derive(Debug, ValueEnum, Eq, PartialEq, Clone, Hash, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 pub enum DeviceBlocks {
     /// Number of bus device is attached
@@ -29,8 +46,12 @@ pub enum DeviceBlocks {
     DeviceNumber,
     /// Position of device in parent branch
     BranchPosition,
+    /// Physical port number on parent hub; last of tree positions or device number for root devices
+    PortNumber,
     /// Linux style port path
     PortPath,
+    /// Bus and port chain like `lsusb -t`, e.g. '2-1.4.1'; unlike [`DeviceBlocks::PortPath`] a root_hub is its bus '2-0' rather than its interface
+    FullPath,
     /// Linux udev reported syspath
     SysPath,
     /// Linux udev reported driver loaded for device
@@ -41,18 +62,28 @@ pub enum DeviceBlocks {
     VendorId,
     /// Vendor unique product identifier
     ProductId,
+    /// Vendor and product identifier pair as 'vid:pid' in lsusb style
+    VidPid,
+    /// Number of configurations the device has
+    NumConfigurations,
     /// The device name as reported in descriptor or using usb_ids if None
     Name,
+    /// Where the shown [`DeviceBlocks::Name`] came from: 'desc' for the descriptor, 'ids' for usb_ids or '-' if unknown
+    NameSource,
     /// The device manufacturer as provided in descriptor or using usb_ids if None
     Manufacturer,
     /// The device product name as reported by usb_ids vidpid lookup
     ProductName,
     /// The device vendor name as reported by usb_ids vid lookup
     VendorName,
+    /// First vendor available of descriptor manufacturer, usb_ids vendor name then hex VID
+    Vendor,
     /// Device serial string as reported by descriptor
     Serial,
     /// Advertised device capable speed
     Speed,
+    /// Negotiated speed paired with the maximum implied by bcd_usb like '5Gb/10Gb'; coloured differently when they differ
+    SpeedVsMax,
     /// Position along all branches back to trunk device
     TreePositions,
     /// macOS system_profiler only - actually bus current in mA not power!
@@ -65,17 +96,25 @@ pub enum DeviceBlocks {
     BcdDevice,
     /// The supported USB version
     BcdUsb,
+    /// Friendly category of the supported USB version like 'USB 3.2 Gen 2'; raw bcd if unknown
+    UsbVersionCategory,
     /// Class of interface provided by USB IF - only available when using libusb
     ClassCode,
     /// Sub-class of interface provided by USB IF - only available when using libusb
     SubClass,
     /// Prototol code for interface provided by USB IF - only available when using libusb
     Protocol,
+    /// Time since the device was connected like '3m ago' - Linux udev only
+    ConnectedSince,
+    /// Runtime power management status like 'active' or 'suspended' - Linux sysfs only
+    PowerState,
+    /// Comma separated udev tags like 'seat,uaccess' - Linux udev only
+    Tags,
 }
 
 /// Info that can be printed about a [`USBBus`]
 #[non_exhaustive]
-#[derive(Debug, ValueEnum, Eq, PartialEq, Hash, Clone, Serialize, Deserialize)]
+#[derive(Debug, ValueEnum, Eq, PartialEq, Hash, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 pub enum BusBlocks {
     /// System bus number identifier
@@ -94,16 +133,24 @@ pub enum BusBlocks {
     PciRevision,
     /// syspath style port path to bus, applicable to Linux only
     PortPath,
+    /// Number of devices attached directly to the bus
+    NumDevices,
+    /// Total number of devices on the bus including those attached to hubs
+    NumTotalDevices,
+    /// USB version supported by the controller from the root_hub bcd_usb
+    UsbVersion,
+    /// Host controller driver like 'xhci_hcd' - Linux udev only
+    Driver,
 }
 
 /// Info that can be printed about a [`USBConfiguration`]
 #[non_exhaustive]
-#[derive(Debug, ValueEnum, Eq, PartialEq, Hash, Clone, Serialize, Deserialize)]
+#[derive(Debug, ValueEnum, Eq, PartialEq, Hash, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 pub enum ConfigurationBlocks {
     /// Name from string descriptor
     Name,
-    /// Number of config, bConfigurationValue; value to set to enable to configuration
+    /// Number of config, bConfigurationValue; value to set to enable to configuration. Active configuration is marked with '*'
     Number,
     /// Interfaces available for this configuruation
     NumInterfaces,
@@ -113,11 +160,17 @@ pub enum ConfigurationBlocks {
     IconAttributes,
     /// Maximum current consumption in mA
     MaxPower,
+    /// 'Y' if the configuration is self powered, otherwise '-'
+    SelfPowered,
+    /// 'Y' if the configuration supports remote wakeup, otherwise '-'
+    RemoteWakeup,
+    /// wTotalLength: size in bytes of the full configuration descriptor, '-' if not captured
+    TotalLength,
 }
 
 /// Info that can be printed about a [`USBInterface`]
 #[non_exhaustive]
-#[derive(Debug, ValueEnum, Eq, PartialEq, Hash, Clone, Serialize, Deserialize)]
+#[derive(Debug, ValueEnum, Eq, PartialEq, Hash, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 pub enum InterfaceBlocks {
     /// Name from string descriptor
@@ -128,29 +181,41 @@ pub enum InterfaceBlocks {
     PortPath,
     /// Class of interface provided by USB IF
     ClassCode,
+    /// Full descriptive name of the interface class like 'Human Interface Device'
+    ClassName,
     /// Sub-class of interface provided by USB IF
     SubClass,
     /// Prototol code for interface provided by USB IF
     Protocol,
     /// Interfaces can have the same number but an alternate settings defined here
     AltSetting,
+    /// Number of alternate settings sharing this interface number within the parent configuration
+    NumAltSettings,
     /// Driver obtained from udev on Linux only
     Driver,
     /// syspath obtained from udev on Linux only
     SysPath,
     /// An interface can have many endpoints
     NumEndpoints,
+    /// iInterface string descriptor index of the interface name; '-' if none
+    StringIndex,
+    /// Number of IN and OUT endpoints of the interface, e.g. '2IN 1OUT'
+    EndpointSummary,
+    /// Function class and first interface number of the Interface Association the interface is part of, e.g. 'Video/0'; '-' if none
+    AssociationId,
     /// Icon based on ClassCode/SubCode/Protocol
     Icon,
 }
 
 /// Info that can be printed about a [`USBEndpoint`]
 #[non_exhaustive]
-#[derive(Debug, ValueEnum, Eq, PartialEq, Hash, Clone, Serialize, Deserialize)]
+#[derive(Debug, ValueEnum, Eq, PartialEq, Hash, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 pub enum EndpointBlocks {
     /// Endpoint number on interface
     Number,
+    /// Full bEndpointAddress byte; direction bit and number like '0x81'
+    Address,
     /// Direction of data into endpoint
     Direction,
     /// Type of data transfer endpoint accepts
@@ -163,6 +228,305 @@ pub enum EndpointBlocks {
     MaxPacketSize,
     /// Interval for polling endpoint data transfers. Value in frame counts. Ignored for Bulk & Control Endpoints. Isochronous must equal 1 and field may range from 1 to 255 for interrupt endpoints.
     Interval,
+    /// Polling interval in real time like '125 µs' or '4 ms', decoded from the interval using the device speed; only with libusb profiling
+    IntervalTime,
+    /// Raw bmAttributes byte reconstructed from transfer, sync and usage type
+    Attributes,
+    /// Estimated bandwidth in bytes per ms from max packet size, transactions per interval and the [`EndpointBlocks::IntervalTime`]; Isochronous and Interrupt only with libusb profiling
+    BytesPerInterval,
+    /// Additional packets per burst from the SuperSpeed endpoint companion descriptor; '-' for endpoints of devices slower than SuperSpeed
+    MaxBurst,
+}
+
+/// Block selection argument; a plain block selects it, a leading '+' adds it to the defaults and a leading '-' removes it from the defaults
+///
+/// ```
+/// use cyme::display::{BlockArg, DeviceBlocks};
+///
+/// let args: Vec<BlockArg<DeviceBlocks>> = vec!["+sys-path".parse().unwrap(), "-serial".parse().unwrap()];
+/// assert_eq!(args, vec![BlockArg::Add(DeviceBlocks::SysPath), BlockArg::Remove(DeviceBlocks::Serial)]);
+/// assert_eq!(args[0].to_string(), "+sys-path");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlockArg<B> {
+    /// Use block, replacing the defaults
+    Select(B),
+    /// Add block to the defaults
+    Add(B),
+    /// Remove block from the defaults
+    Remove(B),
+}
+
+impl<B: ValueEnum> FromStr for BlockArg<B> {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (arg, name): (fn(B) -> BlockArg<B>, &str) = if let Some(n) = s.strip_prefix('+') {
+            (BlockArg::Add, n)
+        } else if let Some(n) = s.strip_prefix('-') {
+            (BlockArg::Remove, n)
+        } else {
+            (BlockArg::Select, s)
+        };
+
+        <B as ValueEnum>::from_str(name, true)
+            .map(arg)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+    }
+}
+
+impl<B: ValueEnum> fmt::Display for BlockArg<B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (prefix, b) = match self {
+            BlockArg::Select(b) => ("", b),
+            BlockArg::Add(b) => ("+", b),
+            BlockArg::Remove(b) => ("-", b),
+        };
+        match b.to_possible_value() {
+            Some(v) => write!(f, "{}{}", prefix, v.get_name()),
+            None => write!(f, "{}", prefix),
+        }
+    }
+}
+
+impl<B: ValueEnum> Serialize for BlockArg<B> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de, B: ValueEnum> Deserialize<'de> for BlockArg<B> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// clap parser for [`BlockArg`] so that block names are still listed as possible values
+#[derive(Debug, Clone)]
+pub struct BlockArgParser<B>(PhantomData<B>);
+
+impl<B: ValueEnum + Clone + Send + Sync + 'static> TypedValueParser for BlockArgParser<B> {
+    type Value = BlockArg<B>;
+
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        _arg: Option<&clap::Arg>,
+        value: &OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        let value = value.to_str().ok_or_else(|| {
+            clap::Error::raw(clap::error::ErrorKind::InvalidUtf8, "Invalid UTF-8 block\n")
+                .with_cmd(cmd)
+        })?;
+        value.parse().map_err(|e| {
+            clap::Error::raw(
+                clap::error::ErrorKind::InvalidValue,
+                format!("Invalid block '{}': {}\n", value, e),
+            )
+            .with_cmd(cmd)
+        })
+    }
+
+    fn possible_values(&self) -> Option<Box<dyn Iterator<Item = PossibleValue> + '_>> {
+        Some(Box::new(
+            B::value_variants()
+                .iter()
+                .filter_map(|v| v.to_possible_value()),
+        ))
+    }
+}
+
+impl<B: ValueEnum + Clone + Send + Sync + 'static> ValueParserFactory for BlockArg<B> {
+    type Parser = BlockArgParser<B>;
+
+    fn value_parser() -> Self::Parser {
+        BlockArgParser(PhantomData)
+    }
+}
+
+/// Resolves `args` to a list of blocks: any [`BlockArg::Select`] replace `defaults`, then [`BlockArg::Add`] are appended if not present and [`BlockArg::Remove`] are removed
+///
+/// ```
+/// use cyme::display::{resolve_blocks, BlockArg, DeviceBlocks};
+///
+/// let defaults = vec![DeviceBlocks::Name, DeviceBlocks::Serial];
+/// let blocks = resolve_blocks(&vec![BlockArg::Add(DeviceBlocks::SysPath), BlockArg::Remove(DeviceBlocks::Serial)], defaults.clone());
+/// assert_eq!(blocks, vec![DeviceBlocks::Name, DeviceBlocks::SysPath]);
+/// let blocks = resolve_blocks(&vec![BlockArg::Select(DeviceBlocks::VendorId), BlockArg::Add(DeviceBlocks::Name)], defaults);
+/// assert_eq!(blocks, vec![DeviceBlocks::VendorId, DeviceBlocks::Name]);
+/// ```
+pub fn resolve_blocks<B: Clone + PartialEq>(args: &Vec<BlockArg<B>>, defaults: Vec<B>) -> Vec<B> {
+    let selected: Vec<B> = args
+        .iter()
+        .filter_map(|a| match a {
+            BlockArg::Select(b) => Some(b.to_owned()),
+            _ => None,
+        })
+        .collect();
+    let mut blocks = if selected.is_empty() {
+        defaults
+    } else {
+        selected
+    };
+
+    for a in args {
+        match a {
+            BlockArg::Add(b) => {
+                if !blocks.contains(b) {
+                    blocks.push(b.to_owned());
+                }
+            }
+            BlockArg::Remove(b) => blocks.retain(|v| v != b),
+            BlockArg::Select(_) => (),
+        }
+    }
+
+    blocks
+}
+
+/// Typed value of a block from [`Block::raw_value`] for programmatic use, rather than the display String of [`Block::format_value`]
+///
+/// Serializes numbers as json numbers and [`BlockValue::None`] as null. Display is the unpadded value with '-' for None.
+///
+/// ```
+/// use cyme::display::BlockValue;
+///
+/// assert_eq!(BlockValue::from(Some(0x1d50u16)), BlockValue::U16(0x1d50));
+/// assert_eq!(BlockValue::from(None::<u16>), BlockValue::None);
+/// assert_eq!(serde_json::to_string(&BlockValue::U16(0x1d50)).unwrap(), "7504");
+/// assert_eq!(BlockValue::None.to_string(), "-");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum BlockValue {
+    /// Flag like a configuration attribute
+    Bool(bool),
+    /// Byte value like a device number or class sub-code
+    U8(u8),
+    /// Word value like a VID or current in mA
+    U16(u16),
+    /// Value like max power in mA or an interval in µs
+    U32(u32),
+    /// Count or timestamp
+    U64(u64),
+    /// String value like a name or path
+    Str(String),
+    /// Device speed
+    Speed(system_profiler::DeviceSpeed),
+    /// BCD version like the USB version
+    Version(Version),
+    /// USB class code
+    ClassCode(ClassCode),
+    /// Several values of one block like the negotiated and maximum speed
+    List(Vec<BlockValue>),
+    /// Value not known for the item or block is only presentational like an icon
+    None,
+}
+
+impl fmt::Display for BlockValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlockValue::Bool(v) => write!(f, "{}", v),
+            BlockValue::U8(v) => write!(f, "{}", v),
+            BlockValue::U16(v) => write!(f, "{}", v),
+            BlockValue::U32(v) => write!(f, "{}", v),
+            BlockValue::U64(v) => write!(f, "{}", v),
+            BlockValue::Str(v) => write!(f, "{}", v),
+            BlockValue::Speed(v) => write!(f, "{}", v),
+            BlockValue::Version(v) => write!(f, "{}", v),
+            BlockValue::ClassCode(v) => write!(f, "{}", v),
+            BlockValue::List(v) => write!(f, "{}", v.iter().format("/")),
+            BlockValue::None => write!(f, "-"),
+        }
+    }
+}
+
+impl Serialize for BlockValue {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            BlockValue::Bool(v) => serializer.serialize_bool(*v),
+            BlockValue::U8(v) => serializer.serialize_u8(*v),
+            BlockValue::U16(v) => serializer.serialize_u16(*v),
+            BlockValue::U32(v) => serializer.serialize_u32(*v),
+            BlockValue::U64(v) => serializer.serialize_u64(*v),
+            BlockValue::Str(v) => serializer.serialize_str(v),
+            BlockValue::Speed(v) => v.serialize(serializer),
+            BlockValue::Version(v) => serializer.serialize_str(&v.to_string()),
+            BlockValue::ClassCode(v) => v.serialize(serializer),
+            BlockValue::List(v) => serializer.collect_seq(v),
+            BlockValue::None => serializer.serialize_none(),
+        }
+    }
+}
+
+impl From<bool> for BlockValue {
+    fn from(v: bool) -> Self {
+        BlockValue::Bool(v)
+    }
+}
+
+impl From<u8> for BlockValue {
+    fn from(v: u8) -> Self {
+        BlockValue::U8(v)
+    }
+}
+
+impl From<u16> for BlockValue {
+    fn from(v: u16) -> Self {
+        BlockValue::U16(v)
+    }
+}
+
+impl From<u32> for BlockValue {
+    fn from(v: u32) -> Self {
+        BlockValue::U32(v)
+    }
+}
+
+impl From<u64> for BlockValue {
+    fn from(v: u64) -> Self {
+        BlockValue::U64(v)
+    }
+}
+
+impl From<usize> for BlockValue {
+    fn from(v: usize) -> Self {
+        BlockValue::U64(v as u64)
+    }
+}
+
+impl From<String> for BlockValue {
+    fn from(v: String) -> Self {
+        BlockValue::Str(v)
+    }
+}
+
+impl From<system_profiler::DeviceSpeed> for BlockValue {
+    fn from(v: system_profiler::DeviceSpeed) -> Self {
+        BlockValue::Speed(v)
+    }
+}
+
+impl From<Version> for BlockValue {
+    fn from(v: Version) -> Self {
+        BlockValue::Version(v)
+    }
+}
+
+impl From<ClassCode> for BlockValue {
+    fn from(v: ClassCode) -> Self {
+        BlockValue::ClassCode(v)
+    }
+}
+
+impl<T: Into<BlockValue>> From<Option<T>> for BlockValue {
+    fn from(v: Option<T>) -> Self {
+        v.map_or(BlockValue::None, |v| v.into())
+    }
 }
 
 /// Intended to be `impl` by a xxxBlocks `enum`
@@ -172,11 +536,45 @@ pub trait Block<B, T> {
     where
         Self: Sized;
 
+    /// List of default blocks to use for printing T with `settings`; [`Block::default_blocks`] verbose if at maximum verbosity or more
+    fn default_blocks_for(settings: &PrintSettings) -> Vec<Self>
+    where
+        Self: Sized,
+    {
+        Self::default_blocks(settings.verbosity >= MAX_VERBOSITY || settings.more)
+    }
+
     /// Creates a HashMap of B keys to usize of longest value for that key in the `d` Vec; values can then be padded to match this
     fn generate_padding(d: &Vec<&T>) -> HashMap<B, usize>;
 
-    /// Colour the block String
-    fn colour(&self, s: &String, ct: &colour::ColourTheme) -> ColoredString;
+    /// Theme colour of the block String `s`; None if the theme does not colour it
+    fn theme_colour(&self, s: &String, ct: &colour::ColourTheme) -> Option<Color>;
+
+    /// Colour the block String with [`Block::theme_colour`]
+    fn colour(&self, s: &String, ct: &colour::ColourTheme) -> ColoredString {
+        self.theme_colour(s, ct).map_or(s.normal(), |c| s.color(c))
+    }
+
+    /// Theme colour of the block String `s` with the context of the item `d` it was formatted from; defaults to [`Block::theme_colour`]
+    fn value_colour(&self, _d: &T, s: &String, ct: &colour::ColourTheme) -> Option<Color> {
+        self.theme_colour(s, ct)
+    }
+
+    /// Colour the block String `s` with the context of the item `d` it was formatted from with [`Block::value_colour`]
+    fn colour_value(&self, d: &T, s: &String, ct: &colour::ColourTheme) -> ColoredString {
+        self.value_colour(d, s, ct)
+            .map_or(s.normal(), |c| s.color(c))
+    }
+
+    /// Depth of the first segment if the block is a path like '2-1.4' coloured a segment per depth with [`PrintSettings::rainbow_path`]; 0 if the first segment is the bus number. None if not such a path
+    fn path_depth_offset(&self) -> Option<usize> {
+        None
+    }
+
+    /// Colour of the block derived from the value in the item `d` itself, such as a gradient by speed, used in place of the theme colour with [`PrintSettings::semantic_colours`]; None to use the theme colour
+    fn semantic_colour(&self, _d: &T) -> Option<Color> {
+        None
+    }
 
     /// Creates the heading for the block value, for use with the heading flag
     fn heading(&self, pad: &HashMap<B, usize>) -> String;
@@ -184,7 +582,23 @@ pub trait Block<B, T> {
     /// Returns whether the value intended for the block is a String type
     fn value_is_string(&self) -> bool;
 
-    /// Formats the value associated with the block into a display String
+    /// [`Alignment`] override for the block from `settings`; None keeps [`Block::default_alignment`]
+    fn alignment(&self, _settings: &PrintSettings) -> Option<Alignment> {
+        None
+    }
+
+    /// [`Alignment`] of the value in `d` within [`Block::width`] when not overridden by [`Block::alignment`]
+    fn default_alignment(&self, d: &T) -> Alignment;
+
+    /// Width [`render_value`] pads the value of the block to: the `pad` width for blocks padded to the widest value, otherwise the fixed width of the block; 0 to not pad
+    fn width(&self, pad: &HashMap<B, usize>, settings: &PrintSettings) -> usize;
+
+    /// Maximum display width for the block from `settings`; values wider are truncated with an ellipsis. None for no truncation
+    fn max_width(&self, _settings: &PrintSettings) -> Option<usize> {
+        None
+    }
+
+    /// Formats the value associated with the block into an unpadded display String; [`render_value`] pads and aligns it
     fn format_value(
         &self,
         d: &T,
@@ -192,26 +606,52 @@ pub trait Block<B, T> {
         settings: &PrintSettings,
     ) -> Option<String>;
 
-    /// Formats u16 values like VID as base16 or base10 depending on decimal setting
-    fn format_base_u16(v: u16, settings: &PrintSettings) -> String {
-        if settings.decimal {
-            format!("{:6}", v)
+    /// Typed value associated with the block in `d`, see [`BlockValue`]; numbers are not padded or formatted in base16 and [`BlockValue::None`] is used where [`Block::format_value`] would show '-'. `settings` picks the value shown like [`PrintSettings::prefer_usb_ids`] but not its format
+    fn raw_value(&self, d: &T, settings: &PrintSettings) -> BlockValue;
+
+    /// [`NumberCategory`] of the numeric block value, which [`PrintSettings::number_format`] uses to pick base16 or base10
+    fn number_category(&self) -> NumberCategory {
+        NumberCategory::Descriptor
+    }
+
+    /// Formats u16 values like VID as base16 or base10 depending on the number format of [`Block::number_category`]
+    fn format_base_u16(&self, v: u16, settings: &PrintSettings) -> String {
+        if settings.number_format.is_decimal(self.number_category()) {
+            v.to_string()
         } else {
             format!("0x{:04x}", v)
         }
     }
 
-    /// Formats u8 values like codes as base16 or base10 depending on decimal setting
-    fn format_base_u8(v: u8, settings: &PrintSettings) -> String {
-        if settings.decimal {
-            format!("{:3}", v)
+    /// Formats u8 values like codes as base16 or base10 depending on the number format of [`Block::number_category`]
+    fn format_base_u8(&self, v: u8, settings: &PrintSettings) -> String {
+        if settings.number_format.is_decimal(self.number_category()) {
+            v.to_string()
         } else {
             format!("0x{:02x}", v)
         }
     }
+
+    /// Width of [`Block::format_base_u8`] values; '255' as base10 or '0xff' as base16
+    fn base_u8_width(&self, settings: &PrintSettings) -> usize {
+        if settings.number_format.is_decimal(self.number_category()) {
+            3
+        } else {
+            4
+        }
+    }
 }
 
 impl DeviceBlocks {
+    /// Minimal fixed `DeviceBlocks` used in place of any others when printing with `PrintSettings.compact`
+    pub fn compact_blocks() -> Vec<DeviceBlocks> {
+        vec![
+            DeviceBlocks::PortPath,
+            DeviceBlocks::VidPid,
+            DeviceBlocks::Name,
+        ]
+    }
+
     /// Default `DeviceBlocks` for tree printing are different to list, get them here
     pub fn default_device_tree_blocks() -> Vec<DeviceBlocks> {
         vec![
@@ -223,9 +663,44 @@ impl DeviceBlocks {
             DeviceBlocks::Serial,
         ]
     }
+
+    /// `DeviceBlocks` in order of importance when dropping blocks to fit [`PrintSettings::auto_width`]; blocks are dropped from the tail first and blocks not listed are dropped before any listed
+    pub fn width_priority() -> Vec<DeviceBlocks> {
+        vec![
+            DeviceBlocks::Name,
+            DeviceBlocks::VidPid,
+            DeviceBlocks::VendorId,
+            DeviceBlocks::ProductId,
+            DeviceBlocks::BusNumber,
+            DeviceBlocks::DeviceNumber,
+            DeviceBlocks::PortPath,
+            DeviceBlocks::Serial,
+            DeviceBlocks::Speed,
+            DeviceBlocks::Icon,
+            DeviceBlocks::Manufacturer,
+            DeviceBlocks::Driver,
+            DeviceBlocks::TreePositions,
+            DeviceBlocks::BcdUsb,
+            DeviceBlocks::BcdDevice,
+            DeviceBlocks::ClassCode,
+            DeviceBlocks::SubClass,
+            DeviceBlocks::Protocol,
+        ]
+    }
 }
 
 impl Block<DeviceBlocks, USBDevice> for DeviceBlocks {
+    /// Tree blocks are used for a tree unless verbose
+    fn default_blocks_for(settings: &PrintSettings) -> Vec<DeviceBlocks> {
+        if settings.verbosity >= MAX_VERBOSITY || settings.more {
+            DeviceBlocks::default_blocks(true)
+        } else if settings.tree {
+            DeviceBlocks::default_device_tree_blocks()
+        } else {
+            DeviceBlocks::default_blocks(false)
+        }
+    }
+
     fn default_blocks(verbose: bool) -> Vec<DeviceBlocks> {
         if verbose {
             vec![
@@ -263,19 +738,37 @@ impl Block<DeviceBlocks, USBDevice> for DeviceBlocks {
 
     fn generate_padding(d: &Vec<&system_profiler::USBDevice>) -> HashMap<Self, usize> {
         HashMap::from([
+            (
+                DeviceBlocks::BusPower,
+                current_padding(d.iter().map(|d| d.bus_power.map(|v| format!("{} mA", v)))),
+            ),
+            (
+                DeviceBlocks::BusPowerUsed,
+                current_padding(
+                    d.iter()
+                        .map(|d| d.bus_power_used.map(|v| format!("{} mA", v))),
+                ),
+            ),
+            (
+                DeviceBlocks::ExtraCurrentUsed,
+                current_padding(
+                    d.iter()
+                        .map(|d| d.extra_current_used.map(|v| format!("{} mA", v))),
+                ),
+            ),
             (
                 DeviceBlocks::Name,
                 cmp::max(
-                    DeviceBlocks::Name.heading(&Default::default()).len(),
-                    d.iter().map(|d| d.name.len()).max().unwrap_or(0),
+                    DeviceBlocks::Name.heading(&Default::default()).width(),
+                    d.iter().map(|d| d.name.width()).max().unwrap_or(0),
                 ),
             ),
             (
                 DeviceBlocks::Serial,
                 cmp::max(
-                    DeviceBlocks::Serial.heading(&Default::default()).len(),
+                    DeviceBlocks::Serial.heading(&Default::default()).width(),
                     d.iter()
-                        .map(|d| d.serial_num.as_ref().unwrap_or(&String::new()).len())
+                        .map(|d| d.serial_num.as_ref().unwrap_or(&String::new()).width())
                         .max()
                         .unwrap_or(0),
                 ),
@@ -285,9 +778,19 @@ impl Block<DeviceBlocks, USBDevice> for DeviceBlocks {
                 cmp::max(
                     DeviceBlocks::Manufacturer
                         .heading(&Default::default())
-                        .len(),
+                        .width(),
                     d.iter()
-                        .map(|d| d.manufacturer.as_ref().unwrap_or(&String::new()).len())
+                        .map(|d| d.manufacturer.as_ref().unwrap_or(&String::new()).width())
+                        .max()
+                        .unwrap_or(0),
+                ),
+            ),
+            (
+                DeviceBlocks::Vendor,
+                cmp::max(
+                    DeviceBlocks::Vendor.heading(&Default::default()).width(),
+                    d.iter()
+                        .map(|d| preferred_vendor(d, false).map_or(0, |v| v.width()))
                         .max()
                         .unwrap_or(0),
                 ),
@@ -297,7 +800,7 @@ impl Block<DeviceBlocks, USBDevice> for DeviceBlocks {
                 cmp::max(
                     DeviceBlocks::TreePositions
                         .heading(&Default::default())
-                        .len(),
+                        .width(),
                     d.iter()
                         .map(|d| d.location_id.tree_positions.len() * 2)
                         .max()
@@ -307,19 +810,29 @@ impl Block<DeviceBlocks, USBDevice> for DeviceBlocks {
             (
                 DeviceBlocks::PortPath,
                 cmp::max(
-                    DeviceBlocks::PortPath.heading(&Default::default()).len(),
-                    d.iter().map(|d| d.port_path().len()).max().unwrap_or(0),
+                    DeviceBlocks::PortPath.heading(&Default::default()).width(),
+                    d.iter().map(|d| d.port_path().width()).max().unwrap_or(0),
+                ),
+            ),
+            (
+                DeviceBlocks::FullPath,
+                cmp::max(
+                    DeviceBlocks::FullPath.heading(&Default::default()).width(),
+                    d.iter()
+                        .map(|d| d.location_id.port_path().width())
+                        .max()
+                        .unwrap_or(0),
                 ),
             ),
             (
                 DeviceBlocks::SysPath,
                 cmp::max(
-                    DeviceBlocks::SysPath.heading(&Default::default()).len(),
+                    DeviceBlocks::SysPath.heading(&Default::default()).width(),
                     d.iter()
                         .map(|d| {
                             d.extra
                                 .as_ref()
-                                .map_or(0, |e| e.syspath.as_ref().unwrap_or(&String::new()).len())
+                                .map_or(0, |e| e.syspath.as_ref().unwrap_or(&String::new()).width())
                         })
                         .max()
                         .unwrap_or(0),
@@ -328,25 +841,37 @@ impl Block<DeviceBlocks, USBDevice> for DeviceBlocks {
             (
                 DeviceBlocks::Driver,
                 cmp::max(
-                    DeviceBlocks::Driver.heading(&Default::default()).len(),
+                    DeviceBlocks::Driver.heading(&Default::default()).width(),
                     d.iter()
                         .map(|d| {
                             d.extra
                                 .as_ref()
-                                .map_or(0, |e| e.driver.as_ref().unwrap_or(&String::new()).len())
+                                .map_or(0, |e| e.driver.as_ref().unwrap_or(&String::new()).width())
                         })
                         .max()
                         .unwrap_or(0),
                 ),
             ),
+            (
+                DeviceBlocks::Tags,
+                cmp::max(
+                    DeviceBlocks::Tags.heading(&Default::default()).width(),
+                    d.iter()
+                        .map(|d| tags_string(d).map_or(0, |t| t.width()))
+                        .max()
+                        .unwrap_or(0),
+                ),
+            ),
             (
                 DeviceBlocks::ProductName,
                 cmp::max(
-                    DeviceBlocks::ProductName.heading(&Default::default()).len(),
+                    DeviceBlocks::ProductName
+                        .heading(&Default::default())
+                        .width(),
                     d.iter()
                         .map(|d| {
                             d.extra.as_ref().map_or(0, |e| {
-                                e.product_name.as_ref().unwrap_or(&String::new()).len()
+                                e.product_name.as_ref().unwrap_or(&String::new()).width()
                             })
                         })
                         .max()
@@ -356,12 +881,14 @@ impl Block<DeviceBlocks, USBDevice> for DeviceBlocks {
             (
                 DeviceBlocks::VendorName,
                 cmp::max(
-                    DeviceBlocks::VendorName.heading(&Default::default()).len(),
+                    DeviceBlocks::VendorName
+                        .heading(&Default::default())
+                        .width(),
                     d.iter()
                         .map(|d| {
                             d.extra
                                 .as_ref()
-                                .map_or(0, |e| e.vendor.as_ref().unwrap_or(&String::new()).len())
+                                .map_or(0, |e| e.vendor.as_ref().unwrap_or(&String::new()).width())
                         })
                         .max()
                         .unwrap_or(0),
@@ -370,13 +897,13 @@ impl Block<DeviceBlocks, USBDevice> for DeviceBlocks {
             (
                 DeviceBlocks::ClassCode,
                 cmp::max(
-                    DeviceBlocks::ClassCode.heading(&Default::default()).len(),
+                    DeviceBlocks::ClassCode.heading(&Default::default()).width(),
                     d.iter()
                         .map(|d| {
                             d.class
                                 .as_ref()
                                 .map_or(String::new(), |c| c.to_string())
-                                .len()
+                                .width()
                         })
                         .max()
                         .unwrap_or(0),
@@ -385,179 +912,375 @@ impl Block<DeviceBlocks, USBDevice> for DeviceBlocks {
         ])
     }
 
+    fn alignment(&self, settings: &PrintSettings) -> Option<Alignment> {
+        settings
+            .device_alignment
+            .as_ref()
+            .and_then(|a| a.get(self))
+            .copied()
+    }
+
+    fn number_category(&self) -> NumberCategory {
+        match self {
+            DeviceBlocks::VendorId | DeviceBlocks::ProductId => NumberCategory::Ids,
+            DeviceBlocks::SubClass | DeviceBlocks::Protocol => NumberCategory::Codes,
+            _ => NumberCategory::Descriptor,
+        }
+    }
+
+    fn max_width(&self, settings: &PrintSettings) -> Option<usize> {
+        settings
+            .max_widths
+            .as_ref()
+            .and_then(|m| m.get(self))
+            .copied()
+    }
+
+    fn value_colour(&self, d: &USBDevice, s: &String, ct: &colour::ColourTheme) -> Option<Color> {
+        match self {
+            DeviceBlocks::Serial if d.serial_masked => ct.masked_serial.or(ct.serial),
+            _ => self.theme_colour(s, ct),
+        }
+    }
+
+    fn path_depth_offset(&self) -> Option<usize> {
+        match self {
+            DeviceBlocks::PortPath | DeviceBlocks::FullPath => Some(0),
+            DeviceBlocks::TreePositions => Some(1),
+            _ => None,
+        }
+    }
+
+    fn semantic_colour(&self, d: &USBDevice) -> Option<Color> {
+        match self {
+            DeviceBlocks::Speed => match d.device_speed.as_ref() {
+                Some(system_profiler::DeviceSpeed::SpeedValue(v)) => colour::speed_gradient(v),
+                _ => None,
+            },
+            DeviceBlocks::BusPowerUsed => {
+                d.bus_power_used.map(|v| colour::power_gradient(v as u32))
+            }
+            DeviceBlocks::ExtraCurrentUsed => d
+                .extra_current_used
+                .map(|v| colour::power_gradient(v as u32)),
+            _ => None,
+        }
+    }
+
     fn value_is_string(&self) -> bool {
         match self {
             DeviceBlocks::Name
             | DeviceBlocks::Serial
             | DeviceBlocks::PortPath
-            | DeviceBlocks::Manufacturer => true,
+            | DeviceBlocks::FullPath
+            | DeviceBlocks::Manufacturer
+            | DeviceBlocks::Vendor => true,
             _ => false,
         }
     }
 
+    fn default_alignment(&self, d: &USBDevice) -> Alignment {
+        match self {
+            DeviceBlocks::BusNumber
+            | DeviceBlocks::DeviceNumber
+            | DeviceBlocks::BranchPosition
+            | DeviceBlocks::PortNumber
+            | DeviceBlocks::VendorId
+            | DeviceBlocks::ProductId
+            | DeviceBlocks::NumConfigurations
+            | DeviceBlocks::Speed
+            | DeviceBlocks::SpeedVsMax
+            | DeviceBlocks::BusPower
+            | DeviceBlocks::BusPowerUsed
+            | DeviceBlocks::ExtraCurrentUsed
+            | DeviceBlocks::SubClass
+            | DeviceBlocks::Protocol
+            | DeviceBlocks::ConnectedSince => Alignment::Right,
+            // versions are left aligned but '-' to the right
+            DeviceBlocks::BcdDevice if d.bcd_device.is_none() => Alignment::Right,
+            DeviceBlocks::BcdUsb if d.bcd_usb.is_none() => Alignment::Right,
+            _ => Alignment::Left,
+        }
+    }
+
+    fn width(&self, pad: &HashMap<Self, usize>, settings: &PrintSettings) -> usize {
+        match self {
+            DeviceBlocks::BusNumber
+            | DeviceBlocks::DeviceNumber
+            | DeviceBlocks::BranchPosition
+            | DeviceBlocks::PortNumber => 3,
+            DeviceBlocks::VendorId | DeviceBlocks::ProductId => 6,
+            DeviceBlocks::NumConfigurations | DeviceBlocks::NameSource => 4,
+            DeviceBlocks::BcdDevice | DeviceBlocks::BcdUsb => 5,
+            DeviceBlocks::Speed | DeviceBlocks::PowerState => 10,
+            DeviceBlocks::SpeedVsMax => 11,
+            DeviceBlocks::UsbVersionCategory => 15,
+            DeviceBlocks::ConnectedSince => 8,
+            DeviceBlocks::SubClass | DeviceBlocks::Protocol => self.base_u8_width(settings),
+            DeviceBlocks::BusPower
+            | DeviceBlocks::BusPowerUsed
+            | DeviceBlocks::ExtraCurrentUsed => {
+                cmp::max(*pad.get(self).unwrap_or(&0), CURRENT_WIDTH)
+            }
+            DeviceBlocks::Icon | DeviceBlocks::VidPid => 0,
+            _ => *pad.get(self).unwrap_or(&0),
+        }
+    }
+
     fn format_value(
         &self,
         d: &USBDevice,
-        pad: &HashMap<Self, usize>,
+        _pad: &HashMap<Self, usize>,
         settings: &PrintSettings,
     ) -> Option<String> {
         match self {
-            DeviceBlocks::BusNumber => Some(format!("{:3}", d.location_id.bus)),
-            DeviceBlocks::DeviceNumber => Some(format!("{:3}", d.location_id.number)),
-            DeviceBlocks::BranchPosition => Some(format!("{:3}", d.get_branch_position())),
-            DeviceBlocks::PortPath => Some(format!(
-                "{:pad$}",
-                d.port_path(),
-                pad = pad.get(self).unwrap_or(&0)
-            )),
-            DeviceBlocks::SysPath => Some(match d.extra.as_ref() {
-                Some(e) => format!(
-                    "{:pad$}",
-                    e.syspath.as_ref().unwrap_or(&format!(
-                        "{:pad$}",
-                        "-",
-                        pad = pad.get(self).unwrap_or(&0)
-                    )),
-                    pad = pad.get(self).unwrap_or(&0)
-                ),
-                None => format!("{:pad$}", "-", pad = pad.get(self).unwrap_or(&0)),
-            }),
-            DeviceBlocks::Driver => Some(match d.extra.as_ref() {
-                Some(e) => format!(
-                    "{:pad$}",
-                    e.driver.as_ref().unwrap_or(&format!(
-                        "{:pad$}",
-                        "-",
-                        pad = pad.get(self).unwrap_or(&0)
-                    )),
-                    pad = pad.get(self).unwrap_or(&0)
-                ),
-                None => format!("{:pad$}", "-", pad = pad.get(self).unwrap_or(&0)),
-            }),
-            DeviceBlocks::ProductName => Some(match d.extra.as_ref() {
-                Some(e) => format!(
-                    "{:pad$}",
-                    e.product_name.as_ref().unwrap_or(&format!(
-                        "{:pad$}",
-                        "-",
-                        pad = pad.get(self).unwrap_or(&0)
-                    )),
-                    pad = pad.get(self).unwrap_or(&0)
-                ),
-                None => format!("{:pad$}", "-", pad = pad.get(self).unwrap_or(&0)),
-            }),
-            DeviceBlocks::VendorName => Some(match d.extra.as_ref() {
-                Some(e) => format!(
-                    "{:pad$}",
-                    e.vendor.as_ref().unwrap_or(&format!(
-                        "{:pad$}",
-                        "-",
-                        pad = pad.get(self).unwrap_or(&0)
-                    )),
-                    pad = pad.get(self).unwrap_or(&0)
-                ),
-                None => format!("{:pad$}", "-", pad = pad.get(self).unwrap_or(&0)),
-            }),
+            DeviceBlocks::BusNumber => Some(d.location_id.bus.to_string()),
+            DeviceBlocks::DeviceNumber => Some(d.location_id.number.to_string()),
+            DeviceBlocks::BranchPosition => Some(d.get_branch_position().to_string()),
+            DeviceBlocks::PortNumber => Some(d.get_port_number().to_string()),
+            DeviceBlocks::PortPath => Some(d.port_path()),
+            DeviceBlocks::FullPath => Some(d.location_id.port_path()),
+            DeviceBlocks::SysPath => Some(
+                d.extra
+                    .as_ref()
+                    .and_then(|e| e.syspath.to_owned())
+                    .unwrap_or(String::from("-")),
+            ),
+            DeviceBlocks::Driver => Some(
+                d.extra
+                    .as_ref()
+                    .and_then(|e| e.driver.to_owned())
+                    .unwrap_or(String::from("-")),
+            ),
+            DeviceBlocks::ProductName => Some(
+                d.extra
+                    .as_ref()
+                    .and_then(|e| e.product_name.to_owned())
+                    .unwrap_or(String::from("-")),
+            ),
+            DeviceBlocks::VendorName => Some(
+                d.extra
+                    .as_ref()
+                    .and_then(|e| e.vendor.to_owned())
+                    .unwrap_or(String::from("-")),
+            ),
             DeviceBlocks::Icon => settings
                 .icons
                 .as_ref()
                 .map_or(None, |i| Some(i.get_device_icon(d))),
             DeviceBlocks::VendorId => Some(match d.vendor_id {
-                Some(v) => Self::format_base_u16(v, settings),
-                None => format!("{:>6}", "-"),
+                Some(v) => self.format_base_u16(v, settings),
+                None => String::from("-"),
             }),
             DeviceBlocks::ProductId => Some(match d.product_id {
-                Some(v) => Self::format_base_u16(v, settings),
-                None => format!("{:>6}", "-"),
+                Some(v) => self.format_base_u16(v, settings),
+                None => String::from("-"),
             }),
-            DeviceBlocks::Name => Some(format!(
-                "{:pad$}",
-                d.name,
-                pad = pad.get(self).unwrap_or(&0)
-            )),
-            DeviceBlocks::Manufacturer => Some(match d.manufacturer.as_ref() {
-                Some(v) => format!("{:pad$}", v, pad = pad.get(self).unwrap_or(&0)),
-                None => format!("{:pad$}", "-", pad = pad.get(self).unwrap_or(&0)),
+            DeviceBlocks::NumConfigurations => Some(match d.extra.as_ref() {
+                Some(e) => e.configurations.len().to_string(),
+                None => String::from("-"),
             }),
+            DeviceBlocks::VidPid => Some(format!(
+                "{}:{}",
+                d.vendor_id
+                    .map_or(String::from("----"), |v| format!("{:04x}", v)),
+                d.product_id
+                    .map_or(String::from("----"), |v| format!("{:04x}", v))
+            )),
+            DeviceBlocks::Name => Some(preferred_name(d, settings.prefer_usb_ids)),
+            DeviceBlocks::Manufacturer => Some(
+                preferred_manufacturer(d, settings.prefer_usb_ids).unwrap_or(String::from("-")),
+            ),
+            DeviceBlocks::Vendor => {
+                Some(preferred_vendor(d, settings.prefer_usb_ids).unwrap_or(String::from("-")))
+            }
             DeviceBlocks::Serial => Some(match d.serial_num.as_ref() {
-                Some(v) => format!("{:pad$}", v, pad = pad.get(self).unwrap_or(&0)),
-                None => format!("{:pad$}", "-", pad = pad.get(self).unwrap_or(&0)),
+                Some(v) => v.to_owned(),
+                None => String::from("-"),
             }),
             DeviceBlocks::Speed => Some(match d.device_speed.as_ref() {
-                Some(v) => format!("{:>10}", v.to_string()),
-                None => format!("{:>10}", "-"),
+                Some(v) => v.to_string(),
+                None => String::from("-"),
             }),
-            DeviceBlocks::TreePositions => Some(format!(
-                "{:pad$}",
-                format!("{:}", d.location_id.tree_positions.iter().format("-")),
-                pad = pad.get(self).unwrap_or(&0)
+            DeviceBlocks::SpeedVsMax => {
+                let negotiated = d.device_speed.as_ref().and_then(|s| match s {
+                    system_profiler::DeviceSpeed::SpeedValue(Speed::Unknown) => None,
+                    system_profiler::DeviceSpeed::SpeedValue(v) => Some(v),
+                    system_profiler::DeviceSpeed::Description(_) => None,
+                });
+                let max = d.bcd_usb.as_ref().and_then(Speed::max_for_version);
+                Some(match (negotiated, max) {
+                    (Some(n), Some(m)) => {
+                        format!("{}/{}", n.to_short_string(), m.to_short_string())
+                    }
+                    (Some(n), None) => n.to_short_string(),
+                    (None, _) => String::from("-"),
+                })
+            }
+            DeviceBlocks::TreePositions => Some(format_tree_positions(
+                &d.location_id.tree_positions,
+                settings.pad_positions,
             )),
-            DeviceBlocks::BusPower => Some(match d.bus_power {
-                Some(v) => format!("{:3} mA", v),
-                None => format!("{:>6}", "-"),
-            }),
-            DeviceBlocks::BusPowerUsed => Some(match d.bus_power_used {
-                Some(v) => format!("{:3} mA", v),
-                None => format!("{:>6}", "-"),
-            }),
-            DeviceBlocks::ExtraCurrentUsed => Some(match d.extra_current_used {
-                Some(v) => format!("{:3} mA", v),
-                None => format!("{:>6}", "-"),
-            }),
+            DeviceBlocks::BusPower => Some(format_current(d.bus_power.map(u32::from))),
+            DeviceBlocks::BusPowerUsed => Some(format_current(d.bus_power_used.map(u32::from))),
+            DeviceBlocks::ExtraCurrentUsed => {
+                Some(format_current(d.extra_current_used.map(u32::from)))
+            }
             DeviceBlocks::BcdDevice => Some(match d.bcd_device {
-                Some(v) => format!("{:5}", v.to_string()),
-                None => format!("{:>5}", "-"),
+                Some(v) => v.to_string(),
+                None => String::from("-"),
             }),
             DeviceBlocks::BcdUsb => Some(match d.bcd_usb {
-                Some(v) => format!("{:5}", v.to_string()),
-                None => format!("{:>5}", "-"),
+                Some(v) => v.to_string(),
+                None => String::from("-"),
+            }),
+            DeviceBlocks::UsbVersionCategory => Some(match d.bcd_usb {
+                Some(v) => v.usb_category().map_or(v.to_string(), String::from),
+                None => String::from("-"),
             }),
             DeviceBlocks::ClassCode => Some(match d.class.as_ref() {
-                Some(v) => format!("{:pad$}", v.to_string(), pad = pad.get(self).unwrap_or(&0)),
-                None => format!("{:pad$}", "-", pad = pad.get(self).unwrap_or(&0)),
+                Some(v) => v.to_string(),
+                None => String::from("-"),
             }),
             DeviceBlocks::SubClass => Some(match d.sub_class.as_ref() {
-                Some(v) => Self::format_base_u8(*v, settings),
-                None => format!("{:>4}", "-"),
+                Some(v) => self.format_base_u8(*v, settings),
+                None => String::from("-"),
             }),
             DeviceBlocks::Protocol => Some(match d.protocol.as_ref() {
-                Some(v) => Self::format_base_u8(*v, settings),
-                None => format!("{:>4}", "-"),
+                Some(v) => self.format_base_u8(*v, settings),
+                None => String::from("-"),
             }),
-            // _ => None,
+            DeviceBlocks::ConnectedSince => Some(
+                d.extra
+                    .as_ref()
+                    .and_then(|e| e.connected_since)
+                    .map_or(String::from("-"), format_age),
+            ),
+            DeviceBlocks::PowerState => Some(
+                d.extra
+                    .as_ref()
+                    .and_then(|e| e.power_state.to_owned())
+                    .unwrap_or(String::from("-")),
+            ),
+            DeviceBlocks::Tags => Some(tags_string(d).unwrap_or_else(|| String::from("-"))),
+            DeviceBlocks::NameSource => Some(
+                shown_name_source(d, settings.prefer_usb_ids)
+                    .map_or(String::from("-"), |s| s.to_string()),
+            ),
         }
     }
 
-    fn colour(&self, s: &String, ct: &colour::ColourTheme) -> ColoredString {
+    fn raw_value(&self, d: &USBDevice, settings: &PrintSettings) -> BlockValue {
         match self {
-            DeviceBlocks::BcdUsb | DeviceBlocks::BcdDevice | DeviceBlocks::DeviceNumber => {
-                ct.number.map_or(s.normal(), |c| s.color(c))
+            DeviceBlocks::BusNumber => d.location_id.bus.into(),
+            DeviceBlocks::DeviceNumber => d.location_id.number.into(),
+            DeviceBlocks::BranchPosition => d.get_branch_position().into(),
+            DeviceBlocks::PortNumber => d.get_port_number().into(),
+            DeviceBlocks::PortPath => d.port_path().into(),
+            DeviceBlocks::FullPath => d.location_id.port_path().into(),
+            DeviceBlocks::SysPath => d.extra.as_ref().and_then(|e| e.syspath.to_owned()).into(),
+            DeviceBlocks::Driver => d.extra.as_ref().and_then(|e| e.driver.to_owned()).into(),
+            DeviceBlocks::Icon => BlockValue::None,
+            DeviceBlocks::VendorId => d.vendor_id.into(),
+            DeviceBlocks::ProductId => d.product_id.into(),
+            DeviceBlocks::VidPid => match (d.vendor_id, d.product_id) {
+                (Some(v), Some(p)) => format!("{:04x}:{:04x}", v, p).into(),
+                _ => BlockValue::None,
+            },
+            DeviceBlocks::NumConfigurations => {
+                d.extra.as_ref().map(|e| e.configurations.len()).into()
             }
+            DeviceBlocks::Name => preferred_name(d, settings.prefer_usb_ids).into(),
+            DeviceBlocks::Manufacturer => preferred_manufacturer(d, settings.prefer_usb_ids).into(),
+            DeviceBlocks::ProductName => d
+                .extra
+                .as_ref()
+                .and_then(|e| e.product_name.to_owned())
+                .into(),
+            DeviceBlocks::VendorName => d.extra.as_ref().and_then(|e| e.vendor.to_owned()).into(),
+            DeviceBlocks::Vendor => preferred_vendor(d, settings.prefer_usb_ids).into(),
+            DeviceBlocks::Serial => d.serial_num.to_owned().into(),
+            DeviceBlocks::Speed => d.device_speed.to_owned().into(),
+            DeviceBlocks::SpeedVsMax => match d.device_speed.as_ref() {
+                Some(system_profiler::DeviceSpeed::SpeedValue(Speed::Unknown))
+                | Some(system_profiler::DeviceSpeed::Description(_))
+                | None => BlockValue::None,
+                Some(n) => BlockValue::List(vec![
+                    n.to_owned().into(),
+                    d.bcd_usb
+                        .as_ref()
+                        .and_then(Speed::max_for_version)
+                        .map(system_profiler::DeviceSpeed::SpeedValue)
+                        .into(),
+                ]),
+            },
+            DeviceBlocks::TreePositions => {
+                format_tree_positions(&d.location_id.tree_positions, false).into()
+            }
+            DeviceBlocks::BusPower => d.bus_power.into(),
+            DeviceBlocks::BusPowerUsed => d.bus_power_used.into(),
+            DeviceBlocks::ExtraCurrentUsed => d.extra_current_used.into(),
+            DeviceBlocks::BcdDevice => d.bcd_device.into(),
+            DeviceBlocks::BcdUsb => d.bcd_usb.into(),
+            DeviceBlocks::UsbVersionCategory => d
+                .bcd_usb
+                .map(|v| v.usb_category().map_or(v.to_string(), String::from))
+                .into(),
+            DeviceBlocks::ClassCode => d.class.to_owned().into(),
+            DeviceBlocks::SubClass => d.sub_class.into(),
+            DeviceBlocks::Protocol => d.protocol.into(),
+            DeviceBlocks::ConnectedSince => d.extra.as_ref().and_then(|e| e.connected_since).into(),
+            DeviceBlocks::Tags => tags_string(d).into(),
+            DeviceBlocks::NameSource => shown_name_source(d, settings.prefer_usb_ids)
+                .filter(|s| *s != system_profiler::NameSource::Unknown)
+                .map(|s| s.to_string())
+                .into(),
+            DeviceBlocks::PowerState => d
+                .extra
+                .as_ref()
+                .and_then(|e| e.power_state.to_owned())
+                .into(),
+        }
+    }
+
+    fn theme_colour(&self, s: &String, ct: &colour::ColourTheme) -> Option<Color> {
+        match self {
+            DeviceBlocks::BcdUsb
+            | DeviceBlocks::UsbVersionCategory
+            | DeviceBlocks::BcdDevice
+            | DeviceBlocks::DeviceNumber
+            | DeviceBlocks::ConnectedSince
+            | DeviceBlocks::NameSource => ct.number,
             DeviceBlocks::BusNumber
             | DeviceBlocks::BranchPosition
-            | DeviceBlocks::TreePositions => ct.location.map_or(s.normal(), |c| s.color(c)),
-            DeviceBlocks::Icon => ct.icon.map_or(s.normal(), |c| s.color(c)),
-            DeviceBlocks::PortPath | DeviceBlocks::SysPath => {
-                ct.path.map_or(s.normal(), |c| s.color(c))
-            }
-            DeviceBlocks::VendorId => ct.vid.map_or(s.normal(), |c| s.color(c)),
-            DeviceBlocks::ProductId => ct.pid.map_or(s.normal(), |c| s.color(c)),
-            DeviceBlocks::Name | DeviceBlocks::ProductName => {
-                ct.name.map_or(s.normal(), |c| s.color(c))
-            }
-            DeviceBlocks::Serial => ct.serial.map_or(s.normal(), |c| s.color(c)),
-            DeviceBlocks::Manufacturer | DeviceBlocks::VendorName => {
-                ct.manufacturer.map_or(s.normal(), |c| s.color(c))
-            }
-            DeviceBlocks::Driver => ct.driver.map_or(s.normal(), |c| s.color(c)),
-            DeviceBlocks::Speed => ct.speed.map_or(s.normal(), |c| s.color(c)),
+            | DeviceBlocks::PortNumber
+            | DeviceBlocks::TreePositions => ct.location,
+            DeviceBlocks::Icon => ct.icon,
+            DeviceBlocks::PortPath | DeviceBlocks::FullPath | DeviceBlocks::SysPath => ct.path,
+            DeviceBlocks::VendorId => ct.vid,
+            DeviceBlocks::VidPid => ct.vid,
+            DeviceBlocks::NumConfigurations => ct.number,
+            DeviceBlocks::ProductId => ct.pid,
+            DeviceBlocks::Name | DeviceBlocks::ProductName => ct.name,
+            DeviceBlocks::Serial => ct.serial,
+            DeviceBlocks::Manufacturer | DeviceBlocks::VendorName | DeviceBlocks::Vendor => {
+                ct.manufacturer
+            }
+            DeviceBlocks::Driver => ct.driver,
+            DeviceBlocks::PowerState => ct.power,
+            DeviceBlocks::Tags => ct.attributes,
+            DeviceBlocks::Speed => ct.speed,
+            DeviceBlocks::SpeedVsMax => match s.trim().split_once('/') {
+                Some((n, m)) if n != m => ct.speed_mismatch.or(ct.speed),
+                _ => ct.speed,
+            },
             DeviceBlocks::BusPower
             | DeviceBlocks::BusPowerUsed
-            | DeviceBlocks::ExtraCurrentUsed => ct.power.map_or(s.normal(), |c| s.color(c)),
-            DeviceBlocks::ClassCode => ct.class_code.map_or(s.normal(), |c| s.color(c)),
-            DeviceBlocks::SubClass => ct.sub_code.map_or(s.normal(), |c| s.color(c)),
-            DeviceBlocks::Protocol => ct.protocol.map_or(s.normal(), |c| s.color(c)),
-            // _ => s.normal(),
+            | DeviceBlocks::ExtraCurrentUsed => ct.power,
+            DeviceBlocks::ClassCode => ct.class_code,
+            DeviceBlocks::SubClass => ct.sub_code,
+            DeviceBlocks::Protocol => ct.protocol,
+            // _ => None,
         }
     }
 
@@ -566,9 +1289,13 @@ impl Block<DeviceBlocks, USBDevice> for DeviceBlocks {
             DeviceBlocks::BusNumber => "Bus".into(),
             DeviceBlocks::DeviceNumber => " # ".into(),
             DeviceBlocks::BranchPosition => "Prt".into(),
+            DeviceBlocks::PortNumber => "Port".into(),
             DeviceBlocks::PortPath => {
                 format!("{:^pad$}", "PPath", pad = pad.get(self).unwrap_or(&0))
             }
+            DeviceBlocks::FullPath => {
+                format!("{:^pad$}", "FPath", pad = pad.get(self).unwrap_or(&0))
+            }
             DeviceBlocks::SysPath => {
                 format!("{:^pad$}", "SPath", pad = pad.get(self).unwrap_or(&0))
             }
@@ -576,6 +1303,8 @@ impl Block<DeviceBlocks, USBDevice> for DeviceBlocks {
                 format!("{:^pad$}", "Driver", pad = pad.get(self).unwrap_or(&0))
             }
             DeviceBlocks::VendorId => format!("{:^6}", "VID"),
+            DeviceBlocks::VidPid => format!("{:^9}", "VID:PID"),
+            DeviceBlocks::NumConfigurations => "Cfg#".into(),
             DeviceBlocks::ProductId => format!("{:^6}", "PID"),
             DeviceBlocks::Name => format!("{:^pad$}", "Name", pad = pad.get(self).unwrap_or(&0)),
             DeviceBlocks::Manufacturer => {
@@ -591,25 +1320,49 @@ impl Block<DeviceBlocks, USBDevice> for DeviceBlocks {
             DeviceBlocks::VendorName => {
                 format!("{:^pad$}", "VName", pad = pad.get(self).unwrap_or(&0))
             }
+            DeviceBlocks::Vendor => {
+                format!("{:^pad$}", "Vendor", pad = pad.get(self).unwrap_or(&0))
+            }
             DeviceBlocks::Serial => {
                 format!("{:^pad$}", "Serial", pad = pad.get(self).unwrap_or(&0))
             }
             DeviceBlocks::Speed => format!("{:^10}", "Speed"),
+            DeviceBlocks::SpeedVsMax => format!("{:^11}", "Speed/Max"),
             DeviceBlocks::TreePositions => {
                 format!("{:^pad$}", "TPos", pad = pad.get(self).unwrap_or(&0))
             }
-            // will be 000 mA = 6
-            DeviceBlocks::BusPower => "PBus".into(),
-            DeviceBlocks::BusPowerUsed => "PUsd".into(),
-            DeviceBlocks::ExtraCurrentUsed => "PExr".into(),
+            // at least 000 mA = 6
+            DeviceBlocks::BusPower => format!(
+                "{:>pad$}",
+                "PBus",
+                pad = cmp::max(*pad.get(self).unwrap_or(&0), CURRENT_WIDTH)
+            ),
+            DeviceBlocks::BusPowerUsed => format!(
+                "{:>pad$}",
+                "PUsd",
+                pad = cmp::max(*pad.get(self).unwrap_or(&0), CURRENT_WIDTH)
+            ),
+            DeviceBlocks::ExtraCurrentUsed => format!(
+                "{:>pad$}",
+                "PExr",
+                pad = cmp::max(*pad.get(self).unwrap_or(&0), CURRENT_WIDTH)
+            ),
             // 00.00 = 5
             DeviceBlocks::BcdDevice => "Dev V".into(),
             DeviceBlocks::BcdUsb => "USB V".into(),
+            DeviceBlocks::UsbVersionCategory => format!("{:15}", "USBCat"),
             DeviceBlocks::ClassCode => {
                 format!("{:^pad$}", "Class", pad = pad.get(self).unwrap_or(&0))
             }
             DeviceBlocks::SubClass => "SubC".into(),
             DeviceBlocks::Protocol => "Pcol".into(),
+            // 000d ago = 8
+            DeviceBlocks::ConnectedSince => format!("{:^8}", "Age"),
+            // suspending = 10
+            DeviceBlocks::PowerState => format!("{:^10}", "PwrSt"),
+            DeviceBlocks::Tags => format!("{:^pad$}", "Tags", pad = pad.get(self).unwrap_or(&0)),
+            // desc = 4
+            DeviceBlocks::NameSource => format!("{:4}", "Src"),
             DeviceBlocks::Icon => ICON_HEADING.into(),
             // _ => "",
         }
@@ -617,6 +1370,19 @@ impl Block<DeviceBlocks, USBDevice> for DeviceBlocks {
 }
 
 impl Block<BusBlocks, USBBus> for BusBlocks {
+    /// Verbose blocks at maximum verbosity or more, with the controller driver added to the defaults at any verbosity
+    fn default_blocks_for(settings: &PrintSettings) -> Vec<BusBlocks> {
+        if settings.verbosity >= MAX_VERBOSITY || settings.more {
+            BusBlocks::default_blocks(true)
+        } else if settings.verbosity > 0 {
+            let mut blocks = BusBlocks::default_blocks(false);
+            blocks.push(BusBlocks::Driver);
+            blocks
+        } else {
+            BusBlocks::default_blocks(false)
+        }
+    }
+
     fn default_blocks(verbose: bool) -> Vec<BusBlocks> {
         if verbose {
             vec![
@@ -627,6 +1393,8 @@ impl Block<BusBlocks, USBBus> for BusBlocks {
                 BusBlocks::PciVendor,
                 BusBlocks::PciDevice,
                 BusBlocks::PciRevision,
+                BusBlocks::UsbVersion,
+                BusBlocks::Driver,
             ]
         } else {
             vec![BusBlocks::Name, BusBlocks::HostController]
@@ -638,91 +1406,188 @@ impl Block<BusBlocks, USBBus> for BusBlocks {
             (
                 BusBlocks::Name,
                 cmp::max(
-                    BusBlocks::Name.heading(&Default::default()).len(),
-                    d.iter().map(|d| d.name.len()).max().unwrap_or(0),
+                    BusBlocks::Name.heading(&Default::default()).width(),
+                    d.iter().map(|d| d.name.width()).max().unwrap_or(0),
                 ),
             ),
             (
                 BusBlocks::HostController,
                 cmp::max(
-                    BusBlocks::HostController.heading(&Default::default()).len(),
-                    d.iter().map(|d| d.host_controller.len()).max().unwrap_or(0),
+                    BusBlocks::HostController
+                        .heading(&Default::default())
+                        .width(),
+                    d.iter()
+                        .map(|d| d.host_controller.width())
+                        .max()
+                        .unwrap_or(0),
+                ),
+            ),
+            (
+                BusBlocks::Driver,
+                cmp::max(
+                    BusBlocks::Driver.heading(&Default::default()).width(),
+                    d.iter()
+                        .map(|d| d.driver.as_ref().map_or(0, |v| v.width()))
+                        .max()
+                        .unwrap_or(0),
                 ),
             ),
             (
                 BusBlocks::PortPath,
                 cmp::max(
-                    BusBlocks::PortPath.heading(&Default::default()).len(),
-                    d.iter().map(|d| d.path().len()).max().unwrap_or(0),
+                    BusBlocks::PortPath.heading(&Default::default()).width(),
+                    d.iter().map(|d| d.path().width()).max().unwrap_or(0),
+                ),
+            ),
+            (
+                BusBlocks::NumDevices,
+                cmp::max(
+                    BusBlocks::NumDevices.heading(&Default::default()).width(),
+                    d.iter()
+                        .map(|d| {
+                            d.devices
+                                .as_ref()
+                                .map_or(0, |dd| dd.len())
+                                .to_string()
+                                .len()
+                        })
+                        .max()
+                        .unwrap_or(0),
+                ),
+            ),
+            (
+                BusBlocks::NumTotalDevices,
+                cmp::max(
+                    BusBlocks::NumTotalDevices
+                        .heading(&Default::default())
+                        .width(),
+                    d.iter()
+                        .map(|d| d.flattened_devices().len().to_string().len())
+                        .max()
+                        .unwrap_or(0),
                 ),
             ),
         ])
     }
 
+    fn alignment(&self, settings: &PrintSettings) -> Option<Alignment> {
+        settings
+            .bus_alignment
+            .as_ref()
+            .and_then(|a| a.get(self))
+            .copied()
+    }
+
+    fn number_category(&self) -> NumberCategory {
+        match self {
+            BusBlocks::PciVendor | BusBlocks::PciDevice | BusBlocks::PciRevision => {
+                NumberCategory::Ids
+            }
+            _ => NumberCategory::Descriptor,
+        }
+    }
+
     fn value_is_string(&self) -> bool {
         match self {
-            BusBlocks::Name | BusBlocks::HostController => true,
+            BusBlocks::Name | BusBlocks::HostController | BusBlocks::Driver => true,
             _ => false,
         }
     }
 
-    fn colour(&self, s: &String, ct: &colour::ColourTheme) -> ColoredString {
+    fn theme_colour(&self, _s: &String, ct: &colour::ColourTheme) -> Option<Color> {
+        match self {
+            BusBlocks::BusNumber => ct.location,
+            BusBlocks::PciVendor => ct.vid,
+            BusBlocks::PciDevice => ct.pid,
+            BusBlocks::Name => ct.name,
+            BusBlocks::HostController => ct.serial,
+            BusBlocks::Driver => ct.driver,
+            BusBlocks::PciRevision | BusBlocks::UsbVersion => ct.number,
+            BusBlocks::Icon => ct.icon,
+            BusBlocks::PortPath => ct.path,
+            BusBlocks::NumDevices | BusBlocks::NumTotalDevices => ct.number,
+        }
+    }
+
+    fn default_alignment(&self, bus: &system_profiler::USBBus) -> Alignment {
+        match self {
+            BusBlocks::BusNumber
+            | BusBlocks::PciVendor
+            | BusBlocks::PciDevice
+            | BusBlocks::PciRevision
+            | BusBlocks::NumDevices
+            | BusBlocks::NumTotalDevices => Alignment::Right,
+            // version is left aligned but '-' to the right
+            BusBlocks::UsbVersion if bus.usb_version().is_none() => Alignment::Right,
+            _ => Alignment::Left,
+        }
+    }
+
+    fn width(&self, pad: &HashMap<Self, usize>, _settings: &PrintSettings) -> usize {
         match self {
-            BusBlocks::BusNumber => ct.location.map_or(s.normal(), |c| s.color(c)),
-            BusBlocks::PciVendor => ct.vid.map_or(s.normal(), |c| s.color(c)),
-            BusBlocks::PciDevice => ct.pid.map_or(s.normal(), |c| s.color(c)),
-            BusBlocks::Name => ct.name.map_or(s.normal(), |c| s.color(c)),
-            BusBlocks::HostController => ct.serial.map_or(s.normal(), |c| s.color(c)),
-            BusBlocks::PciRevision => ct.number.map_or(s.normal(), |c| s.color(c)),
-            BusBlocks::Icon => ct.icon.map_or(s.normal(), |c| s.color(c)),
-            BusBlocks::PortPath => ct.path.map_or(s.normal(), |c| s.color(c)),
-            // _ => s.normal(),
+            BusBlocks::BusNumber => 3,
+            BusBlocks::PciVendor | BusBlocks::PciDevice | BusBlocks::PciRevision => 6,
+            BusBlocks::UsbVersion => 5,
+            BusBlocks::Icon => 0,
+            _ => *pad.get(self).unwrap_or(&0),
         }
     }
 
     fn format_value(
         &self,
         bus: &system_profiler::USBBus,
-        pad: &HashMap<Self, usize>,
+        _pad: &HashMap<Self, usize>,
         settings: &PrintSettings,
     ) -> Option<String> {
         match self {
-            BusBlocks::BusNumber => Some(format!("{:3}", bus.get_bus_number())),
+            BusBlocks::BusNumber => Some(bus.get_bus_number().to_string()),
             BusBlocks::Icon => settings
                 .icons
                 .as_ref()
                 .map_or(None, |i| Some(i.get_bus_icon(bus))),
             BusBlocks::PciVendor => Some(match bus.pci_vendor {
-                Some(v) => Self::format_base_u16(v, settings),
-                None => format!("{:>6}", "-"),
+                Some(v) => self.format_base_u16(v, settings),
+                None => String::from("-"),
             }),
             BusBlocks::PciDevice => Some(match bus.pci_device {
-                Some(v) => Self::format_base_u16(v, settings),
-                None => format!("{:>6}", "-"),
+                Some(v) => self.format_base_u16(v, settings),
+                None => String::from("-"),
             }),
             BusBlocks::PciRevision => Some(match bus.pci_revision {
-                Some(v) => Self::format_base_u16(v, settings),
-                None => format!("{:>6}", "-"),
+                Some(v) => self.format_base_u16(v, settings),
+                None => String::from("-"),
             }),
-            BusBlocks::Name => Some(format!(
-                "{:pad$}",
-                bus.name,
-                pad = pad.get(self).unwrap_or(&0)
-            )),
-            BusBlocks::HostController => Some(format!(
-                "{:pad$}",
-                bus.host_controller,
-                pad = pad.get(self).unwrap_or(&0)
-            )),
-            BusBlocks::PortPath => Some(format!(
-                "{:pad$}",
-                bus.path(),
-                pad = pad.get(self).unwrap_or(&0)
-            )),
+            BusBlocks::UsbVersion => Some(match bus.usb_version() {
+                Some(v) => v.to_string(),
+                None => String::from("-"),
+            }),
+            BusBlocks::Name => Some(bus.name.to_owned()),
+            BusBlocks::HostController => Some(bus.host_controller.to_owned()),
+            BusBlocks::PortPath => Some(bus.path()),
+            BusBlocks::Driver => Some(bus.driver.as_deref().unwrap_or("-").to_string()),
+            BusBlocks::NumDevices => Some(bus.devices.as_ref().map_or(0, |d| d.len()).to_string()),
+            BusBlocks::NumTotalDevices => Some(bus.flattened_devices().len().to_string()),
             // _ => None,
         }
     }
 
+    fn raw_value(&self, bus: &system_profiler::USBBus, _settings: &PrintSettings) -> BlockValue {
+        match self {
+            BusBlocks::BusNumber => bus.get_bus_number().into(),
+            BusBlocks::Icon => BlockValue::None,
+            BusBlocks::PciVendor => bus.pci_vendor.into(),
+            BusBlocks::PciDevice => bus.pci_device.into(),
+            BusBlocks::PciRevision => bus.pci_revision.into(),
+            BusBlocks::UsbVersion => bus.usb_version().into(),
+            BusBlocks::Name => bus.name.to_owned().into(),
+            BusBlocks::HostController => bus.host_controller.to_owned().into(),
+            BusBlocks::PortPath => bus.path().into(),
+            BusBlocks::Driver => bus.driver.to_owned().into(),
+            BusBlocks::NumDevices => bus.devices.as_ref().map_or(0, |d| d.len()).into(),
+            BusBlocks::NumTotalDevices => bus.flattened_devices().len().into(),
+        }
+    }
+
     fn heading(&self, pad: &HashMap<Self, usize>) -> String {
         match self {
             BusBlocks::BusNumber => "Bus".into(),
@@ -730,6 +1595,11 @@ impl Block<BusBlocks, USBBus> for BusBlocks {
             BusBlocks::PciDevice => " PID ".into(),
             BusBlocks::PciVendor => " VID ".into(),
             BusBlocks::PciRevision => " Rev ".into(),
+            BusBlocks::UsbVersion => "USB V".into(),
+            BusBlocks::NumDevices => format!("{:^pad$}", "Dev#", pad = pad.get(self).unwrap_or(&0)),
+            BusBlocks::NumTotalDevices => {
+                format!("{:^pad$}", "Tot#", pad = pad.get(self).unwrap_or(&0))
+            }
             BusBlocks::Name => format!("{:^pad$}", "Name", pad = pad.get(self).unwrap_or(&0)),
             BusBlocks::HostController => {
                 format!(
@@ -738,6 +1608,9 @@ impl Block<BusBlocks, USBBus> for BusBlocks {
                     pad = pad.get(self).unwrap_or(&0)
                 )
             }
+            BusBlocks::Driver => {
+                format!("{:^pad$}", "Driver", pad = pad.get(self).unwrap_or(&0))
+            }
             BusBlocks::Icon => ICON_HEADING.into(),
             // _ => "",
         }
@@ -767,11 +1640,17 @@ impl Block<ConfigurationBlocks, USBConfiguration> for ConfigurationBlocks {
 
     fn generate_padding(d: &Vec<&USBConfiguration>) -> HashMap<Self, usize> {
         HashMap::from([
+            (
+                ConfigurationBlocks::MaxPower,
+                current_padding(d.iter().map(|d| Some(d.max_power.to_string()))),
+            ),
             (
                 ConfigurationBlocks::Name,
                 cmp::max(
-                    ConfigurationBlocks::Name.heading(&Default::default()).len(),
-                    d.iter().map(|d| d.name.len()).max().unwrap_or(0),
+                    ConfigurationBlocks::Name
+                        .heading(&Default::default())
+                        .width(),
+                    d.iter().map(|d| d.name.width()).max().unwrap_or(0),
                 ),
             ),
             (
@@ -779,9 +1658,27 @@ impl Block<ConfigurationBlocks, USBConfiguration> for ConfigurationBlocks {
                 cmp::max(
                     ConfigurationBlocks::Attributes
                         .heading(&Default::default())
-                        .len(),
+                        .width(),
+                    d.iter()
+                        .map(|d| d.attributes_string().width())
+                        .max()
+                        .unwrap_or(0),
+                ),
+            ),
+            (
+                ConfigurationBlocks::IconAttributes,
+                cmp::max(
+                    ConfigurationBlocks::IconAttributes
+                        .heading(&Default::default())
+                        .width(),
+                    // padding for whichever of icons or ascii is wider since settings are not known
                     d.iter()
-                        .map(|d| d.attributes_string().len())
+                        .map(|d| {
+                            cmp::max(
+                                attributes_icon_string(&d.attributes, true).width(),
+                                attributes_icon_string(&d.attributes, false).width(),
+                            )
+                        })
                         .max()
                         .unwrap_or(0),
                 ),
@@ -789,6 +1686,14 @@ impl Block<ConfigurationBlocks, USBConfiguration> for ConfigurationBlocks {
         ])
     }
 
+    fn alignment(&self, settings: &PrintSettings) -> Option<Alignment> {
+        settings
+            .config_alignment
+            .as_ref()
+            .and_then(|a| a.get(self))
+            .copied()
+    }
+
     fn value_is_string(&self) -> bool {
         match self {
             ConfigurationBlocks::Name | ConfigurationBlocks::Attributes => true,
@@ -796,52 +1701,119 @@ impl Block<ConfigurationBlocks, USBConfiguration> for ConfigurationBlocks {
         }
     }
 
-    fn colour(&self, s: &String, ct: &colour::ColourTheme) -> ColoredString {
+    fn theme_colour(&self, _s: &String, ct: &colour::ColourTheme) -> Option<Color> {
+        match self {
+            ConfigurationBlocks::Number => ct.location,
+            ConfigurationBlocks::NumInterfaces | ConfigurationBlocks::TotalLength => ct.number,
+            ConfigurationBlocks::MaxPower => ct.power,
+            ConfigurationBlocks::Name => ct.name,
+            ConfigurationBlocks::Attributes => ct.attributes,
+            ConfigurationBlocks::IconAttributes => ct.icon,
+            ConfigurationBlocks::SelfPowered | ConfigurationBlocks::RemoteWakeup => ct.attributes,
+        }
+    }
+
+    fn semantic_colour(&self, config: &USBConfiguration) -> Option<Color> {
+        match self {
+            ConfigurationBlocks::MaxPower => Some(colour::power_gradient(config.max_power.value)),
+            _ => None,
+        }
+    }
+
+    fn default_alignment(&self, _config: &USBConfiguration) -> Alignment {
+        match self {
+            ConfigurationBlocks::Number
+            | ConfigurationBlocks::NumInterfaces
+            | ConfigurationBlocks::TotalLength
+            | ConfigurationBlocks::MaxPower => Alignment::Right,
+            _ => Alignment::Left,
+        }
+    }
+
+    fn width(&self, pad: &HashMap<Self, usize>, _settings: &PrintSettings) -> usize {
         match self {
-            ConfigurationBlocks::Number => ct.location.map_or(s.normal(), |c| s.color(c)),
-            ConfigurationBlocks::NumInterfaces => ct.number.map_or(s.normal(), |c| s.color(c)),
-            ConfigurationBlocks::MaxPower => ct.power.map_or(s.normal(), |c| s.color(c)),
-            ConfigurationBlocks::Name => ct.name.map_or(s.normal(), |c| s.color(c)),
-            ConfigurationBlocks::Attributes => ct.attributes.map_or(s.normal(), |c| s.color(c)),
-            ConfigurationBlocks::IconAttributes => ct.icon.map_or(s.normal(), |c| s.color(c)),
-            // _ => s.normal(),
+            ConfigurationBlocks::Number => 3,
+            ConfigurationBlocks::NumInterfaces
+            | ConfigurationBlocks::SelfPowered
+            | ConfigurationBlocks::RemoteWakeup => 2,
+            ConfigurationBlocks::TotalLength => 4,
+            ConfigurationBlocks::MaxPower => cmp::max(*pad.get(self).unwrap_or(&0), CURRENT_WIDTH),
+            _ => *pad.get(self).unwrap_or(&0),
         }
     }
 
     fn format_value(
         &self,
         config: &USBConfiguration,
-        pad: &HashMap<Self, usize>,
+        _pad: &HashMap<Self, usize>,
         settings: &PrintSettings,
     ) -> Option<String> {
         match self {
-            ConfigurationBlocks::Number => Some(format!("{:2}", config.number)),
-            ConfigurationBlocks::NumInterfaces => Some(format!("{:2}", config.interfaces.len())),
-            ConfigurationBlocks::Name => Some(format!(
-                "{:pad$}",
-                config.name,
-                pad = pad.get(self).unwrap_or(&0)
+            // active configuration marked with '*'
+            ConfigurationBlocks::Number => Some(format!(
+                "{}{}",
+                config.number,
+                if config.active { "*" } else { " " }
             )),
-            ConfigurationBlocks::MaxPower => Some(format!("{:3}", config.max_power)),
-            ConfigurationBlocks::Attributes => Some(format!(
-                "{:pad$}",
-                config.attributes_string(),
-                pad = pad.get(self).unwrap_or(&0)
+            ConfigurationBlocks::NumInterfaces => Some(config.interfaces.len().to_string()),
+            ConfigurationBlocks::TotalLength => Some(match config.total_length() {
+                Some(l) => l.to_string(),
+                None => String::from("-"),
+            }),
+            ConfigurationBlocks::Name => Some(config.name.to_owned()),
+            ConfigurationBlocks::MaxPower => Some(config.max_power.to_string()),
+            ConfigurationBlocks::Attributes => Some(config.attributes_string()),
+            ConfigurationBlocks::IconAttributes => {
+                Some(attributes_to_icons(&config.attributes, settings))
+            }
+            ConfigurationBlocks::SelfPowered => Some(String::from(
+                if config.attributes.contains(&ConfigAttributes::SelfPowered) {
+                    "Y"
+                } else {
+                    "-"
+                },
             )),
-            ConfigurationBlocks::IconAttributes => Some(format!(
-                "{:pad$}",
-                attributes_to_icons(&config.attributes, settings),
-                pad = pad.get(self).unwrap_or(&3)
+            ConfigurationBlocks::RemoteWakeup => Some(String::from(
+                if config.attributes.contains(&ConfigAttributes::RemoteWakeup) {
+                    "Y"
+                } else {
+                    "-"
+                },
             )),
             // _ => None,
         }
     }
 
+    fn raw_value(&self, config: &USBConfiguration, _settings: &PrintSettings) -> BlockValue {
+        match self {
+            ConfigurationBlocks::Number => config.number.into(),
+            ConfigurationBlocks::NumInterfaces => config.interfaces.len().into(),
+            ConfigurationBlocks::TotalLength => config.total_length().into(),
+            ConfigurationBlocks::Name => config.name.to_owned().into(),
+            ConfigurationBlocks::MaxPower => config.max_power.value.into(),
+            ConfigurationBlocks::Attributes => config.attributes_value().into(),
+            ConfigurationBlocks::IconAttributes => BlockValue::None,
+            ConfigurationBlocks::SelfPowered => config
+                .attributes
+                .contains(&ConfigAttributes::SelfPowered)
+                .into(),
+            ConfigurationBlocks::RemoteWakeup => config
+                .attributes
+                .contains(&ConfigAttributes::RemoteWakeup)
+                .into(),
+        }
+    }
+
     fn heading(&self, pad: &HashMap<Self, usize>) -> String {
         match self {
-            ConfigurationBlocks::Number => " #".into(),
+            ConfigurationBlocks::Number => " # ".into(),
             ConfigurationBlocks::NumInterfaces => "I#".into(),
-            ConfigurationBlocks::MaxPower => "PMax".into(),
+            ConfigurationBlocks::TotalLength => " Len".into(),
+            ConfigurationBlocks::MaxPower => format!(
+                "{:>pad$}",
+                "PMax",
+                pad = cmp::max(*pad.get(self).unwrap_or(&0), CURRENT_WIDTH)
+            ),
             ConfigurationBlocks::Name => {
                 format!("{:^pad$}", "Name", pad = pad.get(self).unwrap_or(&0))
             }
@@ -849,9 +1821,11 @@ impl Block<ConfigurationBlocks, USBConfiguration> for ConfigurationBlocks {
                 format!("{:^pad$}", "Attributes", pad = pad.get(self).unwrap_or(&0))
             }
             ConfigurationBlocks::IconAttributes => {
-                format!("{:^pad$}", ICON_HEADING, pad = pad.get(self).unwrap_or(&3))
-            } // getting len of utf-8 icons is not pretty so resort to fixed 3
-              // _ => "",
+                format!("{:^pad$}", ICON_HEADING, pad = pad.get(self).unwrap_or(&0))
+            }
+            ConfigurationBlocks::SelfPowered => "SP".into(),
+            ConfigurationBlocks::RemoteWakeup => "RW".into(),
+            // _ => "",
         }
     }
 }
@@ -888,8 +1862,8 @@ impl Block<InterfaceBlocks, USBInterface> for InterfaceBlocks {
             (
                 InterfaceBlocks::Name,
                 cmp::max(
-                    InterfaceBlocks::Name.heading(&Default::default()).len(),
-                    d.iter().map(|d| d.name.len()).max().unwrap_or(0),
+                    InterfaceBlocks::Name.heading(&Default::default()).width(),
+                    d.iter().map(|d| d.name.width()).max().unwrap_or(0),
                 ),
             ),
             (
@@ -897,9 +1871,21 @@ impl Block<InterfaceBlocks, USBInterface> for InterfaceBlocks {
                 cmp::max(
                     InterfaceBlocks::ClassCode
                         .heading(&Default::default())
-                        .len(),
+                        .width(),
                     d.iter()
-                        .map(|d| d.class.to_string().len())
+                        .map(|d| d.class.to_string().width())
+                        .max()
+                        .unwrap_or(0),
+                ),
+            ),
+            (
+                InterfaceBlocks::ClassName,
+                cmp::max(
+                    InterfaceBlocks::ClassName
+                        .heading(&Default::default())
+                        .width(),
+                    d.iter()
+                        .map(|d| d.class.to_descriptive_string().width())
                         .max()
                         .unwrap_or(0),
                 ),
@@ -907,16 +1893,66 @@ impl Block<InterfaceBlocks, USBInterface> for InterfaceBlocks {
             (
                 InterfaceBlocks::PortPath,
                 cmp::max(
-                    InterfaceBlocks::PortPath.heading(&Default::default()).len(),
-                    d.iter().map(|d| d.path.len()).max().unwrap_or(0),
+                    InterfaceBlocks::PortPath
+                        .heading(&Default::default())
+                        .width(),
+                    d.iter().map(|d| d.path.width()).max().unwrap_or(0),
+                ),
+            ),
+            (
+                InterfaceBlocks::NumAltSettings,
+                cmp::max(
+                    InterfaceBlocks::NumAltSettings
+                        .heading(&Default::default())
+                        .width(),
+                    d.iter()
+                        .map(|i| i.num_alt_settings.to_string().width())
+                        .max()
+                        .unwrap_or(0),
                 ),
             ),
             (
                 InterfaceBlocks::SysPath,
                 cmp::max(
-                    InterfaceBlocks::SysPath.heading(&Default::default()).len(),
+                    InterfaceBlocks::SysPath
+                        .heading(&Default::default())
+                        .width(),
+                    d.iter()
+                        .map(|d| d.syspath.as_ref().unwrap_or(&String::new()).width())
+                        .max()
+                        .unwrap_or(0),
+                ),
+            ),
+            (
+                InterfaceBlocks::StringIndex,
+                cmp::max(
+                    InterfaceBlocks::StringIndex
+                        .heading(&Default::default())
+                        .width(),
+                    // 0xFF
+                    4,
+                ),
+            ),
+            (
+                InterfaceBlocks::EndpointSummary,
+                cmp::max(
+                    InterfaceBlocks::EndpointSummary
+                        .heading(&Default::default())
+                        .width(),
+                    d.iter()
+                        .map(|i| i.endpoint_summary().width())
+                        .max()
+                        .unwrap_or(0),
+                ),
+            ),
+            (
+                InterfaceBlocks::AssociationId,
+                cmp::max(
+                    InterfaceBlocks::AssociationId
+                        .heading(&Default::default())
+                        .width(),
                     d.iter()
-                        .map(|d| d.syspath.as_ref().unwrap_or(&String::new()).len())
+                        .map(|i| i.association_id().width())
                         .max()
                         .unwrap_or(0),
                 ),
@@ -924,9 +1960,9 @@ impl Block<InterfaceBlocks, USBInterface> for InterfaceBlocks {
             (
                 InterfaceBlocks::Driver,
                 cmp::max(
-                    InterfaceBlocks::Driver.heading(&Default::default()).len(),
+                    InterfaceBlocks::Driver.heading(&Default::default()).width(),
                     d.iter()
-                        .map(|d| d.driver.as_ref().unwrap_or(&String::new()).len())
+                        .map(|d| d.driver.as_ref().unwrap_or(&String::new()).width())
                         .max()
                         .unwrap_or(0),
                 ),
@@ -934,73 +1970,116 @@ impl Block<InterfaceBlocks, USBInterface> for InterfaceBlocks {
         ])
     }
 
+    fn alignment(&self, settings: &PrintSettings) -> Option<Alignment> {
+        settings
+            .interface_alignment
+            .as_ref()
+            .and_then(|a| a.get(self))
+            .copied()
+    }
+
+    fn number_category(&self) -> NumberCategory {
+        match self {
+            InterfaceBlocks::SubClass | InterfaceBlocks::Protocol => NumberCategory::Codes,
+            _ => NumberCategory::Descriptor,
+        }
+    }
+
     fn value_is_string(&self) -> bool {
         match self {
             InterfaceBlocks::Name
             | InterfaceBlocks::PortPath
             | InterfaceBlocks::ClassCode
+            | InterfaceBlocks::ClassName
             | InterfaceBlocks::Driver
-            | InterfaceBlocks::SysPath => true,
+            | InterfaceBlocks::SysPath
+            | InterfaceBlocks::EndpointSummary
+            | InterfaceBlocks::AssociationId => true,
             _ => false,
         }
     }
 
-    fn colour(&self, s: &String, ct: &colour::ColourTheme) -> ColoredString {
+    fn theme_colour(&self, _s: &String, ct: &colour::ColourTheme) -> Option<Color> {
         match self {
-            InterfaceBlocks::Number => ct.number.map_or(s.normal(), |c| s.color(c)),
-            InterfaceBlocks::Name => ct.name.map_or(s.normal(), |c| s.color(c)),
-            InterfaceBlocks::PortPath | InterfaceBlocks::SysPath => {
-                ct.path.map_or(s.normal(), |c| s.color(c))
-            }
-            InterfaceBlocks::Icon => ct.icon.map_or(s.normal(), |c| s.color(c)),
-            InterfaceBlocks::ClassCode => ct.class_code.map_or(s.normal(), |c| s.color(c)),
-            InterfaceBlocks::SubClass => ct.sub_code.map_or(s.normal(), |c| s.color(c)),
-            InterfaceBlocks::Protocol => ct.protocol.map_or(s.normal(), |c| s.color(c)),
-            InterfaceBlocks::Driver => ct.driver.map_or(s.normal(), |c| s.color(c)),
-            InterfaceBlocks::AltSetting | InterfaceBlocks::NumEndpoints => {
-                ct.number.map_or(s.normal(), |c| s.color(c))
+            InterfaceBlocks::Number => ct.number,
+            InterfaceBlocks::Name => ct.name,
+            InterfaceBlocks::PortPath | InterfaceBlocks::SysPath => ct.path,
+            InterfaceBlocks::Icon => ct.icon,
+            InterfaceBlocks::ClassCode
+            | InterfaceBlocks::ClassName
+            | InterfaceBlocks::AssociationId => ct.class_code,
+            InterfaceBlocks::SubClass => ct.sub_code,
+            InterfaceBlocks::Protocol => ct.protocol,
+            InterfaceBlocks::Driver => ct.driver,
+            InterfaceBlocks::EndpointSummary => ct.attributes,
+            InterfaceBlocks::AltSetting
+            | InterfaceBlocks::NumAltSettings
+            | InterfaceBlocks::NumEndpoints
+            | InterfaceBlocks::StringIndex => ct.number,
+            // _ => None,
+        }
+    }
+
+    fn default_alignment(&self, _interface: &USBInterface) -> Alignment {
+        match self {
+            InterfaceBlocks::Number
+            | InterfaceBlocks::NumEndpoints
+            | InterfaceBlocks::SubClass
+            | InterfaceBlocks::Protocol
+            | InterfaceBlocks::AltSetting
+            | InterfaceBlocks::NumAltSettings => Alignment::Right,
+            _ => Alignment::Left,
+        }
+    }
+
+    fn width(&self, pad: &HashMap<Self, usize>, settings: &PrintSettings) -> usize {
+        match self {
+            InterfaceBlocks::Number | InterfaceBlocks::NumEndpoints => 2,
+            InterfaceBlocks::SubClass | InterfaceBlocks::Protocol | InterfaceBlocks::AltSetting => {
+                self.base_u8_width(settings)
             }
-            // _ => s.normal(),
+            InterfaceBlocks::NumAltSettings => *pad.get(self).unwrap_or(&2),
+            InterfaceBlocks::Icon => 0,
+            _ => *pad.get(self).unwrap_or(&0),
         }
     }
 
     fn format_value(
         &self,
         interface: &USBInterface,
-        pad: &HashMap<Self, usize>,
+        _pad: &HashMap<Self, usize>,
         settings: &PrintSettings,
     ) -> Option<String> {
         match self {
-            InterfaceBlocks::Number => Some(format!("{:2}", interface.number)),
-            InterfaceBlocks::Name => Some(format!(
-                "{:pad$}",
-                interface.name,
-                pad = pad.get(self).unwrap_or(&0)
-            )),
-            InterfaceBlocks::NumEndpoints => Some(format!("{:2}", interface.endpoints.len())),
-            InterfaceBlocks::PortPath => Some(format!(
-                "{:pad$}",
-                interface.path,
-                pad = pad.get(self).unwrap_or(&0)
-            )),
+            InterfaceBlocks::Number => Some(interface.number.to_string()),
+            InterfaceBlocks::Name => Some(interface.name.to_owned()),
+            InterfaceBlocks::NumEndpoints => Some(interface.endpoints.len().to_string()),
+            InterfaceBlocks::PortPath => Some(interface.path.to_owned()),
             InterfaceBlocks::SysPath => Some(match interface.syspath.as_ref() {
-                Some(v) => format!("{:pad$}", v, pad = pad.get(self).unwrap_or(&0)),
-                None => format!("{:pad$}", "-", pad = pad.get(self).unwrap_or(&0)),
+                Some(v) => v.to_owned(),
+                None => String::from("-"),
             }),
             InterfaceBlocks::Driver => Some(match interface.driver.as_ref() {
-                Some(v) => format!("{:pad$}", v, pad = pad.get(self).unwrap_or(&0)),
-                None => format!("{:pad$}", "-", pad = pad.get(self).unwrap_or(&0)),
+                Some(v) => v.to_owned(),
+                None => String::from("-"),
             }),
-            InterfaceBlocks::ClassCode => Some(format!(
-                "{:pad$}",
-                interface.class.to_string(),
-                pad = pad.get(self).unwrap_or(&0)
-            )),
-            InterfaceBlocks::SubClass => Some(Self::format_base_u8(interface.sub_class, settings)),
-            InterfaceBlocks::Protocol => Some(Self::format_base_u8(interface.protocol, settings)),
+            InterfaceBlocks::ClassCode => Some(interface.class.to_string()),
+            InterfaceBlocks::ClassName => Some(interface.class.to_descriptive_string()),
+            InterfaceBlocks::SubClass => Some(self.format_base_u8(interface.sub_class, settings)),
+            InterfaceBlocks::Protocol => Some(self.format_base_u8(interface.protocol, settings)),
             InterfaceBlocks::AltSetting => {
-                Some(Self::format_base_u8(interface.alt_setting, settings))
+                Some(self.format_base_u8(interface.alt_setting, settings))
             }
+            InterfaceBlocks::StringIndex => Some(match interface.string_index {
+                0 => String::from("-"),
+                i => self.format_base_u8(i, settings),
+            }),
+            InterfaceBlocks::EndpointSummary => Some(interface.endpoint_summary()),
+            InterfaceBlocks::AssociationId => Some(interface.association_id()),
+            InterfaceBlocks::NumAltSettings => Some(match interface.num_alt_settings {
+                0 => String::from("-"),
+                n => n.to_string(),
+            }),
             InterfaceBlocks::Icon => settings.icons.as_ref().map_or(None, |i| {
                 Some(i.get_classifier_icon(
                     &interface.class,
@@ -1012,6 +2091,43 @@ impl Block<InterfaceBlocks, USBInterface> for InterfaceBlocks {
         }
     }
 
+    fn raw_value(&self, interface: &USBInterface, _settings: &PrintSettings) -> BlockValue {
+        match self {
+            InterfaceBlocks::Number => interface.number.into(),
+            InterfaceBlocks::Name => interface.name.to_owned().into(),
+            InterfaceBlocks::NumEndpoints => interface.endpoints.len().into(),
+            InterfaceBlocks::PortPath => interface.path.to_owned().into(),
+            InterfaceBlocks::SysPath => interface.syspath.to_owned().into(),
+            InterfaceBlocks::Driver => interface.driver.to_owned().into(),
+            InterfaceBlocks::ClassCode => interface.class.to_owned().into(),
+            InterfaceBlocks::ClassName => interface.class.to_descriptive_string().into(),
+            InterfaceBlocks::SubClass => interface.sub_class.into(),
+            InterfaceBlocks::Protocol => interface.protocol.into(),
+            InterfaceBlocks::AltSetting => interface.alt_setting.into(),
+            InterfaceBlocks::StringIndex => match interface.string_index {
+                0 => BlockValue::None,
+                i => i.into(),
+            },
+            InterfaceBlocks::EndpointSummary => {
+                if interface.endpoints.is_empty() {
+                    BlockValue::None
+                } else {
+                    interface.endpoint_summary().into()
+                }
+            }
+            InterfaceBlocks::AssociationId => interface
+                .association
+                .as_ref()
+                .map(|_| interface.association_id())
+                .into(),
+            InterfaceBlocks::NumAltSettings => match interface.num_alt_settings {
+                0 => BlockValue::None,
+                n => n.into(),
+            },
+            InterfaceBlocks::Icon => BlockValue::None,
+        }
+    }
+
     fn heading(&self, pad: &HashMap<Self, usize>) -> String {
         match self {
             InterfaceBlocks::Number => " #".into(),
@@ -1029,9 +2145,22 @@ impl Block<InterfaceBlocks, USBInterface> for InterfaceBlocks {
             InterfaceBlocks::ClassCode => {
                 format!("{:^pad$}", "Class", pad = pad.get(self).unwrap_or(&0))
             }
+            InterfaceBlocks::ClassName => {
+                format!("{:^pad$}", "Class Name", pad = pad.get(self).unwrap_or(&0))
+            }
             InterfaceBlocks::SubClass => "SubC".into(),
             InterfaceBlocks::Protocol => "Pcol".into(),
             InterfaceBlocks::AltSetting => "Alt#".into(),
+            InterfaceBlocks::NumAltSettings => "NAlt".into(),
+            InterfaceBlocks::StringIndex => {
+                format!("{:^pad$}", "iIf", pad = pad.get(self).unwrap_or(&0))
+            }
+            InterfaceBlocks::EndpointSummary => {
+                format!("{:^pad$}", "EPs", pad = pad.get(self).unwrap_or(&0))
+            }
+            InterfaceBlocks::AssociationId => {
+                format!("{:^pad$}", "Assoc", pad = pad.get(self).unwrap_or(&0))
+            }
             InterfaceBlocks::Icon => ICON_HEADING.into(),
             // _ => "",
         }
@@ -1049,6 +2178,7 @@ impl Block<EndpointBlocks, USBEndpoint> for EndpointBlocks {
                 EndpointBlocks::UsageType,
                 EndpointBlocks::Interval,
                 EndpointBlocks::MaxPacketSize,
+                EndpointBlocks::BytesPerInterval,
             ]
         } else {
             vec![
@@ -1069,9 +2199,9 @@ impl Block<EndpointBlocks, USBEndpoint> for EndpointBlocks {
                 cmp::max(
                     EndpointBlocks::TransferType
                         .heading(&Default::default())
-                        .len(),
+                        .width(),
                     d.iter()
-                        .map(|d| d.transfer_type.to_string().len())
+                        .map(|d| d.transfer_type.to_string().width())
                         .max()
                         .unwrap_or(0),
                 ),
@@ -1079,9 +2209,11 @@ impl Block<EndpointBlocks, USBEndpoint> for EndpointBlocks {
             (
                 EndpointBlocks::SyncType,
                 cmp::max(
-                    EndpointBlocks::SyncType.heading(&Default::default()).len(),
+                    EndpointBlocks::SyncType
+                        .heading(&Default::default())
+                        .width(),
                     d.iter()
-                        .map(|d| d.sync_type.to_string().len())
+                        .map(|d| d.sync_type.to_string().width())
                         .max()
                         .unwrap_or(0),
                 ),
@@ -1089,9 +2221,11 @@ impl Block<EndpointBlocks, USBEndpoint> for EndpointBlocks {
             (
                 EndpointBlocks::UsageType,
                 cmp::max(
-                    EndpointBlocks::UsageType.heading(&Default::default()).len(),
+                    EndpointBlocks::UsageType
+                        .heading(&Default::default())
+                        .width(),
                     d.iter()
-                        .map(|d| d.usage_type.to_string().len())
+                        .map(|d| d.usage_type.to_string().width())
                         .max()
                         .unwrap_or(0),
                 ),
@@ -1099,9 +2233,11 @@ impl Block<EndpointBlocks, USBEndpoint> for EndpointBlocks {
             (
                 EndpointBlocks::Direction,
                 cmp::max(
-                    EndpointBlocks::Direction.heading(&Default::default()).len(),
+                    EndpointBlocks::Direction
+                        .heading(&Default::default())
+                        .width(),
                     d.iter()
-                        .map(|d| d.address.direction.to_string().len())
+                        .map(|d| d.address.direction.to_string().width())
                         .max()
                         .unwrap_or(0),
                 ),
@@ -1111,9 +2247,48 @@ impl Block<EndpointBlocks, USBEndpoint> for EndpointBlocks {
                 cmp::max(
                     EndpointBlocks::MaxPacketSize
                         .heading(&Default::default())
-                        .len(),
+                        .width(),
+                    // padding for whichever of raw or human sizes is wider since settings are not known
+                    d.iter()
+                        .map(|d| {
+                            cmp::max(
+                                d.max_packet_string().width(),
+                                d.max_packet_human_string().width(),
+                            )
+                        })
+                        .max()
+                        .unwrap_or(0),
+                ),
+            ),
+            (
+                EndpointBlocks::Attributes,
+                cmp::max(
+                    EndpointBlocks::Attributes
+                        .heading(&Default::default())
+                        .width(),
+                    // 0xFF
+                    4,
+                ),
+            ),
+            (
+                EndpointBlocks::Address,
+                cmp::max(
+                    EndpointBlocks::Address.heading(&Default::default()).width(),
+                    // 0xFF
+                    4,
+                ),
+            ),
+            (
+                EndpointBlocks::BytesPerInterval,
+                cmp::max(
+                    EndpointBlocks::BytesPerInterval
+                        .heading(&Default::default())
+                        .width(),
                     d.iter()
-                        .map(|d| d.max_packet_string().len())
+                        .map(|d| {
+                            d.bytes_per_interval()
+                                .map_or(1, |b| format!("{} B/ms", b).width())
+                        })
                         .max()
                         .unwrap_or(0),
                 ),
@@ -1121,6 +2296,14 @@ impl Block<EndpointBlocks, USBEndpoint> for EndpointBlocks {
         ])
     }
 
+    fn alignment(&self, settings: &PrintSettings) -> Option<Alignment> {
+        settings
+            .endpoint_alignment
+            .as_ref()
+            .and_then(|a| a.get(self))
+            .copied()
+    }
+
     fn value_is_string(&self) -> bool {
         match self {
             EndpointBlocks::TransferType
@@ -1131,60 +2314,122 @@ impl Block<EndpointBlocks, USBEndpoint> for EndpointBlocks {
         }
     }
 
-    fn colour(&self, s: &String, ct: &colour::ColourTheme) -> ColoredString {
+    fn theme_colour(&self, _s: &String, ct: &colour::ColourTheme) -> Option<Color> {
         match self {
-            EndpointBlocks::Number | EndpointBlocks::Interval | EndpointBlocks::MaxPacketSize => {
-                ct.number.map_or(s.normal(), |c| s.color(c))
-            }
+            EndpointBlocks::Number
+            | EndpointBlocks::Address
+            | EndpointBlocks::Interval
+            | EndpointBlocks::IntervalTime
+            | EndpointBlocks::MaxPacketSize
+            | EndpointBlocks::BytesPerInterval
+            | EndpointBlocks::MaxBurst => ct.number,
             EndpointBlocks::Direction
             | EndpointBlocks::UsageType
             | EndpointBlocks::TransferType
-            | EndpointBlocks::SyncType => ct.attributes.map_or(s.normal(), |c| s.color(c)),
+            | EndpointBlocks::SyncType
+            | EndpointBlocks::Attributes => ct.attributes,
+        }
+    }
+
+    fn default_alignment(&self, _end: &USBEndpoint) -> Alignment {
+        match self {
+            EndpointBlocks::Number
+            | EndpointBlocks::Interval
+            | EndpointBlocks::IntervalTime
+            | EndpointBlocks::BytesPerInterval
+            | EndpointBlocks::MaxBurst => Alignment::Right,
+            _ => Alignment::Left,
+        }
+    }
+
+    fn width(&self, pad: &HashMap<Self, usize>, _settings: &PrintSettings) -> usize {
+        match self {
+            EndpointBlocks::Number | EndpointBlocks::Interval => 2,
+            // 32768 ms = 8
+            EndpointBlocks::IntervalTime => 8,
+            EndpointBlocks::MaxBurst => 5,
+            _ => *pad.get(self).unwrap_or(&0),
         }
     }
 
     fn format_value(
         &self,
         end: &USBEndpoint,
-        pad: &HashMap<Self, usize>,
-        _settings: &PrintSettings,
+        _pad: &HashMap<Self, usize>,
+        settings: &PrintSettings,
     ) -> Option<String> {
         match self {
-            EndpointBlocks::Number => Some(format!("{:2}", end.address.number)),
-            EndpointBlocks::Interval => Some(format!("{:2}", end.interval)),
-            EndpointBlocks::MaxPacketSize => Some(format!(
-                "{:pad$}",
-                end.max_packet_string(),
-                pad = pad.get(self).unwrap_or(&0)
-            )),
-            EndpointBlocks::Direction => Some(format!(
-                "{:pad$}",
-                end.address.direction.to_string(),
-                pad = pad.get(self).unwrap_or(&0)
-            )),
-            EndpointBlocks::TransferType => Some(format!(
-                "{:pad$}",
-                end.transfer_type.to_string(),
-                pad = pad.get(self).unwrap_or(&0)
-            )),
-            EndpointBlocks::SyncType => Some(format!(
-                "{:pad$}",
-                end.sync_type.to_string(),
-                pad = pad.get(self).unwrap_or(&0)
-            )),
-            EndpointBlocks::UsageType => Some(format!(
-                "{:pad$}",
-                end.usage_type.to_string(),
-                pad = pad.get(self).unwrap_or(&0)
-            )),
+            EndpointBlocks::Number => Some(end.address.number.to_string()),
+            EndpointBlocks::Address => Some(self.format_base_u8(end.address.address, settings)),
+            EndpointBlocks::Attributes => {
+                Some(self.format_base_u8(end.attributes_value(), settings))
+            }
+            EndpointBlocks::Interval => Some(end.interval.to_string()),
+            EndpointBlocks::IntervalTime => Some(
+                end.device_speed
+                    .as_ref()
+                    .and_then(|s| end.interval_us(s))
+                    .map_or(String::from("-"), |us| {
+                        if us < 1000 {
+                            format!("{} µs", us)
+                        } else {
+                            format!("{} ms", us / 1000)
+                        }
+                    }),
+            ),
+            EndpointBlocks::MaxPacketSize => Some(if settings.human_sizes {
+                end.max_packet_human_string()
+            } else {
+                end.max_packet_string()
+            }),
+            EndpointBlocks::Direction => Some(end.address.direction.to_string()),
+            EndpointBlocks::TransferType => Some(end.transfer_type.to_string()),
+            EndpointBlocks::SyncType => Some(end.sync_type.to_string()),
+            EndpointBlocks::UsageType => Some(end.usage_type.to_string()),
+            EndpointBlocks::BytesPerInterval => Some(
+                end.bytes_per_interval()
+                    .map_or(String::from("-"), |b| format!("{} B/ms", b)),
+            ),
+            EndpointBlocks::MaxBurst => Some(match end.companion.as_ref() {
+                Some(c) => c.max_burst.to_string(),
+                None => String::from("-"),
+            }),
             // _ => None,
         }
     }
 
+    fn raw_value(&self, end: &USBEndpoint, _settings: &PrintSettings) -> BlockValue {
+        match self {
+            EndpointBlocks::Number => end.address.number.into(),
+            EndpointBlocks::Address => end.address.address.into(),
+            EndpointBlocks::Attributes => end.attributes_value().into(),
+            EndpointBlocks::Interval => end.interval.into(),
+            EndpointBlocks::IntervalTime => end
+                .device_speed
+                .as_ref()
+                .and_then(|s| end.interval_us(s))
+                .into(),
+            EndpointBlocks::MaxPacketSize => end.max_packet_size.into(),
+            EndpointBlocks::Direction => end.address.direction.to_string().into(),
+            EndpointBlocks::TransferType => end.transfer_type.to_string().into(),
+            EndpointBlocks::SyncType => end.sync_type.to_string().into(),
+            EndpointBlocks::UsageType => end.usage_type.to_string().into(),
+            EndpointBlocks::BytesPerInterval => end.bytes_per_interval().into(),
+            EndpointBlocks::MaxBurst => end.companion.as_ref().map(|c| c.max_burst).into(),
+        }
+    }
+
     fn heading(&self, pad: &HashMap<Self, usize>) -> String {
         match self {
             EndpointBlocks::Number => " #".into(),
+            EndpointBlocks::Address => {
+                format!("{:^pad$}", "Addr", pad = pad.get(self).unwrap_or(&0))
+            }
             EndpointBlocks::Interval => "Iv".into(),
+            EndpointBlocks::IntervalTime => format!("{:^8}", "IvTime"),
+            EndpointBlocks::Attributes => {
+                format!("{:^pad$}", "bmAtt", pad = pad.get(self).unwrap_or(&0))
+            }
             EndpointBlocks::MaxPacketSize => {
                 format!("{:^pad$}", "MaxPkB", pad = pad.get(self).unwrap_or(&0))
             }
@@ -1200,11 +2445,114 @@ impl Block<EndpointBlocks, USBEndpoint> for EndpointBlocks {
             EndpointBlocks::UsageType => {
                 format!("{:^pad$}", "UsageT", pad = pad.get(self).unwrap_or(&0))
             }
+            EndpointBlocks::BytesPerInterval => {
+                format!("{:^pad$}", "B/Iv", pad = pad.get(self).unwrap_or(&0))
+            }
+            EndpointBlocks::MaxBurst => "Burst".into(),
             // _ => "",
         }
     }
 }
 
+/// Value to sort [`USBBus`]
+#[derive(Default, PartialEq, Eq, Debug, ValueEnum, Clone, Serialize, Deserialize)]
+pub enum BusSort {
+    /// Sort by bus number
+    Number,
+    /// Sort alphabetically by bus name
+    Name,
+    /// Sort alphabetically by host controller
+    HostController,
+    /// Sort naturally by port path, comparing each segment as a number, see [`natural_cmp`]
+    PortPath,
+    #[default]
+    /// No sorting; whatever order it was parsed
+    NoSort,
+}
+
+impl BusSort {
+    /// Sort `buses` in place; ties are kept in bus number order
+    ///
+    /// ```
+    /// use cyme::display::BusSort;
+    /// use cyme::system_profiler::USBBus;
+    ///
+    /// let mut buses = vec![
+    ///     USBBus { name: "xHCI".into(), usb_bus_number: Some(1), ..Default::default() },
+    ///     USBBus { name: "EHCI".into(), usb_bus_number: Some(2), ..Default::default() },
+    /// ];
+    /// BusSort::Name.sort_buses(&mut buses);
+    /// assert_eq!(buses[0].name, "EHCI");
+    /// BusSort::Number.sort_buses(&mut buses);
+    /// assert_eq!(buses[0].name, "xHCI");
+    /// ```
+    pub fn sort_buses(&self, buses: &mut Vec<USBBus>) {
+        match self {
+            BusSort::Number => buses.sort_by_key(|b| b.get_bus_number()),
+            BusSort::Name => buses.sort_by(|a, b| {
+                a.name
+                    .cmp(&b.name)
+                    .then(a.get_bus_number().cmp(&b.get_bus_number()))
+            }),
+            BusSort::HostController => buses.sort_by(|a, b| {
+                a.host_controller
+                    .cmp(&b.host_controller)
+                    .then(a.get_bus_number().cmp(&b.get_bus_number()))
+            }),
+            BusSort::PortPath => buses.sort_by(|a, b| natural_cmp(&a.path(), &b.path())),
+            BusSort::NoSort => (),
+        }
+    }
+}
+
+/// Value to sort the groups of [`Group`] by
+#[derive(PartialEq, Eq, Debug, ValueEnum, Clone, Copy, Serialize, Deserialize)]
+pub enum GroupSort {
+    /// Sort alphabetically by group name
+    Name,
+    /// Sort by number of devices in the group, fewest first
+    Count,
+    /// Sort by number of devices in the group, most first
+    CountDesc,
+}
+
+impl GroupSort {
+    /// Sort `groups` of (name, devices) in place; stable so ties keep their order
+    ///
+    /// ```
+    /// use cyme::display::GroupSort;
+    ///
+    /// let mut groups = vec![
+    ///     (String::from("OpenMoko"), vec![1]),
+    ///     (String::from("Linux Foundation"), vec![2, 3, 4]),
+    ///     (String::from("SEGGER"), vec![5]),
+    /// ];
+    /// GroupSort::CountDesc.sort_groups(&mut groups);
+    /// assert_eq!(groups[0].0, "Linux Foundation");
+    /// assert_eq!(groups[1].0, "OpenMoko");
+    /// GroupSort::Name.sort_groups(&mut groups);
+    /// assert_eq!(groups[2].0, "SEGGER");
+    /// ```
+    pub fn sort_groups<T>(&self, groups: &mut [(String, Vec<T>)]) {
+        match self {
+            GroupSort::Name => groups.sort_by_cached_key(|(n, _)| n.to_lowercase()),
+            GroupSort::Count => groups.sort_by_key(|(_, d)| d.len()),
+            GroupSort::CountDesc => groups.sort_by_key(|(_, d)| cmp::Reverse(d.len())),
+        }
+    }
+
+    /// Sort `buses` in place by name or number of devices on the bus including those attached to hubs; stable so ties keep their order
+    pub fn sort_buses(&self, buses: &mut [&USBBus]) {
+        match self {
+            GroupSort::Name => buses.sort_by_cached_key(|b| b.name.to_lowercase()),
+            GroupSort::Count => buses.sort_by_cached_key(|b| b.flattened_devices().len()),
+            GroupSort::CountDesc => {
+                buses.sort_by_cached_key(|b| cmp::Reverse(b.flattened_devices().len()))
+            }
+        }
+    }
+}
+
 /// Value to sort [`USBDevice`]
 #[derive(Default, PartialEq, Eq, Debug, ValueEnum, Clone, Serialize, Deserialize)]
 pub enum Sort {
@@ -1213,6 +2561,8 @@ pub enum Sort {
     BranchPosition,
     /// Sort by bus device number
     DeviceNumber,
+    /// Sort naturally by port path so that '1-2' precedes '1-10'; bus then each tree position as a number
+    PortPath,
     /// No sorting; whatever order it was parsed
     NoSort,
 }
@@ -1227,6 +2577,7 @@ impl Sort {
         match self {
             Sort::BranchPosition => sorted.sort_by_key(|d| d.get_branch_position()),
             Sort::DeviceNumber => sorted.sort_by_key(|d| d.location_id.number),
+            Sort::PortPath => sorted.sort_by(|a, b| port_path_cmp(a, b)),
             _ => (),
         }
 
@@ -1242,6 +2593,7 @@ impl Sort {
         match self {
             Sort::BranchPosition => sorted.sort_by_key(|d| d.get_branch_position()),
             Sort::DeviceNumber => sorted.sort_by_key(|d| d.location_id.number),
+            Sort::PortPath => sorted.sort_by(|a, b| port_path_cmp(a, b)),
             _ => (),
         }
 
@@ -1249,6 +2601,60 @@ impl Sort {
     }
 }
 
+/// Compares devices by bus then each of `location_id.tree_positions` numerically
+fn port_path_cmp(a: &USBDevice, b: &USBDevice) -> cmp::Ordering {
+    a.location_id.bus.cmp(&b.location_id.bus).then_with(|| {
+        a.location_id
+            .tree_positions
+            .cmp(&b.location_id.tree_positions)
+    })
+}
+
+/// Natural comparison of port paths like '1-2.10': runs of digits compare as numbers and anything else as chars
+///
+/// ```
+/// use cyme::display::natural_cmp;
+/// use std::cmp::Ordering;
+///
+/// assert_eq!(natural_cmp("1-2", "1-10"), Ordering::Less);
+/// assert_eq!(natural_cmp("2-0", "10-0"), Ordering::Less);
+/// assert_eq!(natural_cmp("1-2.3", "1-2.3"), Ordering::Equal);
+/// assert_eq!(natural_cmp("1-2", "1-2.1"), Ordering::Less);
+/// ```
+pub fn natural_cmp(a: &str, b: &str) -> cmp::Ordering {
+    let mut a = a.chars().peekable();
+    let mut b = b.chars().peekable();
+
+    loop {
+        match (a.peek().copied(), b.peek().copied()) {
+            (None, None) => return cmp::Ordering::Equal,
+            (None, Some(_)) => return cmp::Ordering::Less,
+            (Some(_), None) => return cmp::Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let take_number = |c: &mut std::iter::Peekable<std::str::Chars>| {
+                    let mut n: u64 = 0;
+                    while let Some(d) = c.peek().and_then(|c| c.to_digit(10)) {
+                        n = n.saturating_mul(10).saturating_add(d as u64);
+                        c.next();
+                    }
+                    n
+                };
+                let ord = take_number(&mut a).cmp(&take_number(&mut b));
+                if ord != cmp::Ordering::Equal {
+                    return ord;
+                }
+            }
+            (Some(x), Some(y)) => {
+                if x != y {
+                    return x.cmp(&y);
+                }
+                a.next();
+                b.next();
+            }
+        }
+    }
+}
+
 /// Value to group [`USBDevice`]
 #[derive(Default, Debug, ValueEnum, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
@@ -1258,6 +2664,8 @@ pub enum Group {
     NoGroup,
     /// Group into buses with bus info as heading - like a flat tree
     Bus,
+    /// Group by vendor name with vendor as heading; usb_ids vendor, manufacturer then VID
+    Vendor,
 }
 
 /// Charactor printing settings
@@ -1275,8 +2683,71 @@ pub enum Printing {
     NoIcons,
 }
 
+/// Alignment of a [`Block`] value within its padded width, see [`PrintSettings`] alignment maps
+#[derive(Debug, ValueEnum, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum Alignment {
+    /// Pad on the right
+    Left,
+    /// Pad on the left
+    Right,
+    /// Pad evenly either side
+    Center,
+}
+
+/// Category of numeric [`Block`] values for choosing base10 decimal over base16 with [`NumberFormat`]
+#[derive(
+    Debug, ValueEnum, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum NumberCategory {
+    /// Vendor, product and PCI IDs
+    Ids,
+    /// Sub-class and protocol codes
+    Codes,
+    /// Other descriptor fields like endpoint address and attributes, alternate setting and string index
+    Descriptor,
+    /// All of the above
+    All,
+}
+
+/// Set of [`NumberCategory`] to print as base10 decimal; others are printed as base16
+///
+/// ```
+/// use cyme::display::{NumberCategory, NumberFormat};
+///
+/// let format = NumberFormat::new(&[NumberCategory::Codes]);
+/// assert!(format.is_decimal(NumberCategory::Codes));
+/// assert!(!format.is_decimal(NumberCategory::Ids));
+/// assert!(NumberFormat::all_decimal().is_decimal(NumberCategory::Ids));
+/// assert!(!NumberFormat::default().is_decimal(NumberCategory::Codes));
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct NumberFormat {
+    decimal: HashSet<NumberCategory>,
+}
+
+impl NumberFormat {
+    /// Print `decimal` categories as base10
+    pub fn new(decimal: &[NumberCategory]) -> Self {
+        NumberFormat {
+            decimal: decimal.iter().copied().collect(),
+        }
+    }
+
+    /// Print all categories as base10, like the plain `--decimal` flag
+    pub fn all_decimal() -> Self {
+        NumberFormat::new(&[NumberCategory::All])
+    }
+
+    /// Whether values of `category` should be printed as base10
+    pub fn is_decimal(&self, category: NumberCategory) -> bool {
+        self.decimal.contains(&NumberCategory::All) || self.decimal.contains(&category)
+    }
+}
+
 /// Options for [`PrintSettings`] mask_serials
-#[derive(Default, Debug, ValueEnum, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Default, Debug, ValueEnum, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 pub enum MaskSerial {
     #[default]
@@ -1286,6 +2757,37 @@ pub enum MaskSerial {
     Scramble,
     /// Mask by replacing length with random chars
     Replace,
+    /// Keep the first and last visible chars and hide the middle with '*'; number visible each end is [`PrintSettings`] mask_serials_visible
+    Partial,
+}
+
+/// Default number of chars kept at each end of a serial with [`MaskSerial::Partial`]
+pub const DEFAULT_MASK_VISIBLE: usize = 2;
+
+/// Default number of chars configurations, interfaces and endpoints are indented per level with verbose non-tree printing
+pub const DEFAULT_INDENT_STEP: usize = 2;
+
+/// When to apply [`PrintSettings`] colours
+#[derive(Default, Debug, ValueEnum, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ColourWhen {
+    #[default]
+    /// Colour only if stdout is a terminal and `NO_COLOR` is not set
+    Auto,
+    /// Always colour, even if piped or `NO_COLOR` is set
+    Always,
+    /// Never colour
+    Never,
+}
+
+/// How to notify of devices added or removed between re-prints with [`crate::watch`], see [`crate::watch::notify_changes`]
+#[derive(Debug, ValueEnum, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Notify {
+    /// Ring the terminal bell
+    Bell,
+    /// Ring the terminal bell and send a desktop notification naming each device with `notify-send` on Linux or `osascript` on macOS
+    Desktop,
 }
 
 /// Passed to printing functions allows default args
@@ -1293,28 +2795,94 @@ pub enum MaskSerial {
 pub struct PrintSettings {
     /// Don't pad in order to align blocks
     pub no_padding: bool,
-    /// Print in decimal not base16
-    pub decimal: bool,
+    /// [`NumberCategory`]s to print in decimal not base16
+    pub number_format: NumberFormat,
     /// No tree printing
     pub tree: bool,
+    /// Character set to draw the tree with unless `ascii_tree`
+    pub tree_style: icon::TreeStyle,
+    /// Draw the tree with ascii characters regardless of `tree_style` and `icons`, see [`tree_icon`]
+    pub ascii_tree: bool,
+    /// Print only the device at this port path and its descendants, see [`print_device_path`]
+    pub root_path: Option<String>,
     /// Hide empty buses
     pub hide_buses: bool,
     /// Sort devices
     pub sort_devices: Sort,
-    /// Sort buses by bus number
-    pub sort_buses: bool,
+    /// Sort buses
+    pub sort_buses: BusSort,
+    /// Show sizes like endpoint max packet size with binary unit suffixes (B/KiB) rather than raw numbers
+    pub human_sizes: bool,
+    /// Sort interfaces by number then alternate setting rather than parse order
+    pub sort_interfaces: bool,
+    /// Sort endpoints by address rather than parse order
+    pub sort_endpoints: bool,
     /// Group devices
     pub group_devices: Group,
+    /// Order of the groups when printing with `group_devices`; None keeps vendors alphabetical with "Unknown" last and buses in `sort_buses` order. Not applicable to json where groups are object keys
+    pub group_sort: Option<GroupSort>,
     /// Print headings for blocks
     pub headings: bool,
+    /// Print the device heading only once at the top rather than for each group; bus headings are still printed per group
+    pub headings_once: bool,
+    /// Draw indent guides in the tree colour for configurations, interfaces and endpoints when not printing as a tree
+    pub indent_guides: bool,
+    /// Number of chars per level (configuration 1, interface 2, endpoint 3) verbose non-tree printing is indented; None for [`DEFAULT_INDENT_STEP`]
+    pub indent_step: Option<usize>,
+    /// Pad device blocks across all devices in the tree rather than only siblings at each depth so that columns are the same width at every depth
+    pub align_tree: bool,
+    /// Print raw descriptor bytes as offset annotated hex rows under each configuration, interface and endpoint
+    pub hex: bool,
+    /// Colour speed and power blocks with a gradient by their value rather than the flat theme colour
+    pub semantic_colours: bool,
+    /// Colour each segment of [`DeviceBlocks::PortPath`], [`DeviceBlocks::FullPath`] and [`DeviceBlocks::TreePositions`] by its depth, see [`colour_path_depths`]
+    pub rainbow_path: bool,
+    /// Show only the first alternate setting of each interface with a count of the hidden alternates; all are shown at maximum verbosity
+    pub collapse_alt_settings: bool,
+    /// Take [`DeviceBlocks::Name`], [`DeviceBlocks::Manufacturer`] and [`DeviceBlocks::Vendor`] from the usb_ids lookup before the descriptor strings, which are sometimes generic like 'USB DEVICE'
+    pub prefer_usb_ids: bool,
+    /// Show only interfaces of this class under each configuration, see [`prune_interfaces`]; the device row is still shown. Typically the filter class
+    pub prune_interfaces: Option<ClassCode>,
+    /// Write output to this file rather than stdout, see [`PrintSettings::output_writer`]
+    pub output: Option<PathBuf>,
+    /// Page output through `$PAGER` when stdout is a terminal, see [`PrintSettings::output_writer`]
+    pub pager: bool,
+    /// Notify when devices are added or removed between re-prints with [`crate::watch`]; None to not notify
+    pub notify: Option<Notify>,
+    /// Zero-pad each [`DeviceBlocks::TreePositions`] position to two digits like '01-02-03' so they align and sort lexically
+    pub pad_positions: bool,
     /// Level of verbosity
     pub verbosity: u8,
     /// Print more blocks by default
     pub more: bool,
+    /// Print a footer with the number of buses, devices and hubs after the devices
+    pub summary: bool,
+    /// Print only the number of flattened devices after filtering rather than any device output
+    pub count_only: bool,
+    /// Maximum depth of devices to print in a tree; deeper branches are replaced by an ellipsis. Does not apply to json/yaml
+    pub max_depth: Option<usize>,
+    /// Maximum number of devices to print: in total for flattened devices and per branch for a tree; the rest are counted on a line after them. Does not apply to json/yaml
+    pub limit: Option<usize>,
     /// Print as json
     pub json: bool,
+    /// Print as yaml
+    pub yaml: bool,
+    /// Print flattened devices as a self-contained HTML table with inline colour styles
+    pub html: bool,
+    /// Print flattened devices as newline-delimited json: one compact device object per line; not applicable to tree
+    pub ndjson: bool,
+    /// With `json`, always print the flat array of [`system_profiler::SPUSBDataType::flatten_devices`] rather than the nested tree with `tree` or groups with `group_devices`
+    pub flatten_json: bool,
+    /// With `json`, print minified JSON rather than pretty-printed
+    pub json_compact: bool,
     /// Scramble serial numbers, useful if sharing sensitive device dumps
     pub mask_serials: Option<MaskSerial>,
+    /// Number of chars kept at each end of a serial with [`MaskSerial::Partial`]; None for [`DEFAULT_MASK_VISIBLE`]
+    pub mask_serials_visible: Option<usize>,
+    /// Drop devices retained by the filter only because they are parents of a match; not applicable to tree
+    pub only_matching: bool,
+    /// Print a terse single line per device using [`DeviceBlocks::compact_blocks`] without padding, overriding `device_blocks`
+    pub compact: bool,
     /// [`DeviceBlocks`] to use for printing
     pub device_blocks: Option<Vec<DeviceBlocks>>,
     /// [`BusBlocks`] to use for printing
@@ -1325,26 +2893,377 @@ pub struct PrintSettings {
     pub interface_blocks: Option<Vec<InterfaceBlocks>>,
     /// [`EndpointBlocks`] to use for printing
     pub endpoint_blocks: Option<Vec<EndpointBlocks>>,
+    /// [`Alignment`] overrides for [`DeviceBlocks`]; blocks not present keep their default alignment
+    pub device_alignment: Option<HashMap<DeviceBlocks, Alignment>>,
+    /// [`Alignment`] overrides for [`BusBlocks`]
+    pub bus_alignment: Option<HashMap<BusBlocks, Alignment>>,
+    /// [`Alignment`] overrides for [`ConfigurationBlocks`]
+    pub config_alignment: Option<HashMap<ConfigurationBlocks, Alignment>>,
+    /// [`Alignment`] overrides for [`InterfaceBlocks`]
+    pub interface_alignment: Option<HashMap<InterfaceBlocks, Alignment>>,
+    /// [`Alignment`] overrides for [`EndpointBlocks`]
+    pub endpoint_alignment: Option<HashMap<EndpointBlocks, Alignment>>,
+    /// Only serialize these [`DeviceBlocks`] values for each device with json output, see [`project_device`]
+    pub json_fields: Option<Vec<DeviceBlocks>>,
+    /// Maximum display width for [`DeviceBlocks`]; longer values are truncated with '…'. Blocks not present are not truncated
+    pub max_widths: Option<HashMap<DeviceBlocks, usize>>,
+    /// Terminal width flattened device rows should fit within; least important [`DeviceBlocks::width_priority`] blocks are dropped until they do. None to never drop blocks
+    pub auto_width: Option<usize>,
     /// [`crate::icon::IconTheme`] to apply - None to not print any icons
     pub icons: Option<icon::IconTheme>,
     /// [`crate::colour::ColourTheme`] to apply - None to not colour
     pub colours: Option<colour::ColourTheme>,
 }
 
-/// Converts a HashSet of [`ConfigAttributes`] a String of nerd icons
-fn attributes_to_icons(attributes: &Vec<ConfigAttributes>, settings: &PrintSettings) -> String {
-    let mut icon_strs = Vec::new();
-    if settings.icons.is_some() {
-        for a in attributes {
-            match a {
-                ConfigAttributes::SelfPowered => icon_strs.push("\u{fba4}"), // ﮤ
-                ConfigAttributes::RemoteWakeup => icon_strs.push("\u{f654}"), // 
+impl PrintSettings {
+    /// Disables `colours` if `no_color`, the value of the `NO_COLOR` environment variable, is set to anything, see <https://no-color.org>
+    ///
+    /// ```
+    /// use std::env;
+    /// use cyme::display::PrintSettings;
+    ///
+    /// let mut settings = PrintSettings::default();
+    /// settings.honour_no_color(env::var_os("NO_COLOR").as_deref());
+    /// ```
+    pub fn honour_no_color(&mut self, no_color: Option<&OsStr>) {
+        if no_color.is_some() {
+            log::info!("NO_COLOR set, disabling colours");
+            self.colours = None;
+        }
+    }
+
+    /// Applies `when` to `colours`: [`ColourWhen::Auto`] disables colours if `NO_COLOR` is set, writing to an `output` file or stdout is not a terminal, [`ColourWhen::Always`] forces colours even when piped or written to a file and [`ColourWhen::Never`] disables them
+    ///
+    /// `html` colours are inline styles rather than escape codes so [`ColourWhen::Auto`] keeps them when not writing to a terminal
+    pub fn apply_colour_when(&mut self, when: ColourWhen) {
+        match when {
+            ColourWhen::Auto => {
+                self.honour_no_color(env::var_os("NO_COLOR").as_deref());
+                if self.html {
+                    log::debug!("html colours are inline styles, keeping colours");
+                } else if self.output.is_some() {
+                    log::info!("Writing to output file, disabling colours");
+                    self.colours = None;
+                } else if !io::stdout().is_terminal() {
+                    log::info!("stdout is not a terminal, disabling colours");
+                    self.colours = None;
+                }
+            }
+            ColourWhen::Always => colored::control::set_override(true),
+            ColourWhen::Never => {
+                colored::control::set_override(false);
+                self.colours = None;
             }
         }
     }
+
+    /// Writer to print to: the `output` file, created or truncated and buffered, if set; a [`PagerWriter`] if `pager` and stdout is a terminal; otherwise stdout
+    ///
+    /// Flush the writer once done since a buffered file writer discards errors when dropped. Falls back to stdout if the pager fails to spawn.
+    pub fn output_writer(&self) -> Result<Box<dyn io::Write>, io::Error> {
+        self.output_writer_with(env::var_os("PAGER").as_deref(), io::stdout().is_terminal())
+    }
+
+    /// [`PrintSettings::output_writer`] with the `pager` command, normally `$PAGER`, and whether stdout `is_terminal` passed in
+    pub fn output_writer_with(
+        &self,
+        pager: Option<&OsStr>,
+        is_terminal: bool,
+    ) -> Result<Box<dyn io::Write>, io::Error> {
+        match self.output.as_ref() {
+            Some(path) => Ok(Box::new(io::BufWriter::new(fs::File::create(path)?))),
+            None if self.pager && is_terminal => match PagerWriter::spawn(pager) {
+                Ok(pager) => Ok(Box::new(pager)),
+                Err(e) => {
+                    log::warn!("Failed to spawn pager, writing to stdout: Error({})", e);
+                    Ok(Box::new(io::stdout().lock()))
+                }
+            },
+            None => Ok(Box::new(io::stdout().lock())),
+        }
+    }
+}
+
+/// Writes to the stdin of a pager process; `$PAGER` or 'less -R' to keep colours
+///
+/// The pager stdin is closed and the process waited on when dropped so output can be browsed before exiting. Writes after the pager has quit are discarded rather than returning a broken pipe error.
+pub struct PagerWriter {
+    child: process::Child,
+}
+
+impl PagerWriter {
+    /// Spawn the `pager` with piped stdin, 'less -R' if `None` or empty; `pager` is split on whitespace into the command and its arguments
+    pub fn spawn(pager: Option<&OsStr>) -> Result<PagerWriter, io::Error> {
+        let pager = pager
+            .map(|p| p.to_string_lossy().into_owned())
+            .filter(|p| !p.trim().is_empty())
+            .unwrap_or(String::from("less -R"));
+        let mut parts = pager.split_whitespace();
+        let command = parts.next().unwrap_or("less");
+        log::info!("Paging output with {}", pager);
+        let child = process::Command::new(command)
+            .args(parts)
+            .stdin(process::Stdio::piped())
+            .spawn()?;
+
+        Ok(PagerWriter { child })
+    }
+}
+
+impl io::Write for PagerWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self.child.stdin.as_mut() {
+            Some(stdin) => match io::Write::write(stdin, buf) {
+                Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(buf.len()),
+                r => r,
+            },
+            None => Ok(buf.len()),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.child.stdin.as_mut() {
+            Some(stdin) => match io::Write::flush(stdin) {
+                Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+                r => r,
+            },
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for PagerWriter {
+    fn drop(&mut self) {
+        // closing stdin lets the pager know the output is complete
+        drop(self.child.stdin.take());
+        if let Err(e) = self.child.wait() {
+            log::warn!("Failed to wait for pager: Error({})", e);
+        }
+    }
+}
+
+/// Converts a HashSet of [`ConfigAttributes`] a String of nerd icons if `icons`, otherwise short ascii tags; used for padding
+fn attributes_icon_string(attributes: &Vec<ConfigAttributes>, icons: bool) -> String {
+    let mut icon_strs = Vec::new();
+    for a in attributes {
+        match a {
+            ConfigAttributes::SelfPowered if !icons => icon_strs.push("SP"),
+            ConfigAttributes::RemoteWakeup if !icons => icon_strs.push("RW"),
+            ConfigAttributes::SelfPowered => icon_strs.push("\u{fba4}"), // ﮤ
+            ConfigAttributes::RemoteWakeup => icon_strs.push("\u{f654}"), // 
+        }
+    }
     icon_strs.join(" ")
 }
 
+/// Converts a HashSet of [`ConfigAttributes`] a String of nerd icons; falls back to ascii tags 'SP' (self-powered) and 'RW' (remote wakeup) if icons are disabled
+fn attributes_to_icons(attributes: &Vec<ConfigAttributes>, settings: &PrintSettings) -> String {
+    attributes_icon_string(attributes, settings.icons.is_some())
+}
+
+/// Pads `s` with trailing spaces to `pad` using the unicode display width rather than char count, so wide chars and icons align
+fn pad_width<S: AsRef<str>>(s: S, pad: usize) -> String {
+    let s = s.as_ref();
+    format!("{}{}", s, " ".repeat(pad.saturating_sub(s.width())))
+}
+
+/// Truncates `s` to at most `max` display columns, replacing the end with '…' if it was cut; never splits a char
+fn truncate_width(s: String, max: usize) -> String {
+    if s.width() <= max {
+        return s;
+    }
+    let mut width = 0;
+    let mut ret = String::new();
+    for c in s.chars() {
+        let w = c.width().unwrap_or(0);
+        // leave a column for the ellipsis
+        if width + w + 1 > max {
+            break;
+        }
+        width += w;
+        ret.push(c);
+    }
+    if max > 0 {
+        ret.push('…');
+    }
+    ret
+}
+
+/// Joins `positions` with '-' like '1-2-3', zero-padding each to two digits like '01-02-03' if `zero_pad` so they align and sort lexically
+fn format_tree_positions(positions: &[u8], zero_pad: bool) -> String {
+    if zero_pad {
+        format!("{:02}", positions.iter().format("-"))
+    } else {
+        format!("{}", positions.iter().format("-"))
+    }
+}
+
+/// Caps `pad` widths to the [`PrintSettings::max_widths`] so truncated columns still align
+fn truncate_padding(
+    mut pad: HashMap<DeviceBlocks, usize>,
+    settings: &PrintSettings,
+) -> HashMap<DeviceBlocks, usize> {
+    if let Some(max_widths) = settings.max_widths.as_ref() {
+        for (b, w) in pad.iter_mut() {
+            if let Some(max) = max_widths.get(b) {
+                *w = (*w).min(*max);
+            }
+        }
+    }
+    pad
+}
+
+/// Minimum width of current blocks like [`DeviceBlocks::BusPower`]: '000 mA'
+const CURRENT_WIDTH: usize = 6;
+
+/// Formats `current` like '500 mA', or '-' if None; right-justified to at least [`CURRENT_WIDTH`] by [`render_value`] so that columns of present and absent values align
+fn format_current(current: Option<u32>) -> String {
+    current.map_or(String::from("-"), |v| format!("{} mA", v))
+}
+
+/// Padding for the formatted `currents` of a current block so that it fits the widest, at least [`CURRENT_WIDTH`] which is also wider than the headings
+fn current_padding<I: Iterator<Item = Option<String>>>(currents: I) -> usize {
+    currents
+        .map(|c| c.map_or(0, |c| c.width()))
+        .max()
+        .unwrap_or(0)
+        .max(CURRENT_WIDTH)
+}
+
+/// Writes `value` as JSON to `writer`; minified if `settings.json_compact` otherwise pretty-printed
+fn write_json<T: Serialize + ?Sized>(
+    writer: &mut impl io::Write,
+    value: &T,
+    settings: &PrintSettings,
+) -> Result<(), io::Error> {
+    if settings.json_compact {
+        serde_json::to_writer(writer, value)?;
+    } else {
+        serde_json::to_writer_pretty(writer, value)?;
+    }
+    Ok(())
+}
+
+/// Formats Unix `timestamp` as the largest whole unit of time elapsed since like '3m ago'
+fn format_age(timestamp: u64) -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    match now.saturating_sub(timestamp) {
+        s if s < 60 => format!("{}s ago", s),
+        s if s < 60 * 60 => format!("{}m ago", s / 60),
+        s if s < 60 * 60 * 24 => format!("{}h ago", s / (60 * 60)),
+        s => format!("{}d ago", s / (60 * 60 * 24)),
+    }
+}
+
+/// Pads `s` to `width` display columns using `alignment`; unchanged if already as wide
+fn align_width(s: String, width: usize, alignment: Alignment) -> String {
+    let space = width.saturating_sub(s.width());
+    match alignment {
+        Alignment::Left => pad_width(s, width),
+        Alignment::Right => format!("{}{}", " ".repeat(space), s),
+        Alignment::Center => format!(
+            "{}{}{}",
+            " ".repeat(space / 2),
+            s,
+            " ".repeat(space - space / 2)
+        ),
+    }
+}
+
+/// Value of block `b` from `d` truncated to [`Block::max_width`] then padded to [`Block::width`] with [`Block::alignment`], or [`Block::default_alignment`] if not overridden
+fn pad_value<B, T>(
+    b: &impl Block<B, T>,
+    d: &T,
+    pad: &HashMap<B, usize>,
+    settings: &PrintSettings,
+) -> Option<String> {
+    let string = b.format_value(d, pad, settings)?;
+    let width = b.width(pad, settings);
+    let (string, width) = match b.max_width(settings) {
+        Some(max) => (truncate_width(string, max), width.min(max)),
+        None => (string, width),
+    };
+    let alignment = b
+        .alignment(settings)
+        .unwrap_or_else(|| b.default_alignment(d));
+
+    Some(align_width(string, width, alignment))
+}
+
+/// Colour of the value `s` of block `b` from `d`: [`Block::semantic_colour`] if `settings.semantic_colours` and the block has one, otherwise [`Block::value_colour`] from the theme `ct`; None for a path coloured per segment if `settings.rainbow_path`
+fn block_colour<B, T>(
+    b: &impl Block<B, T>,
+    d: &T,
+    s: &String,
+    ct: &colour::ColourTheme,
+    settings: &PrintSettings,
+) -> Option<Color> {
+    if let Some(c) = b.semantic_colour(d).filter(|_| settings.semantic_colours) {
+        return Some(c);
+    }
+    match b.path_depth_offset().filter(|_| settings.rainbow_path) {
+        Some(_) => None,
+        None => b.value_colour(d, s, ct),
+    }
+}
+
+/// Colours the value `s` of block `b` from `d` with [`block_colour`] or a colour per path segment with [`colour_path_depths`] if `settings.rainbow_path` and the block is a path
+fn colour_block<B, T>(
+    b: &impl Block<B, T>,
+    d: &T,
+    s: &String,
+    ct: &colour::ColourTheme,
+    settings: &PrintSettings,
+) -> ColoredString {
+    match block_colour(b, d, s, ct, settings) {
+        Some(c) => s.color(c),
+        None => match b.path_depth_offset().filter(|_| settings.rainbow_path) {
+            Some(offset) => colour_path_depths(s, offset, ct).normal(),
+            None => s.normal(),
+        },
+    }
+}
+
+/// Colours each number segment of path `s` like '2-1.4.1' or '1-4-1' by its depth with the rotating [`colour::ColourTheme::depth_colours`] so that the segments of each hub level are the same colour; separators and padding are not coloured
+///
+/// `offset` is the depth of the first segment: 0 if it is the bus number so that ports at the same depth are coloured the same with or without the bus
+pub fn colour_path_depths(s: &str, offset: usize, ct: &colour::ColourTheme) -> String {
+    path_depth_segments(s, offset, &ct.depth_colours())
+        .into_iter()
+        .map(|(segment, c)| c.map_or(segment.to_string(), |c| segment.color(c).to_string()))
+        .collect()
+}
+
+/// Splits path `s` into number segments with their depth colour from the rotating `colours` and separators or padding without one, see [`colour_path_depths`]
+fn path_depth_segments(s: &str, offset: usize, colours: &[Color]) -> Vec<(String, Option<Color>)> {
+    if colours.is_empty() {
+        return vec![(s.to_string(), None)];
+    }
+
+    let mut ret = Vec::new();
+    let mut depth = offset;
+    let mut segment = String::new();
+    // trailing separator flushes the last segment
+    for c in s.chars().chain(std::iter::once(' ')) {
+        if c.is_ascii_digit() {
+            segment.push(c);
+            continue;
+        }
+        if !segment.is_empty() {
+            ret.push((segment.clone(), Some(colours[depth % colours.len()])));
+            segment.clear();
+            depth += 1;
+        }
+        ret.push((c.to_string(), None));
+    }
+    // drop the flushing separator
+    ret.pop();
+
+    ret
+}
+
 /// Formats each [`Block`] value shown from a device `d`
 pub fn render_value<B, T>(
     d: &T,
@@ -1354,9 +3273,9 @@ pub fn render_value<B, T>(
 ) -> Vec<String> {
     let mut ret = Vec::new();
     for b in blocks {
-        if let Some(string) = b.format_value(d, pad, settings) {
+        if let Some(string) = pad_value(b, d, pad, settings) {
             match &settings.colours {
-                Some(c) => ret.push(format!("{}", b.colour(&string, &c))),
+                Some(c) => ret.push(format!("{}", colour_block(b, d, &string, &c, settings))),
                 None => ret.push(format!("{}", string)),
             }
         }
@@ -1397,15 +3316,7 @@ fn generate_tree_data(
                 icon::Icon::TreeBlank
             };
 
-            format!(
-                "{}{}",
-                pass_tree.prefix,
-                settings
-                    .icons
-                    .as_ref()
-                    .map_or(icon::get_ascii_tree_icon(&edge_icon), |i| i
-                        .get_tree_icon(&edge_icon))
-            )
+            format!("{}{}", pass_tree.prefix, tree_icon(&edge_icon, settings))
         } else {
             format!("{}", pass_tree.prefix)
         };
@@ -1418,106 +3329,525 @@ fn generate_tree_data(
     return pass_tree;
 }
 
-/// Print `devices` `USBDevice` references without looking down each device's devices!
-pub fn print_flattened_devices(
+/// Render `devices` `USBDevice` references without looking down each device's devices!
+///
+/// Returns lines exactly as [`print_flattened_devices`] would print them
+pub fn render_flattened_devices(
     devices: &Vec<&system_profiler::USBDevice>,
     settings: &PrintSettings,
-) {
-    let db = settings
-        .device_blocks
-        .to_owned()
-        .unwrap_or(DeviceBlocks::default_blocks(
-            settings.verbosity >= MAX_VERBOSITY || settings.more,
-        ));
-    let pad = if !settings.no_padding {
-        DeviceBlocks::generate_padding(devices)
-    } else {
-        HashMap::new()
-    };
-    log::trace!("Flattened devices padding {:?}", pad);
+) -> Vec<String> {
+    iter_flattened_devices(devices, settings).collect()
+}
 
-    let sorted = settings.sort_devices.sort_devices_ref(&devices);
+/// Streams the lines [`render_flattened_devices`] would return, rendering one device at a time so that a consumer can write them out incrementally
+///
+/// Padding and sorting still need every device up front but lines are only rendered as the iterator is advanced
+///
+/// ```
+/// use cyme::display::{iter_flattened_devices, render_flattened_devices, PrintSettings};
+/// use cyme::system_profiler::read_json_dump;
+///
+/// let sp_usb = read_json_dump("./tests/data/cyme_libusb_linux_tree.json").unwrap();
+/// let devices = sp_usb.flatten_devices();
+/// let settings = PrintSettings::default();
+/// let lines: Vec<String> = iter_flattened_devices(&devices, &settings).collect();
+/// assert_eq!(lines, render_flattened_devices(&devices, &settings));
+/// ```
+pub fn iter_flattened_devices<'a>(
+    devices: &Vec<&'a system_profiler::USBDevice>,
+    settings: &'a PrintSettings,
+) -> impl Iterator<Item = String> + 'a {
+    let pad = flattened_device_padding(devices, settings);
+    let db = fit_device_blocks(flattened_device_blocks(settings), devices, &pad, settings);
+    log::trace!("Flattened devices padding {:?}", pad);
 
-    if settings.headings {
-        let heading = render_heading(&db, &pad).join(" ");
-        println!("{}", heading.bold().underline());
-    }
+    FlattenedDeviceLines::new(devices, db, pad, settings.headings, settings)
+}
 
-    for (i, device) in sorted.into_iter().enumerate() {
-        println!("{}", render_value(device, &db, &pad, settings).join(" "));
-        // print the configurations
-        if let Some(extra) = device.extra.as_ref() {
-            if settings.verbosity >= 1 {
-                let blocks = (
-                    &settings.config_blocks.to_owned().unwrap_or(Block::<
-                        ConfigurationBlocks,
-                        USBConfiguration,
-                    >::default_blocks(
-                        settings.verbosity >= MAX_VERBOSITY || settings.more,
-                    )),
-                    &settings.interface_blocks.to_owned().unwrap_or(Block::<
-                        InterfaceBlocks,
-                        USBInterface,
-                    >::default_blocks(
-                        settings.verbosity >= MAX_VERBOSITY || settings.more,
-                    )),
-                    &settings.endpoint_blocks.to_owned().unwrap_or(Block::<
-                        EndpointBlocks,
-                        USBEndpoint,
-                    >::default_blocks(
-                        settings.verbosity >= MAX_VERBOSITY || settings.more,
-                    )),
-                );
-                // pass branch length as number of configurations for this device plus devices still to print
-                print_configurations(
-                    &extra.configurations,
-                    blocks,
-                    settings,
-                    &generate_tree_data(
-                        &Default::default(),
-                        extra.configurations.len() + device.devices.as_ref().map_or(0, |d| d.len()),
-                        i,
-                        settings,
-                    ),
-                );
-            }
-        } else if settings.verbosity >= 1 {
-            log::warn!(
-                "Unable to print verbose information for {} because libusb extra data is missing",
-                device
-            )
-        }
+/// [`DeviceBlocks`] used for flattened device rendering with `settings`
+fn flattened_device_blocks(settings: &PrintSettings) -> Vec<DeviceBlocks> {
+    if settings.compact {
+        DeviceBlocks::compact_blocks()
+    } else {
+        settings
+            .device_blocks
+            .to_owned()
+            .unwrap_or(DeviceBlocks::default_blocks(
+                settings.verbosity >= MAX_VERBOSITY || settings.more,
+            ))
     }
 }
 
-/// A way of printing a reference flattened `SPUSBDataType` rather than hard flatten
+/// Drops the least important blocks from `db` until the widest row of `devices` padded with `pad` fits within [`PrintSettings::auto_width`]; `db` unchanged if None
 ///
-/// Prints each `&USBBus` and tuple pair `Vec<&USBDevice>`
-pub fn print_bus_grouped(
-    bus_devices: Vec<(&system_profiler::USBBus, Vec<&system_profiler::USBDevice>)>,
+/// Blocks without a `pad` entry, such as fixed width values, are measured by their widest value and by their heading if headings are shown
+fn fit_device_blocks(
+    mut db: Vec<DeviceBlocks>,
+    devices: &Vec<&system_profiler::USBDevice>,
+    pad: &HashMap<DeviceBlocks, usize>,
     settings: &PrintSettings,
-) {
-    let bb = settings.bus_blocks.to_owned().unwrap_or(
-        Block::<BusBlocks, system_profiler::USBBus>::default_blocks(
-            settings.verbosity >= MAX_VERBOSITY || settings.more,
-        ),
-    );
-    let pad: HashMap<BusBlocks, usize> = if !settings.no_padding {
-        BusBlocks::generate_padding(&bus_devices.iter().map(|bd| bd.0).collect())
-    } else {
+) -> Vec<DeviceBlocks> {
+    let max_width = match settings.auto_width {
+        Some(w) => w,
+        None => return db,
+    };
+    let priority = DeviceBlocks::width_priority();
+    let widths: HashMap<DeviceBlocks, usize> = db
+        .iter()
+        .map(|b| {
+            let value = pad.get(b).copied().unwrap_or_else(|| {
+                devices
+                    .iter()
+                    .filter_map(|d| b.format_value(d, pad, settings))
+                    .map(|v| v.width())
+                    .max()
+                    .unwrap_or(0)
+                    .max(b.width(pad, settings))
+            });
+            let width = if settings.headings {
+                value.max(b.heading(pad).width())
+            } else {
+                value
+            };
+            (b.clone(), width)
+        })
+        .collect();
+    // blocks are joined with a single space
+    let row_width = |db: &Vec<DeviceBlocks>| {
+        db.iter()
+            .map(|b| widths.get(b).copied().unwrap_or(0))
+            .sum::<usize>()
+            + db.len().saturating_sub(1)
+    };
+
+    while db.len() > 1 && row_width(&db) > max_width {
+        let drop = db
+            .iter()
+            .enumerate()
+            .max_by_key(|(i, b)| {
+                (
+                    priority.iter().position(|p| p == *b).unwrap_or(usize::MAX),
+                    *i,
+                )
+            })
+            .map(|(i, _)| i)
+            .unwrap();
+        log::debug!(
+            "Dropping {:?} to fit terminal width {}",
+            db[drop],
+            max_width
+        );
+        db.remove(drop);
+    }
+
+    db
+}
+
+/// Padding for flattened `devices`; empty if padding is disabled in `settings`
+fn flattened_device_padding(
+    devices: &Vec<&system_profiler::USBDevice>,
+    settings: &PrintSettings,
+) -> HashMap<DeviceBlocks, usize> {
+    if !settings.no_padding && !settings.compact {
+        let mut pad = DeviceBlocks::generate_padding(devices);
+        if settings.pad_positions {
+            let width = devices
+                .iter()
+                .map(|d| format_tree_positions(&d.location_id.tree_positions, true).width())
+                .max()
+                .unwrap_or(0);
+            pad.entry(DeviceBlocks::TreePositions)
+                .and_modify(|w| *w = cmp::max(*w, width));
+        }
+        // names are taken from usb_ids instead so widths differ from the descriptor strings
+        if settings.prefer_usb_ids {
+            let widths: [(DeviceBlocks, fn(&system_profiler::USBDevice) -> usize); 3] = [
+                (DeviceBlocks::Name, |d| preferred_name(d, true).width()),
+                (DeviceBlocks::Manufacturer, |d| {
+                    preferred_manufacturer(d, true).map_or(0, |m| m.width())
+                }),
+                (DeviceBlocks::Vendor, |d| {
+                    preferred_vendor(d, true).map_or(0, |v| v.width())
+                }),
+            ];
+            for (block, width) in widths {
+                pad.insert(
+                    block.to_owned(),
+                    cmp::max(
+                        block.heading(&Default::default()).width(),
+                        devices.iter().map(|d| width(d)).max().unwrap_or(0),
+                    ),
+                );
+            }
+        }
+        truncate_padding(pad, settings)
+    } else {
+        HashMap::new()
+    }
+}
+
+/// Renders `devices` with given blocks `db` and `pad`; heading only rendered if `headings`
+fn render_flattened_devices_with(
+    devices: &Vec<&system_profiler::USBDevice>,
+    db: &Vec<DeviceBlocks>,
+    pad: &HashMap<DeviceBlocks, usize>,
+    headings: bool,
+    settings: &PrintSettings,
+) -> Vec<String> {
+    FlattenedDeviceLines::new(devices, db.to_owned(), pad.to_owned(), headings, settings).collect()
+}
+
+/// Renders the lines of a single flattened `device` at index `i`: its blocks `db` and configurations if verbose
+fn render_flattened_device(
+    device: &system_profiler::USBDevice,
+    i: usize,
+    db: &Vec<DeviceBlocks>,
+    pad: &HashMap<DeviceBlocks, usize>,
+    settings: &PrintSettings,
+) -> Vec<String> {
+    let mut ret = vec![render_value(device, db, pad, settings).join(" ")];
+    // render the configurations
+    if let Some(extra) = device.extra.as_ref() {
+        if settings.verbosity >= 1 {
+            let blocks = (
+                &settings.config_blocks.to_owned().unwrap_or(Block::<
+                    ConfigurationBlocks,
+                    USBConfiguration,
+                >::default_blocks(
+                    settings.verbosity >= MAX_VERBOSITY || settings.more,
+                )),
+                &settings.interface_blocks.to_owned().unwrap_or(Block::<
+                    InterfaceBlocks,
+                    USBInterface,
+                >::default_blocks(
+                    settings.verbosity >= MAX_VERBOSITY || settings.more,
+                )),
+                &settings.endpoint_blocks.to_owned().unwrap_or(
+                    Block::<EndpointBlocks, USBEndpoint>::default_blocks(
+                        settings.verbosity >= MAX_VERBOSITY || settings.more,
+                    ),
+                ),
+            );
+            // pass branch length as number of configurations for this device plus devices still to print
+            ret.extend(join_tree_lines(render_configurations(
+                &extra.configurations,
+                blocks,
+                settings,
+                &generate_tree_data(
+                    &Default::default(),
+                    extra.configurations.len() + device.devices.as_ref().map_or(0, |d| d.len()),
+                    i,
+                    settings,
+                ),
+            )));
+        }
+    } else if settings.verbosity >= 1 {
+        log::warn!(
+            "Unable to print verbose information for {} because libusb extra data is missing",
+            device
+        )
+    }
+
+    ret
+}
+
+/// Lazily renders flattened devices one device at a time, see [`iter_flattened_devices`]
+struct FlattenedDeviceLines<'a> {
+    devices: std::iter::Enumerate<std::vec::IntoIter<&'a system_profiler::USBDevice>>,
+    db: Vec<DeviceBlocks>,
+    pad: HashMap<DeviceBlocks, usize>,
+    settings: &'a PrintSettings,
+    /// Devices past `settings.limit` still to be counted
+    more: usize,
+    /// Rendered lines of the current device not yet returned
+    pending: VecDeque<String>,
+}
+
+impl<'a> FlattenedDeviceLines<'a> {
+    fn new(
+        devices: &Vec<&'a system_profiler::USBDevice>,
+        db: Vec<DeviceBlocks>,
+        pad: HashMap<DeviceBlocks, usize>,
+        headings: bool,
+        settings: &'a PrintSettings,
+    ) -> Self {
+        let mut sorted = settings.sort_devices.sort_devices_ref(devices);
+        let more = limit_overflow(sorted.len(), settings);
+        sorted.truncate(sorted.len() - more);
+
+        let mut pending = VecDeque::new();
+        if headings {
+            let heading = render_heading(&db, &pad).join(" ");
+            pending.push_back(heading.bold().underline().to_string());
+        }
+
+        FlattenedDeviceLines {
+            devices: sorted.into_iter().enumerate(),
+            db,
+            pad,
+            settings,
+            more,
+            pending,
+        }
+    }
+}
+
+impl<'a> Iterator for FlattenedDeviceLines<'a> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        loop {
+            if let Some(line) = self.pending.pop_front() {
+                return Some(line);
+            }
+
+            match self.devices.next() {
+                Some((i, device)) => self.pending.extend(render_flattened_device(
+                    device,
+                    i,
+                    &self.db,
+                    &self.pad,
+                    self.settings,
+                )),
+                None if self.more > 0 => {
+                    let more = std::mem::take(&mut self.more);
+                    return Some(render_more_devices(more, self.settings));
+                }
+                None => return None,
+            }
+        }
+    }
+}
+
+/// Print `devices` `USBDevice` references without looking down each device's devices!
+pub fn print_flattened_devices(
+    devices: &Vec<&system_profiler::USBDevice>,
+    settings: &PrintSettings,
+) {
+    for line in iter_flattened_devices(devices, settings) {
+        println!("{}", line);
+    }
+}
+
+/// A way of rendering a reference flattened `SPUSBDataType` rather than hard flatten
+///
+/// Renders each `&USBBus` and tuple pair `Vec<&USBDevice>`, returning lines exactly as [`print_bus_grouped`] would print them
+pub fn render_bus_grouped(
+    bus_devices: Vec<(&system_profiler::USBBus, Vec<&system_profiler::USBDevice>)>,
+    settings: &PrintSettings,
+) -> Vec<String> {
+    let mut ret = Vec::new();
+    let bb = settings
+        .bus_blocks
+        .to_owned()
+        .unwrap_or_else(|| BusBlocks::default_blocks_for(settings));
+    let pad: HashMap<BusBlocks, usize> = if !settings.no_padding {
+        BusBlocks::generate_padding(&bus_devices.iter().map(|bd| bd.0).collect())
+    } else {
         HashMap::new()
     };
 
+    // device heading once at top with padding across all buses so that it lines up with every group
+    let device_heading_once = if settings.headings && settings.headings_once {
+        let all_devices = bus_devices
+            .iter()
+            .flat_map(|bd| bd.1.iter().copied())
+            .collect();
+        let dpad = flattened_device_padding(&all_devices, settings);
+        let db = fit_device_blocks(
+            flattened_device_blocks(settings),
+            &all_devices,
+            &dpad,
+            settings,
+        );
+        ret.push(
+            render_heading(&db, &dpad)
+                .join(" ")
+                .bold()
+                .underline()
+                .to_string(),
+        );
+        Some((db, dpad))
+    } else {
+        None
+    };
+
     for (bus, devices) in bus_devices {
         if settings.headings {
             let heading = render_heading(&bb, &pad).join(" ");
-            println!("{}", heading.bold().underline());
+            ret.push(heading.bold().underline().to_string());
+        }
+        ret.push(render_value(bus, &bb, &pad, settings).join(" "));
+        match device_heading_once.as_ref() {
+            Some((db, dpad)) => ret.extend(render_flattened_devices_with(
+                &devices, db, dpad, false, settings,
+            )),
+            None => ret.extend(render_flattened_devices(&devices, settings)),
         }
-        println!("{}", render_value(bus, &bb, &pad, settings).join(" "));
-        print_flattened_devices(&devices, settings);
         // new line for each group
-        println!();
+        ret.push(String::new());
+    }
+
+    ret
+}
+
+/// A way of printing a reference flattened `SPUSBDataType` rather than hard flatten
+///
+/// Prints each `&USBBus` and tuple pair `Vec<&USBDevice>`
+pub fn print_bus_grouped(
+    bus_devices: Vec<(&system_profiler::USBBus, Vec<&system_profiler::USBDevice>)>,
+    settings: &PrintSettings,
+) {
+    for line in render_bus_grouped(bus_devices, settings) {
+        println!("{}", line);
+    }
+}
+
+/// Name to group `device` under for [`Group::Vendor`]: usb_ids vendor name, then manufacturer, then hex VID; `None` if none are known
+pub fn vendor_group_name(device: &system_profiler::USBDevice) -> Option<String> {
+    device
+        .extra
+        .as_ref()
+        .and_then(|e| e.vendor.to_owned())
+        .filter(|v| !v.trim().is_empty())
+        .or_else(|| {
+            device
+                .manufacturer
+                .to_owned()
+                .filter(|m| !m.trim().is_empty())
+        })
+        .or_else(|| device.vendor_id.map(|v| format!("0x{:04x}", v)))
+}
+
+/// Name for [`DeviceBlocks::Name`]: the descriptor name unless `prefer_usb_ids` and there is a usb_ids product name
+fn preferred_name(device: &system_profiler::USBDevice, prefer_usb_ids: bool) -> String {
+    device
+        .extra
+        .as_ref()
+        .and_then(|e| e.product_name.to_owned())
+        .filter(|p| prefer_usb_ids && !p.trim().is_empty())
+        .unwrap_or_else(|| device.name.to_owned())
+}
+
+/// udev tags of `device` joined with ',' for [`DeviceBlocks::Tags`]; None if there are none
+fn tags_string(device: &system_profiler::USBDevice) -> Option<String> {
+    device
+        .extra
+        .as_ref()
+        .and_then(|e| e.tags.as_ref())
+        .filter(|t| !t.is_empty())
+        .map(|t| t.join(","))
+}
+
+/// Source of the name shown by [`DeviceBlocks::Name`]: usb_ids if `prefer_usb_ids` picked the usb_ids product name, otherwise the recorded `name_source`
+fn shown_name_source(
+    device: &system_profiler::USBDevice,
+    prefer_usb_ids: bool,
+) -> Option<system_profiler::NameSource> {
+    let from_usb_ids = prefer_usb_ids
+        && device
+            .extra
+            .as_ref()
+            .and_then(|e| e.product_name.as_ref())
+            .map_or(false, |p| !p.trim().is_empty());
+    if from_usb_ids {
+        Some(system_profiler::NameSource::UsbIds)
+    } else {
+        device.name_source
+    }
+}
+
+/// Manufacturer for [`DeviceBlocks::Manufacturer`]: the descriptor manufacturer, or usb_ids vendor name first falling back to it if `prefer_usb_ids`
+fn preferred_manufacturer(
+    device: &system_profiler::USBDevice,
+    prefer_usb_ids: bool,
+) -> Option<String> {
+    if prefer_usb_ids {
+        usb_ids_vendor(device).or_else(|| device.manufacturer.to_owned())
+    } else {
+        device.manufacturer.to_owned()
+    }
+}
+
+/// Vendor name from the usb_ids lookup if known and not blank
+fn usb_ids_vendor(device: &system_profiler::USBDevice) -> Option<String> {
+    device
+        .extra
+        .as_ref()
+        .and_then(|e| e.vendor.to_owned())
+        .filter(|v| !v.trim().is_empty())
+}
+
+/// Vendor for [`DeviceBlocks::Vendor`]: descriptor manufacturer, then usb_ids vendor name, then hex VID; `None` if none are known
+///
+/// The descriptor and usb_ids are tried the other way around if `prefer_usb_ids`
+fn preferred_vendor(device: &system_profiler::USBDevice, prefer_usb_ids: bool) -> Option<String> {
+    let descriptor = device
+        .manufacturer
+        .to_owned()
+        .filter(|m| !m.trim().is_empty());
+    if prefer_usb_ids {
+        usb_ids_vendor(device).or(descriptor)
+    } else {
+        descriptor.or_else(|| usb_ids_vendor(device))
+    }
+    .or_else(|| device.vendor_id.map(|v| format!("0x{:04x}", v)))
+}
+
+/// Renders `devices` bucketed by [`vendor_group_name`] with a vendor heading before each group
+///
+/// Groups are sorted alphabetically by vendor with devices without a vendor grouped under "Unknown" last unless `settings.group_sort`. Devices within a group are sorted by `settings.sort_devices`.
+pub fn render_vendor_grouped(
+    devices: Vec<&system_profiler::USBDevice>,
+    settings: &PrintSettings,
+) -> Vec<String> {
+    let mut ret = Vec::new();
+    let mut groups = vendor_groups(devices);
+    if let Some(group_sort) = settings.group_sort {
+        group_sort.sort_groups(&mut groups);
+    }
+
+    for (name, devices) in groups {
+        let heading = match settings.colours.as_ref() {
+            Some(ct) => ct
+                .manufacturer
+                .map_or(name.normal(), |c| name.color(c))
+                .bold()
+                .to_string(),
+            None => name.bold().to_string(),
+        };
+        ret.push(heading);
+        ret.extend(render_flattened_devices(&devices, settings));
+        // new line for each group
+        ret.push(String::new());
+    }
+
+    ret
+}
+
+/// Buckets `devices` by [`vendor_group_name`], sorted alphabetically by vendor with devices without a vendor under "Unknown" last
+fn vendor_groups(
+    devices: Vec<&system_profiler::USBDevice>,
+) -> Vec<(String, Vec<&system_profiler::USBDevice>)> {
+    let mut groups: Vec<(String, Vec<&system_profiler::USBDevice>)> = Vec::new();
+    let mut unknown = Vec::new();
+
+    for device in devices {
+        match vendor_group_name(device) {
+            Some(name) => match groups.iter_mut().find(|(n, _)| *n == name) {
+                Some((_, g)) => g.push(device),
+                None => groups.push((name, vec![device])),
+            },
+            None => unknown.push(device),
+        }
+    }
+
+    groups.sort_by_key(|(n, _)| n.to_lowercase());
+    if !unknown.is_empty() {
+        groups.push((String::from("Unknown"), unknown));
     }
+
+    groups
 }
 
 /// Passed to print functions to support tree building
@@ -1533,13 +3863,135 @@ pub struct TreeData {
     prefix: String,
 }
 
-/// All device [`USBEndpoint`]
-pub fn print_endpoints(
+/// Joins `(prefix, line)` pairs returned by the tree render functions into the lines that would be printed
+pub fn join_tree_lines(lines: Vec<(String, String)>) -> Vec<String> {
+    lines
+        .into_iter()
+        .map(|(prefix, line)| format!("{}{}", prefix, line))
+        .collect()
+}
+
+/// Tree connector for `icon`: ascii if `settings.ascii_tree`, otherwise user `settings.icons` tree overrides then `settings.tree_style`
+///
+/// Independent of whether device icons are shown so that icons can be used with an ascii tree and vice versa
+pub fn tree_icon(icon: &icon::Icon, settings: &PrintSettings) -> String {
+    if settings.ascii_tree {
+        icon::get_ascii_tree_icon(icon)
+    } else {
+        settings
+            .icons
+            .as_ref()
+            .map_or(icon::get_style_tree_icon(icon, &settings.tree_style), |i| {
+                i.get_tree_icon(icon, &settings.tree_style)
+            })
+    }
+}
+
+/// Gets the indentation for non-tree verbose printing at `level` (configuration 1, interface 2, endpoint 3) of `settings.indent_step` chars per level; a tree line guide per level if `settings.indent_guides`, otherwise spaces
+fn render_indent(level: usize, settings: &PrintSettings) -> String {
+    let step = settings.indent_step.unwrap_or(DEFAULT_INDENT_STEP);
+    if !settings.indent_guides || step == 0 {
+        return " ".repeat(level * step);
+    }
+
+    let line = tree_icon(&icon::Icon::TreeLine, settings);
+    let guide = pad_width(line.chars().next().unwrap_or(' ').to_string(), step).repeat(level);
+
+    match settings.colours.as_ref() {
+        Some(ct) => ct
+            .tree
+            .map_or(guide.normal(), |c| guide.color(c))
+            .to_string(),
+        None => guide,
+    }
+}
+
+/// Render the `raw` descriptor bytes of item `index` in the current `tree` branch as `(prefix, line)` hex rows, or a note that they were not captured
+///
+/// Rows are indented one `level` (configuration 1, interface 2, endpoint 3) further than the item when not a tree
+fn render_hex(
+    raw: &Option<Vec<u8>>,
+    tree: &TreeData,
+    index: usize,
+    level: usize,
+    settings: &PrintSettings,
+) -> Vec<(String, String)> {
+    let prefix = if settings.tree {
+        let prefix = format!("{}  ", generate_tree_data(tree, 0, index, settings).prefix);
+        match settings.colours.as_ref() {
+            Some(ct) => ct
+                .tree
+                .map_or(prefix.normal(), |c| prefix.color(c))
+                .to_string(),
+            None => prefix,
+        }
+    } else {
+        render_indent(level + 1, settings)
+    };
+
+    match raw {
+        Some(bytes) => hex_dump_lines(bytes)
+            .into_iter()
+            .map(|l| (prefix.clone(), l))
+            .collect(),
+        None => vec![(prefix, HEX_UNAVAILABLE.to_string())],
+    }
+}
+
+/// Gets the tree prefix for item `index` in the current `tree` branch and the `terminator` icon, both coloured with `colour` from the theme
+fn render_tree_prefix(
+    tree: &TreeData,
+    index: usize,
+    terminator: &icon::Icon,
+    colour: fn(&colour::ColourTheme) -> Option<Color>,
+    settings: &PrintSettings,
+) -> (String, String) {
+    let mut prefix = if tree.depth > 0 {
+        let edge_icon = if index + 1 != tree.branch_length {
+            icon::Icon::TreeEdge
+        } else {
+            icon::Icon::TreeCorner
+        };
+        let edge = tree_icon(&edge_icon, settings);
+        format!("{}{}", tree.prefix, edge)
+    // zero depth
+    } else {
+        format!("{}", tree.prefix)
+    };
+
+    let mut terminator = tree_icon(terminator, settings);
+
+    // colour tree
+    if let Some(ct) = settings.colours.as_ref() {
+        prefix = ct
+            .tree
+            .map_or(prefix.normal(), |c| prefix.color(c))
+            .to_string();
+        terminator = colour(ct)
+            .map_or(terminator.normal(), |c| terminator.color(c))
+            .to_string();
+    }
+
+    (prefix, terminator)
+}
+
+/// Render all device [`USBEndpoint`] as `(prefix, line)` pairs; prefix is the tree or indentation
+pub fn render_endpoints(
     endpoints: &Vec<USBEndpoint>,
     blocks: &Vec<EndpointBlocks>,
     settings: &PrintSettings,
     tree: &TreeData,
-) {
+) -> Vec<(String, String)> {
+    let mut ret = Vec::new();
+    let sorted;
+    let endpoints = if settings.sort_endpoints {
+        let mut s = endpoints.to_owned();
+        s.sort_by_key(|e| e.address.address);
+        sorted = s;
+        &sorted
+    } else {
+        endpoints
+    };
     let pad = if !settings.no_padding {
         EndpointBlocks::generate_padding(&endpoints.iter().map(|d| d).collect())
     } else {
@@ -1550,81 +4002,91 @@ pub fn print_endpoints(
     for (i, endpoint) in endpoints.iter().enumerate() {
         // get current prefix based on if last in tree and whether we are within the tree
         if settings.tree {
-            let mut prefix = if tree.depth > 0 {
-                let edge_icon = if i + 1 != tree.branch_length {
-                    icon::Icon::TreeEdge
+            let (prefix, terminator) = render_tree_prefix(
+                tree,
+                i,
+                &icon::Icon::Endpoint(endpoint.address.direction),
+                if endpoint.address.direction == Direction::In {
+                    |ct| ct.tree_endpoint_in
                 } else {
-                    icon::Icon::TreeCorner
-                };
-                let edge = settings
-                    .icons
-                    .as_ref()
-                    .map_or(icon::get_ascii_tree_icon(&edge_icon), |i| {
-                        i.get_tree_icon(&edge_icon)
-                    });
-                format!("{}{}", tree.prefix, edge)
-            // zero depth
-            } else {
-                format!("{}", tree.prefix)
-            };
-
-            let mut terminator = settings.icons.as_ref().map_or(
-                icon::get_ascii_tree_icon(&icon::Icon::Endpoint(endpoint.address.direction)),
-                |i| i.get_tree_icon(&icon::Icon::Endpoint(endpoint.address.direction)),
+                    |ct| ct.tree_endpoint_out
+                },
+                settings,
             );
 
-            // colour tree
-            if let Some(ct) = settings.colours.as_ref() {
-                prefix = ct
-                    .tree
-                    .map_or(prefix.normal(), |c| prefix.color(c))
-                    .to_string();
-                terminator = if endpoint.address.direction == Direction::In {
-                    ct.tree_endpoint_in
-                        .map_or(terminator.normal(), |c| terminator.color(c))
-                        .to_string()
-                } else {
-                    ct.tree_endpoint_out
-                        .map_or(terminator.normal(), |c| terminator.color(c))
-                        .to_string()
-                };
-            }
-
             // maybe should just do once at start of bus
             if settings.headings && i == 0 {
                 let heading = render_heading(&blocks, &pad).join(" ");
-                println!("{}  {}", prefix, heading.bold().underline());
+                ret.push((
+                    format!("{}  ", prefix),
+                    heading.bold().underline().to_string(),
+                ));
             }
 
-            // render and print tree if doing it
-            print!("{}{} ", prefix, terminator);
-            println!(
-                "{}",
-                render_value(endpoint, blocks, &pad, settings).join(" ")
-            );
+            // render tree if doing it
+            ret.push((
+                format!("{}{} ", prefix, terminator),
+                render_value(endpoint, blocks, &pad, settings).join(" "),
+            ));
         } else {
             if settings.headings && i == 0 {
                 let heading = render_heading(blocks, &pad).join(" ");
-                println!("{:spaces$}{}", "", heading.bold().underline(), spaces = 6);
+                ret.push((
+                    render_indent(3, settings),
+                    heading.bold().underline().to_string(),
+                ));
             }
 
-            println!(
-                "{:spaces$}{}",
-                "",
+            ret.push((
+                render_indent(3, settings),
                 render_value(endpoint, &blocks, &pad, settings).join(" "),
-                spaces = 6
-            );
+            ));
+        }
+
+        if settings.hex {
+            ret.extend(render_hex(&endpoint.raw, tree, i, 3, settings));
         }
     }
+
+    ret
 }
 
-/// All device [`USBInterface`]
-pub fn print_interfaces(
+/// All device [`USBEndpoint`]
+pub fn print_endpoints(
+    endpoints: &Vec<USBEndpoint>,
+    blocks: &Vec<EndpointBlocks>,
+    settings: &PrintSettings,
+    tree: &TreeData,
+) {
+    for line in join_tree_lines(render_endpoints(endpoints, blocks, settings, tree)) {
+        println!("{}", line);
+    }
+}
+
+/// Render all device [`USBInterface`] and their endpoints as `(prefix, line)` pairs; prefix is the tree or indentation
+pub fn render_interfaces(
     interfaces: &Vec<USBInterface>,
     blocks: (&Vec<InterfaceBlocks>, &Vec<EndpointBlocks>),
     settings: &PrintSettings,
     tree: &TreeData,
-) {
+) -> Vec<(String, String)> {
+    let mut ret = Vec::new();
+    let sorted;
+    let all = if settings.sort_interfaces {
+        let mut s = interfaces.to_owned();
+        s.sort_by_key(|i| (i.number, i.alt_setting));
+        sorted = s;
+        &sorted
+    } else {
+        interfaces
+    };
+    let collapsed;
+    let interfaces = if collapses_alt_settings(settings) {
+        collapsed = first_alt_settings(all);
+        &collapsed
+    } else {
+        all
+    };
     let pad = if !settings.no_padding {
         InterfaceBlocks::generate_padding(&interfaces.iter().map(|d| d).collect())
     } else {
@@ -1633,93 +4095,139 @@ pub fn print_interfaces(
     log::trace!("Print interfaces padding {:?}, tree {:?}", pad, tree);
 
     for (i, interface) in interfaces.iter().enumerate() {
-        // get current prefix based on if last in tree and whether we are within the tree
-        if settings.tree {
-            let mut prefix = if tree.depth > 0 {
-                let edge_icon = if i + 1 != tree.branch_length {
-                    icon::Icon::TreeEdge
-                } else {
-                    icon::Icon::TreeCorner
+        let mut value = render_value(interface, &blocks.0, &pad, settings).join(" ");
+        if collapses_alt_settings(settings) {
+            let hidden = all.iter().filter(|s| s.number == interface.number).count() - 1;
+            if hidden > 0 {
+                let count = format!("(+{} alt)", hidden);
+                let count = match settings.colours.as_ref() {
+                    Some(ct) => ct
+                        .number
+                        .map_or(count.normal(), |c| count.color(c))
+                        .to_string(),
+                    None => count,
                 };
-                let edge = settings
-                    .icons
-                    .as_ref()
-                    .map_or(icon::get_ascii_tree_icon(&edge_icon), |i| {
-                        i.get_tree_icon(&edge_icon)
-                    });
-                format!("{}{}", tree.prefix, edge)
-            // zero depth
-            } else {
-                format!("{}", tree.prefix)
-            };
+                value = format!("{} {}", value, count);
+            }
+        }
 
-            let mut terminator = settings.icons.as_ref().map_or(
-                icon::get_ascii_tree_icon(&icon::Icon::TreeInterfaceTerminator),
-                |i| i.get_tree_icon(&icon::Icon::TreeInterfaceTerminator),
+        // get current prefix based on if last in tree and whether we are within the tree
+        if settings.tree {
+            let (prefix, terminator) = render_tree_prefix(
+                tree,
+                i,
+                &icon::Icon::TreeInterfaceTerminator,
+                |ct| ct.tree_interface_terminator,
+                settings,
             );
 
-            // colour tree
-            if let Some(ct) = settings.colours.as_ref() {
-                prefix = ct
-                    .tree
-                    .map_or(prefix.normal(), |c| prefix.color(c))
-                    .to_string();
-                terminator = ct
-                    .tree_interface_terminator
-                    .map_or(terminator.normal(), |c| terminator.color(c))
-                    .to_string();
-            }
-
             // maybe should just do once at start of bus
             if settings.headings && i == 0 {
                 let heading = render_heading(&blocks.0, &pad).join(" ");
-                println!("{}  {}", prefix, heading.bold().underline());
+                ret.push((
+                    format!("{}  ", prefix),
+                    heading.bold().underline().to_string(),
+                ));
             }
 
-            // render and print tree if doing it
-            print!("{}{} ", prefix, terminator);
-
-            println!(
-                "{}",
-                render_value(interface, &blocks.0, &pad, settings).join(" ")
-            );
+            // render tree if doing it
+            ret.push((format!("{}{} ", prefix, terminator), value));
         } else {
             if settings.headings && i == 0 {
                 let heading = render_heading(&blocks.0, &pad).join(" ");
-                println!("{:spaces$}{}", "", heading.bold().underline(), spaces = 4);
+                ret.push((
+                    render_indent(2, settings),
+                    heading.bold().underline().to_string(),
+                ));
             }
 
-            println!(
-                "{:spaces$}{}",
-                "",
-                render_value(interface, &blocks.0, &pad, settings).join(" "),
-                spaces = 4
-            );
+            ret.push((render_indent(2, settings), value));
+        }
+
+        if settings.hex {
+            ret.extend(render_hex(&interface.raw, tree, i, 2, settings));
         }
 
-        // print the endpoints
+        // render the endpoints
         if settings.verbosity >= 3 {
-            print_endpoints(
+            ret.extend(render_endpoints(
                 &interface.endpoints,
                 &blocks.1,
                 settings,
                 &generate_tree_data(tree, interface.endpoints.len(), i, settings),
-            );
+            ));
         }
     }
+
+    ret
 }
 
-/// All device [`USBConfiguration`]
-pub fn print_configurations(
-    configs: &Vec<USBConfiguration>,
-    blocks: (
+/// Whether interfaces are collapsed to their first alternate setting with `settings`
+fn collapses_alt_settings(settings: &PrintSettings) -> bool {
+    settings.collapse_alt_settings && settings.verbosity < MAX_VERBOSITY
+}
+
+/// The first alternate setting of each interface number in `interfaces`, in order
+fn first_alt_settings(interfaces: &Vec<USBInterface>) -> Vec<USBInterface> {
+    interfaces
+        .iter()
+        .filter(|i| {
+            !interfaces
+                .iter()
+                .any(|s| s.number == i.number && s.alt_setting < i.alt_setting)
+        })
+        .cloned()
+        .collect()
+}
+
+/// Clones only those `interfaces` of `settings.prune_interfaces` class, or all if None
+pub fn prune_interfaces(
+    interfaces: &Vec<USBInterface>,
+    settings: &PrintSettings,
+) -> Vec<USBInterface> {
+    match settings.prune_interfaces.as_ref() {
+        Some(c) => interfaces
+            .iter()
+            .filter(|i| i.class == *c)
+            .cloned()
+            .collect(),
+        None => interfaces.to_owned(),
+    }
+}
+
+/// Number of interface rows [`render_interfaces`] renders for `interfaces` with `settings`
+fn interface_row_count(interfaces: &Vec<USBInterface>, settings: &PrintSettings) -> usize {
+    if collapses_alt_settings(settings) {
+        first_alt_settings(interfaces).len()
+    } else {
+        interfaces.len()
+    }
+}
+
+/// All device [`USBInterface`]
+pub fn print_interfaces(
+    interfaces: &Vec<USBInterface>,
+    blocks: (&Vec<InterfaceBlocks>, &Vec<EndpointBlocks>),
+    settings: &PrintSettings,
+    tree: &TreeData,
+) {
+    for line in join_tree_lines(render_interfaces(interfaces, blocks, settings, tree)) {
+        println!("{}", line);
+    }
+}
+
+/// Render all device [`USBConfiguration`] and their interfaces as `(prefix, line)` pairs; prefix is the tree or indentation
+pub fn render_configurations(
+    configs: &Vec<USBConfiguration>,
+    blocks: (
         &Vec<ConfigurationBlocks>,
         &Vec<InterfaceBlocks>,
         &Vec<EndpointBlocks>,
     ),
     settings: &PrintSettings,
     tree: &TreeData,
-) {
+) -> Vec<(String, String)> {
+    let mut ret = Vec::new();
     let pad = if !settings.no_padding {
         ConfigurationBlocks::generate_padding(&configs.iter().map(|d| d).collect())
     } else {
@@ -1730,156 +4238,221 @@ pub fn print_configurations(
     for (i, config) in configs.iter().enumerate() {
         // get current prefix based on if last in tree and whether we are within the tree
         if settings.tree {
-            let mut prefix = if tree.depth > 0 {
-                let edge_icon = if i + 1 != tree.branch_length {
-                    icon::Icon::TreeEdge
-                } else {
-                    icon::Icon::TreeCorner
-                };
-                let edge = settings
-                    .icons
-                    .as_ref()
-                    .map_or(icon::get_ascii_tree_icon(&edge_icon), |i| {
-                        i.get_tree_icon(&edge_icon)
-                    });
-                format!("{}{}", tree.prefix, edge)
-            // zero depth
-            } else {
-                format!("{}", tree.prefix)
-            };
-
-            let mut terminator = settings.icons.as_ref().map_or(
-                icon::get_ascii_tree_icon(&icon::Icon::TreeConfigurationTerminator),
-                |i| i.get_tree_icon(&icon::Icon::TreeConfigurationTerminator),
+            let (prefix, terminator) = render_tree_prefix(
+                tree,
+                i,
+                &icon::Icon::TreeConfigurationTerminator,
+                |ct| ct.tree_configuration_terminator,
+                settings,
             );
 
-            // colour tree
-            if let Some(ct) = settings.colours.as_ref() {
-                prefix = ct
-                    .tree
-                    .map_or(prefix.normal(), |c| prefix.color(c))
-                    .to_string();
-                terminator = ct
-                    .tree_configuration_terminator
-                    .map_or(terminator.normal(), |c| terminator.color(c))
-                    .to_string();
-            }
-
             // maybe should just do once at start of bus
             if settings.headings && i == 0 {
                 let heading = render_heading(blocks.0, &pad).join(" ");
-                println!("{}  {}", prefix, heading.bold().underline());
+                ret.push((
+                    format!("{}  ", prefix),
+                    heading.bold().underline().to_string(),
+                ));
             }
 
-            // render and print tree if doing it
-            print!("{}{} ", prefix, terminator);
-
-            println!(
-                "{}",
-                render_value(config, blocks.0, &pad, settings).join(" ")
-            );
+            // render tree if doing it
+            ret.push((
+                format!("{}{} ", prefix, terminator),
+                render_value(config, blocks.0, &pad, settings).join(" "),
+            ));
         } else {
             if settings.headings && i == 0 {
                 let heading = render_heading(blocks.0, &pad).join(" ");
-                println!("{:spaces$}{}", "", heading.bold().underline(), spaces = 2);
+                ret.push((
+                    render_indent(1, settings),
+                    heading.bold().underline().to_string(),
+                ));
             }
 
-            println!(
-                "{:spaces$}{}",
-                "",
+            ret.push((
+                render_indent(1, settings),
                 render_value(config, blocks.0, &pad, settings).join(" "),
-                spaces = 2
-            );
+            ));
+        }
+
+        if settings.hex {
+            ret.extend(render_hex(&config.raw, tree, i, 1, settings));
         }
 
-        // print the interfaces
+        // render the interfaces
         if settings.verbosity >= 2 {
-            print_interfaces(
-                &config.interfaces,
+            let interfaces = prune_interfaces(&config.interfaces, settings);
+            ret.extend(render_interfaces(
+                &interfaces,
                 (&blocks.1, &blocks.2),
                 settings,
-                &generate_tree_data(tree, config.interfaces.len(), i, settings),
-            );
+                &generate_tree_data(
+                    tree,
+                    interface_row_count(&interfaces, settings),
+                    i,
+                    settings,
+                ),
+            ));
         }
     }
+
+    ret
 }
 
-/// Recursively print `devices`; will call for each `USBDevice` devices if `Some`
+/// All device [`USBConfiguration`]
+pub fn print_configurations(
+    configs: &Vec<USBConfiguration>,
+    blocks: (
+        &Vec<ConfigurationBlocks>,
+        &Vec<InterfaceBlocks>,
+        &Vec<EndpointBlocks>,
+    ),
+    settings: &PrintSettings,
+    tree: &TreeData,
+) {
+    for line in join_tree_lines(render_configurations(configs, blocks, settings, tree)) {
+        println!("{}", line);
+    }
+}
+
+/// Recursively render `devices` as `(prefix, line)` pairs; will render each `USBDevice` devices if `Some`
 ///
-/// Will draw tree if `settings.tree`, otherwise it will be flat
-pub fn print_devices(
+/// Prefix is the tree if `settings.tree`, otherwise it will be flat. Use [`join_tree_lines`] to get the lines [`print_devices`] would print
+pub fn render_device_tree(
     devices: &Vec<system_profiler::USBDevice>,
     db: &Vec<DeviceBlocks>,
     settings: &PrintSettings,
     tree: &TreeData,
-) {
-    let pad = if !settings.no_padding {
-        DeviceBlocks::generate_padding(&devices.iter().map(|d| d).collect())
+) -> Vec<(String, String)> {
+    let tree_pad = if settings.align_tree {
+        let mut pad =
+            flattened_device_padding(&system_profiler::get_all_devices(devices), settings);
+        if let Some(name) = pad.get_mut(&DeviceBlocks::Name) {
+            *name = tree_name_width(devices, settings, tree);
+        }
+        Some(pad)
     } else {
-        HashMap::new()
+        None
+    };
+
+    render_device_tree_with(devices, db, tree_pad.as_ref(), settings, tree)
+}
+
+/// Display width of the prefix [`render_device_tree`] renders before each device at the depth of `tree`; 0 if not `settings.tree`
+fn device_prefix_width(tree: &TreeData, settings: &PrintSettings) -> usize {
+    if !settings.tree {
+        return 0;
+    }
+    // corner and edge are the same width
+    let edge = if tree.depth > 0 {
+        tree_icon(&icon::Icon::TreeEdge, settings).width()
+    } else {
+        0
+    };
+    // terminator is followed by a space
+    tree.prefix.width() + edge + tree_icon(&icon::Icon::TreeDeviceTerminator, settings).width() + 1
+}
+
+/// Widest [`DeviceBlocks::Name`] of `devices` and their devices plus the prefix at their depth from `tree`, used as the Name padding with [`PrintSettings::align_tree`]
+///
+/// [`render_device_tree_with`] takes off the prefix at each depth so that the blocks after the name start at the same column
+fn tree_name_width(
+    devices: &Vec<system_profiler::USBDevice>,
+    settings: &PrintSettings,
+    tree: &TreeData,
+) -> usize {
+    let prefix = device_prefix_width(tree, settings);
+    devices
+        .iter()
+        .enumerate()
+        .map(|(i, d)| {
+            let name = DeviceBlocks::Name
+                .format_value(d, &HashMap::new(), settings)
+                .map_or(0, |v| v.width());
+            let children = d.devices.as_ref().map_or(0, |dd| {
+                tree_name_width(
+                    dd,
+                    settings,
+                    &generate_tree_data(tree, dd.len(), i, settings),
+                )
+            });
+            (name + prefix).max(children)
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// [`render_device_tree`] with `tree_pad` used at every depth if Some, otherwise padding is generated for the siblings at each depth
+fn render_device_tree_with(
+    devices: &Vec<system_profiler::USBDevice>,
+    db: &Vec<DeviceBlocks>,
+    tree_pad: Option<&HashMap<DeviceBlocks, usize>>,
+    settings: &PrintSettings,
+    tree: &TreeData,
+) -> Vec<(String, String)> {
+    let mut ret = Vec::new();
+    let pad = if let Some(tree_pad) = tree_pad {
+        let mut pad = tree_pad.to_owned();
+        // Name pad includes the widest prefix so take off the prefix at this depth to line up the blocks after it
+        if let Some(name) = pad.get_mut(&DeviceBlocks::Name) {
+            *name = name.saturating_sub(device_prefix_width(tree, settings));
+        }
+        pad
+    } else {
+        flattened_device_padding(&devices.iter().collect(), settings)
     };
     log::trace!("Print devices padding {:?}, tree {:?}", pad, tree);
 
     // sort so that can be ascending along branch
     let sorted = settings.sort_devices.sort_devices(&devices);
-
-    for (i, device) in sorted.iter().enumerate() {
-        // get current prefix based on if last in tree and whether we are within the tree
-        if settings.tree {
-            let mut prefix = if tree.depth > 0 {
-                let edge_icon = if i + 1 != tree.branch_length {
-                    icon::Icon::TreeEdge
-                } else {
-                    icon::Icon::TreeCorner
-                };
-                let edge = settings
-                    .icons
-                    .as_ref()
-                    .map_or(icon::get_ascii_tree_icon(&edge_icon), |i| {
-                        i.get_tree_icon(&edge_icon)
-                    });
-                format!("{}{}", tree.prefix, edge)
-            // zero depth
+    // devices of these devices are pruned if at max depth
+    let truncated = settings.max_depth.map_or(false, |m| tree.depth >= m);
+    // devices past the limit are counted on a line after those rendered
+    let more = limit_overflow(sorted.len(), settings);
+
+    for (i, device) in sorted.iter().take(sorted.len() - more).enumerate() {
+        // pruned devices are replaced by a single ellipsis
+        let num_devices = device.devices.as_ref().map_or(0, |d| {
+            if truncated && !d.is_empty() {
+                1
             } else {
-                format!("{}", tree.prefix)
-            };
+                limited_branch_length(d.len(), settings)
+            }
+        });
 
-            let mut terminator = settings.icons.as_ref().map_or(
-                icon::get_ascii_tree_icon(&icon::Icon::TreeDeviceTerminator),
-                |i| i.get_tree_icon(&icon::Icon::TreeDeviceTerminator),
+        // get current prefix based on if last in tree and whether we are within the tree
+        let prefix = if settings.tree {
+            let (prefix, terminator) = render_tree_prefix(
+                tree,
+                i,
+                &icon::Icon::TreeDeviceTerminator,
+                |ct| ct.tree_bus_terminator,
+                settings,
             );
 
-            // colour tree
-            if let Some(ct) = settings.colours.as_ref() {
-                prefix = ct
-                    .tree
-                    .map_or(prefix.normal(), |c| prefix.color(c))
-                    .to_string();
-                terminator = ct
-                    .tree_bus_terminator
-                    .map_or(terminator.normal(), |c| terminator.color(c))
-                    .to_string();
-            }
-
             // maybe should just do once at start of bus
             if settings.headings && i == 0 {
                 let heading = render_heading(db, &pad).join(" ");
-                println!("{}  {}", prefix, heading.bold().underline());
+                ret.push((
+                    format!("{}  ", prefix),
+                    heading.bold().underline().to_string(),
+                ));
             }
 
-            // render and print tree if doing it
-            print!("{}{} ", prefix, terminator);
+            format!("{}{} ", prefix, terminator)
         } else {
             if settings.headings && i == 0 {
                 let heading = render_heading(db, &pad).join(" ");
-                println!("{}", heading.bold().underline());
+                ret.push((String::new(), heading.bold().underline().to_string()));
             }
-        }
 
-        // print the device
-        println!("{}", render_value(device, db, &pad, settings).join(" "));
+            String::new()
+        };
+
+        // render the device
+        ret.push((prefix, render_value(device, db, &pad, settings).join(" ")));
 
-        // print the configurations
+        // render the configurations
         if let Some(extra) = device.extra.as_ref() {
             if settings.verbosity >= 1 {
                 let blocks = (
@@ -1903,17 +4476,17 @@ pub fn print_devices(
                     )),
                 );
                 // pass branch length as number of configurations for this device plus devices still to print
-                print_configurations(
+                ret.extend(render_configurations(
                     &extra.configurations,
                     blocks,
                     settings,
                     &generate_tree_data(
                         &tree,
-                        extra.configurations.len() + device.devices.as_ref().map_or(0, |d| d.len()),
+                        extra.configurations.len() + num_devices,
                         i,
                         settings,
                     ),
-                );
+                ));
             }
         } else if settings.verbosity >= 1 {
             log::warn!(
@@ -1923,38 +4496,178 @@ pub fn print_devices(
         }
 
         match device.devices.as_ref() {
+            Some(d) if truncated && !d.is_empty() => {
+                log::debug!(
+                    "Max depth reached, not rendering {} devices of {}",
+                    d.len(),
+                    device
+                );
+                ret.push(render_truncated_branch(
+                    &generate_tree_data(&tree, num_devices, i, settings),
+                    settings,
+                ));
+            }
             Some(d) => {
-                // and then walk down devices printing them too
-                print_devices(
+                // and then walk down devices rendering them too
+                ret.extend(render_device_tree_with(
                     &d,
                     db,
+                    tree_pad,
                     settings,
-                    &generate_tree_data(&tree, d.len(), i, settings),
-                );
+                    &generate_tree_data(
+                        &tree,
+                        limited_branch_length(d.len(), settings),
+                        i,
+                        settings,
+                    ),
+                ));
             }
             None => (),
         }
     }
-}
 
-/// Print SPUSBDataType
-pub fn print_sp_usb(sp_usb: &system_profiler::SPUSBDataType, settings: &PrintSettings) {
-    let bb = settings.bus_blocks.to_owned().unwrap_or(
-        Block::<BusBlocks, system_profiler::USBBus>::default_blocks(
-            settings.verbosity >= MAX_VERBOSITY || settings.more,
-        ),
-    );
-    let db = settings.device_blocks.to_owned().unwrap_or(
-        if settings.verbosity >= MAX_VERBOSITY || settings.more {
-            DeviceBlocks::default_blocks(true)
+    if more > 0 {
+        let line = render_more_devices(more, settings);
+        if settings.tree {
+            let (prefix, _) = render_tree_prefix(
+                tree,
+                sorted.len() - more,
+                &icon::Icon::TreeDeviceTerminator,
+                |ct| ct.tree_bus_terminator,
+                settings,
+            );
+            ret.push((format!("{} ", prefix), line));
         } else {
-            if settings.tree {
-                DeviceBlocks::default_device_tree_blocks()
-            } else {
-                DeviceBlocks::default_blocks(false)
-            }
-        },
-    );
+            ret.push((String::new(), line));
+        }
+    }
+
+    ret
+}
+
+/// Number of devices in a branch or list of `len` past [`PrintSettings::limit`] that are not rendered
+fn limit_overflow(len: usize, settings: &PrintSettings) -> usize {
+    settings.limit.map_or(0, |l| len.saturating_sub(l))
+}
+
+/// Number of items rendered for a branch of `len` devices: those within [`PrintSettings::limit`] plus the line counting the rest
+fn limited_branch_length(len: usize, settings: &PrintSettings) -> usize {
+    match limit_overflow(len, settings) {
+        0 => len,
+        more => len - more + 1,
+    }
+}
+
+/// Renders the '... (X more)' line counting `more` devices not rendered due to `settings.limit`
+fn render_more_devices(more: usize, settings: &PrintSettings) -> String {
+    let ellipsis = if settings.icons.is_some() {
+        "\u{2026}"
+    } else {
+        "..."
+    };
+    let line = format!("{} ({} more)", ellipsis, more);
+
+    settings
+        .colours
+        .as_ref()
+        .and_then(|ct| ct.tree)
+        .map_or(line.normal(), |c| line.color(c))
+        .to_string()
+}
+
+/// Renders the ellipsis marking devices pruned by `settings.max_depth` as the only item in `tree` branch
+fn render_truncated_branch(tree: &TreeData, settings: &PrintSettings) -> (String, String) {
+    let ellipsis = if settings.icons.is_some() {
+        "\u{2026}"
+    } else {
+        "..."
+    };
+    let ellipsis = settings
+        .colours
+        .as_ref()
+        .and_then(|ct| ct.tree)
+        .map_or(ellipsis.normal(), |c| ellipsis.color(c))
+        .to_string();
+
+    if settings.tree {
+        let (prefix, _) = render_tree_prefix(
+            tree,
+            0,
+            &icon::Icon::TreeDeviceTerminator,
+            |ct| ct.tree_bus_terminator,
+            settings,
+        );
+        (format!("{} ", prefix), ellipsis)
+    } else {
+        (String::new(), ellipsis)
+    }
+}
+
+/// Recursively render `devices` lines exactly as [`print_devices`] would print them
+pub fn render_devices(
+    devices: &Vec<system_profiler::USBDevice>,
+    db: &Vec<DeviceBlocks>,
+    settings: &PrintSettings,
+    tree: &TreeData,
+) -> Vec<String> {
+    join_tree_lines(render_device_tree(devices, db, settings, tree))
+}
+
+/// Render a single `device` and any devices attached to it as lines using `db` blocks, as [`print_devices`] would at the root of a tree
+///
+/// ```
+/// use cyme::display::{render_device_lines, DeviceBlocks, PrintSettings};
+/// use cyme::system_profiler::USBDevice;
+///
+/// let device = USBDevice {
+///     name: "Black Magic Probe".into(),
+///     vendor_id: Some(0x1d50),
+///     product_id: Some(0x6018),
+///     ..Default::default()
+/// };
+/// let lines = render_device_lines(&device, &vec![DeviceBlocks::VendorId, DeviceBlocks::ProductId, DeviceBlocks::Name], &PrintSettings::default());
+/// assert_eq!(lines, vec!["0x1d50 0x6018 Black Magic Probe"]);
+/// ```
+pub fn render_device_lines(
+    device: &system_profiler::USBDevice,
+    db: &Vec<DeviceBlocks>,
+    settings: &PrintSettings,
+) -> Vec<String> {
+    render_devices(&vec![device.to_owned()], db, settings, &Default::default())
+}
+
+/// Recursively print `devices`; will call for each `USBDevice` devices if `Some`
+///
+/// Will draw tree if `settings.tree`, otherwise it will be flat
+pub fn print_devices(
+    devices: &Vec<system_profiler::USBDevice>,
+    db: &Vec<DeviceBlocks>,
+    settings: &PrintSettings,
+    tree: &TreeData,
+) {
+    for line in render_devices(devices, db, settings, tree) {
+        println!("{}", line);
+    }
+}
+
+/// Render SPUSBDataType lines exactly as [`print_sp_usb`] would print them
+pub fn render_sp_usb(
+    sp_usb: &system_profiler::SPUSBDataType,
+    settings: &PrintSettings,
+) -> Vec<String> {
+    let mut ret = Vec::new();
+    let bb = settings
+        .bus_blocks
+        .to_owned()
+        .unwrap_or_else(|| BusBlocks::default_blocks_for(settings));
+    let db = if settings.compact {
+        DeviceBlocks::compact_blocks()
+    } else {
+        settings
+            .device_blocks
+            .to_owned()
+            .unwrap_or(DeviceBlocks::default_blocks_for(settings))
+    };
 
     let base_tree = TreeData {
         ..Default::default()
@@ -1966,6 +4679,31 @@ pub fn print_sp_usb(sp_usb: &system_profiler::SPUSBDataType, settings: &PrintSet
         HashMap::new()
     };
 
+    // one device padding across all buses so that columns line up through the whole tree
+    let tree_pad = if settings.align_tree {
+        let mut pad = flattened_device_padding(&sp_usb.flatten_devices(), settings);
+        if let Some(name) = pad.get_mut(&DeviceBlocks::Name) {
+            *name = sp_usb
+                .buses
+                .iter()
+                .enumerate()
+                .filter_map(|(i, b)| {
+                    b.devices.as_ref().map(|d| {
+                        tree_name_width(
+                            d,
+                            settings,
+                            &generate_tree_data(&base_tree, d.len(), i, settings),
+                        )
+                    })
+                })
+                .max()
+                .unwrap_or(0);
+        }
+        Some(pad)
+    } else {
+        None
+    };
+
     log::trace!(
         "print SPUSBDataType settings, {:?}, padding {:?}, tree {:?}",
         settings,
@@ -1973,15 +4711,16 @@ pub fn print_sp_usb(sp_usb: &system_profiler::SPUSBDataType, settings: &PrintSet
         base_tree
     );
 
-    for (i, bus) in sp_usb.buses.iter().enumerate() {
-        if settings.tree {
+    // buses are the groups when not a tree
+    let mut buses: Vec<&USBBus> = sp_usb.buses.iter().collect();
+    if let (false, Some(group_sort)) = (settings.tree, settings.group_sort) {
+        group_sort.sort_buses(&mut buses);
+    }
+
+    for (i, bus) in buses.into_iter().enumerate() {
+        let start = if settings.tree {
             let mut prefix = base_tree.prefix.to_owned();
-            let mut start = settings
-                .icons
-                .as_ref()
-                .map_or(icon::get_ascii_tree_icon(&icon::Icon::TreeBusStart), |i| {
-                    i.get_tree_icon(&icon::Icon::TreeBusStart)
-                });
+            let mut start = tree_icon(&icon::Icon::TreeBusStart, settings);
 
             // colour tree
             if let Some(ct) = settings.colours.as_ref() {
@@ -1998,99 +4737,275 @@ pub fn print_sp_usb(sp_usb: &system_profiler::SPUSBDataType, settings: &PrintSet
             if settings.headings {
                 let heading = render_heading(&bb, &pad).join(" ");
                 // 2 spaces for bus start icon and space to info
-                println!("{:>spaces$}{}", "", heading.bold().underline(), spaces = 2);
+                ret.push(format!(
+                    "{:>spaces$}{}",
+                    "",
+                    heading.bold().underline(),
+                    spaces = 2
+                ));
             }
 
-            print!("{}{} ", prefix, start);
+            format!("{}{} ", prefix, start)
         } else {
             if settings.headings {
                 let heading = render_heading(&bb, &pad).join(" ");
                 // 2 spaces for bus start icon and space to info
-                println!("{}", heading.bold().underline());
+                ret.push(heading.bold().underline().to_string());
             }
-        }
-        println!("{}", render_value(bus, &bb, &pad, settings).join(" "));
+
+            String::new()
+        };
+        ret.push(format!(
+            "{}{}",
+            start,
+            render_value(bus, &bb, &pad, settings).join(" ")
+        ));
 
         match bus.devices.as_ref() {
             Some(d) => {
-                // and then walk down devices printing them too
-                print_devices(
+                // and then walk down devices rendering them too
+                ret.extend(join_tree_lines(render_device_tree_with(
                     &d,
                     &db,
+                    tree_pad.as_ref(),
                     settings,
-                    &generate_tree_data(&base_tree, d.len(), i, settings),
-                );
+                    &generate_tree_data(
+                        &base_tree,
+                        limited_branch_length(d.len(), settings),
+                        i,
+                        settings,
+                    ),
+                )));
             }
             None => (),
         }
 
         // separate bus groups with line
-        println!();
+        ret.push(String::new());
     }
+
+    ret
 }
 
-/// Mask the `device` serial if it has one using the [`MaskSerial`] method and recursively if `recursive`
-pub fn mask_serial(device: &mut system_profiler::USBDevice, hide: &MaskSerial, recursive: bool) {
-    if let Some(serial) = device.serial_num.as_mut() {
-        *serial = match hide {
-            MaskSerial::Hide => serial.chars().map(|_| '*').collect::<String>(),
-            MaskSerial::Scramble =>
-                serial.chars().map(|_| serial.chars().choose(&mut rand::thread_rng()).unwrap_or('*')).collect::<String>(),
-            MaskSerial::Replace =>
-                rand::thread_rng()
-                    .sample_iter(Alphanumeric)
-                    .take(serial.chars().count())
-                    .map(char::from)
-                    .collect::<String>().to_uppercase(),
-        };
+/// Print SPUSBDataType
+pub fn print_sp_usb(sp_usb: &system_profiler::SPUSBDataType, settings: &PrintSettings) {
+    for line in render_sp_usb(sp_usb, settings) {
+        println!("{}", line);
     }
+}
 
-    if recursive {
-        device.devices.as_mut().map_or((), |dd| dd.iter_mut().for_each(|d| mask_serial(d, hide, recursive)));
+/// Escapes HTML special characters in `s`
+fn html_escape(s: &str) -> String {
+    let mut ret = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => ret.push_str("&amp;"),
+            '<' => ret.push_str("&lt;"),
+            '>' => ret.push_str("&gt;"),
+            '"' => ret.push_str("&quot;"),
+            '\'' => ret.push_str("&#39;"),
+            _ => ret.push(c),
+        }
     }
+    ret
 }
 
-/// Main cyme bin prepare for printing function - changes mutable `sp_usb` with requested `filter` and sort in `settings`
-pub fn prepare(
-    sp_usb: &mut system_profiler::SPUSBDataType,
-    filter: Option<system_profiler::USBFilter>,
+/// Render `items` as lines of a self-contained HTML `<table>` with a column for each of `blocks`
+///
+/// Cells are wrapped in a `<span>` with inline colour style from the block colour if `settings.colours`. No padding is applied since the table aligns the columns.
+///
+/// ```
+/// use cyme::display::{render_html_table, DeviceBlocks, PrintSettings};
+/// use cyme::system_profiler::USBDevice;
+///
+/// let device = USBDevice {
+///     name: "<Probe> & Co".into(),
+///     ..Default::default()
+/// };
+/// let html = render_html_table(&vec![&device], &vec![DeviceBlocks::Name], &PrintSettings::default()).join("\n");
+/// assert!(html.contains("<td>&lt;Probe&gt; &amp; Co</td>"));
+/// ```
+pub fn render_html_table<B, T>(
+    items: &Vec<&T>,
+    blocks: &Vec<impl Block<B, T>>,
     settings: &PrintSettings,
-) {
-    // if not printing tree, hard flatten now before filtering as filter will retain non-matching parents with matching devices in tree
-    // but only do it if there is a filter, grouping by bus (which uses tree print without tree...) or json
-    // flattening now will also mean hubs will be removed when listing if `hide_hubs` because they will appear empty
-    if !settings.tree && (filter.is_some() || settings.group_devices == Group::Bus || settings.json)
-    {
-        sp_usb.flatten();
+) -> Vec<String> {
+    let pad = HashMap::new();
+    let mut ret = vec![String::from("<table>"), String::from("<thead><tr>")];
+
+    for b in blocks {
+        ret.push(format!("<th>{}</th>", html_escape(b.heading(&pad).trim())));
+    }
+    ret.push(String::from("</tr></thead>"));
+    ret.push(String::from("<tbody>"));
+
+    for item in items {
+        ret.push(String::from("<tr>"));
+        for b in blocks {
+            let value = b
+                .format_value(item, &pad, settings)
+                .map_or(String::new(), |v| html_escape(v.trim()));
+            match settings
+                .colours
+                .as_ref()
+                .and_then(|ct| block_colour(b, *item, &value, ct, settings))
+            {
+                Some(c) => ret.push(format!(
+                    "<td><span style=\"color:{}\">{}</span></td>",
+                    colour::color_to_css(c),
+                    value
+                )),
+                None => ret.push(format!("<td>{}</td>", value)),
+            }
+        }
+        ret.push(String::from("</tr>"));
     }
 
-    // do the filter if present; will keep parents of matched devices even if they do not match
-    filter
-        .as_ref()
-        .map_or((), |f| f.retain_buses(&mut sp_usb.buses));
+    ret.push(String::from("</tbody>"));
+    ret.push(String::from("</table>"));
 
-    // hide any empty buses and hubs now we've filtered
-    if settings.hide_buses {
-        sp_usb.buses.retain(|b| b.has_devices());
-        // may still be empty hubs if the hub had an empty hub!
+    ret
+}
+
+/// Mask the `device` serial if it has one using the [`MaskSerial`] method and recursively if `recursive`
+///
+/// [`MaskSerial::Partial`] keeps [`DEFAULT_MASK_VISIBLE`] chars each end, use [`mask_serial_visible`] to change
+pub fn mask_serial(device: &mut system_profiler::USBDevice, hide: &MaskSerial, recursive: bool) {
+    mask_serial_visible(device, hide, DEFAULT_MASK_VISIBLE, recursive)
+}
+
+/// [`mask_serial`] keeping `visible` chars at each end with [`MaskSerial::Partial`]; serials too short to keep both ends are fully hidden
+///
+/// ```
+/// use cyme::display::{mask_serial_visible, MaskSerial};
+/// use cyme::system_profiler::USBDevice;
+///
+/// let mut device = USBDevice { serial_num: Some("AB1234YZ".into()), ..Default::default() };
+/// mask_serial_visible(&mut device, &MaskSerial::Partial, 2, false);
+/// assert_eq!(device.serial_num.unwrap(), "AB****YZ");
+///
+/// let mut device = USBDevice { serial_num: Some("ABCD".into()), ..Default::default() };
+/// mask_serial_visible(&mut device, &MaskSerial::Partial, 2, false);
+/// assert_eq!(device.serial_num.unwrap(), "****");
+/// ```
+pub fn mask_serial_visible(
+    device: &mut system_profiler::USBDevice,
+    hide: &MaskSerial,
+    visible: usize,
+    recursive: bool,
+) {
+    if let Some(serial) = device.serial_num.as_mut() {
+        *serial = match hide {
+            MaskSerial::Hide => serial.chars().map(|_| '*').collect::<String>(),
+            MaskSerial::Partial => {
+                let len = serial.chars().count();
+                serial
+                    .chars()
+                    .enumerate()
+                    .map(|(i, c)| {
+                        if len > visible * 2 && (i < visible || i >= len - visible) {
+                            c
+                        } else {
+                            '*'
+                        }
+                    })
+                    .collect::<String>()
+            }
+            MaskSerial::Scramble => serial
+                .chars()
+                .map(|_| {
+                    serial
+                        .chars()
+                        .choose(&mut rand::thread_rng())
+                        .unwrap_or('*')
+                })
+                .collect::<String>(),
+            MaskSerial::Replace => rand::thread_rng()
+                .sample_iter(Alphanumeric)
+                .take(serial.chars().count())
+                .map(char::from)
+                .collect::<String>()
+                .to_uppercase(),
+        };
+        device.serial_masked = true;
+    }
+
+    if recursive {
+        device.devices.as_mut().map_or((), |dd| {
+            dd.iter_mut()
+                .for_each(|d| mask_serial_visible(d, hide, visible, recursive))
+        });
+    }
+}
+
+/// Main cyme bin prepare for printing function - changes mutable `sp_usb` with requested `filter` and sort in `settings`
+pub fn prepare(
+    sp_usb: &mut system_profiler::SPUSBDataType,
+    filter: Option<system_profiler::USBFilter>,
+    settings: &PrintSettings,
+) {
+    // if not printing tree, hard flatten now but only do it if there is a filter, grouping by bus (which uses tree print without tree...) or json
+    let listing = !settings.tree
+        && (filter.is_some()
+            || settings.group_devices == Group::Bus
+            || settings.json
+            || settings.yaml
+            || settings.ndjson
+            || settings.html);
+    // when listing filter after flattening as filter will retain non-matching parents with matching devices in tree
+    // unless hubs need their devices to check if empty or `only_matching` will drop those parents after flattening
+    let filter_tree = !listing
+        || settings.only_matching
+        || filter.as_ref().map_or(false, |f| f.exclude_empty_hub);
+
+    // do the filter if present on the tree; will keep parents of matched devices even if they do not match
+    let matched = filter
+        .as_ref()
+        .filter(|_| filter_tree)
+        .map(|f| f.retain_buses(&mut sp_usb.buses));
+
+    if listing {
+        sp_usb.flatten();
         if let Some(f) = filter.as_ref() {
-            if f.exclude_empty_hub {
-                sp_usb.buses.retain(|b| !b.has_empty_hubs());
+            if !filter_tree {
+                f.retain_buses(&mut sp_usb.buses);
+            } else if f.exclude_hubs {
+                // hubs retained as parents of a match are still hubs
+                for bus in &mut sp_usb.buses {
+                    bus.devices
+                        .as_mut()
+                        .map_or((), |d| d.retain(|d| !d.is_hub()));
+                }
             }
         }
     }
 
-    // sort the buses if asked
-    if settings.sort_buses {
-        sp_usb.buses.sort_by_key(|d| d.get_bus_number());
+    // drop those parents when listing if only matches are wanted
+    if let Some(matched) = matched.filter(|_| listing && settings.only_matching) {
+        for bus in &mut sp_usb.buses {
+            bus.devices
+                .as_mut()
+                .map_or((), |d| d.retain(|d| matched.contains(&d.port_path())));
+        }
     }
 
+    // hide any empty buses now we've filtered; empty hubs have been pruned by the filter if `exclude_empty_hub`
+    if settings.hide_buses {
+        sp_usb.buses.retain(|b| b.has_devices());
+    }
+
+    // sort the buses if asked
+    settings.sort_buses.sort_buses(&mut sp_usb.buses);
+
     // hide serials Recursively
     if let Some(hide) = settings.mask_serials.as_ref() {
+        let visible = settings
+            .mask_serials_visible
+            .unwrap_or(DEFAULT_MASK_VISIBLE);
         for bus in &mut sp_usb.buses {
             bus.devices.as_mut().map_or((), |devices| {
                 for mut device in devices {
-                    mask_serial(&mut device, hide, true);
+                    mask_serial_visible(&mut device, hide, visible, true);
                 }
             });
         }
@@ -2099,29 +5014,2721 @@ pub fn prepare(
     log::trace!("sp_usb data post filter and bus sort\n\r{:#}", sp_usb);
 }
 
-/// Main cyme bin print function
-pub fn print(sp_usb: &system_profiler::SPUSBDataType, settings: &PrintSettings) {
+/// Projects `device` into a json object of only the `fields` [`DeviceBlocks`] values, keyed by block name
+///
+/// Values are the unpadded [`Block::format_value`] strings; blocks without a value are null
+///
+/// ```
+/// use cyme::display::{project_device, DeviceBlocks, PrintSettings};
+/// use cyme::system_profiler::USBDevice;
+///
+/// let device = USBDevice {
+///     name: "Black Magic Probe".into(),
+///     vendor_id: Some(0x1d50),
+///     ..Default::default()
+/// };
+/// let fields = vec![DeviceBlocks::VendorId, DeviceBlocks::ProductId, DeviceBlocks::Name];
+/// let value = project_device(&device, &fields, &PrintSettings::default());
+/// assert_eq!(
+///     value,
+///     serde_json::json!({"vendor-id": "0x1d50", "product-id": null, "name": "Black Magic Probe"})
+/// );
+/// ```
+pub fn project_device(
+    device: &USBDevice,
+    fields: &Vec<DeviceBlocks>,
+    settings: &PrintSettings,
+) -> serde_json::Value {
+    let pad = HashMap::new();
+    let mut map = serde_json::Map::new();
+    for b in fields {
+        let key = b
+            .to_possible_value()
+            .map_or(format!("{:?}", b), |v| v.get_name().to_string());
+        let value = match b.format_value(device, &pad, settings) {
+            Some(v) if !matches!(v.trim(), "" | "-") => serde_json::Value::String(v.trim().into()),
+            _ => serde_json::Value::Null,
+        };
+        map.insert(key, value);
+    }
+
+    serde_json::Value::Object(map)
+}
+
+/// [`project_device`] for `device` and recursively its `devices`, which are placed under a "devices" key
+pub fn project_device_tree(
+    device: &USBDevice,
+    fields: &Vec<DeviceBlocks>,
+    settings: &PrintSettings,
+) -> serde_json::Value {
+    let mut value = project_device(device, fields, settings);
+    if let (Some(map), Some(devices)) = (value.as_object_mut(), device.devices.as_ref()) {
+        map.insert(
+            "devices".into(),
+            devices
+                .iter()
+                .map(|d| project_device_tree(d, fields, settings))
+                .collect(),
+        );
+    }
+
+    value
+}
+
+/// [`project_device_tree`] for each bus in `sp_usb`, keeping the bus name and number so the tree structure matches the full json
+pub fn project_sp_usb(
+    sp_usb: &system_profiler::SPUSBDataType,
+    fields: &Vec<DeviceBlocks>,
+    settings: &PrintSettings,
+) -> serde_json::Value {
+    let buses: Vec<serde_json::Value> = sp_usb
+        .buses
+        .iter()
+        .map(|bus| {
+            serde_json::json!({
+                "name": bus.name,
+                "usb_bus_number": bus.get_bus_number(),
+                "devices": bus.devices.as_ref().map(|devices| devices
+                    .iter()
+                    .map(|d| project_device_tree(d, fields, settings))
+                    .collect::<Vec<serde_json::Value>>()),
+            })
+        })
+        .collect();
+
+    serde_json::json!({ "buses": buses })
+}
+
+/// Flattened devices in `sp_usb` as a json object keyed by `settings.group_devices` group name with arrays of devices as values
+///
+/// [`Group::Bus`] keys are "Bus 001" etc. and [`Group::Vendor`] keys are [`vendor_group_name`] or "Unknown". Devices are projected with [`project_device`] if `settings.json_fields`. [`Group::NoGroup`] has a single "devices" key.
+pub fn group_devices_json(
+    sp_usb: &system_profiler::SPUSBDataType,
+    settings: &PrintSettings,
+) -> Result<serde_json::Value, serde_json::Error> {
+    let groups: Vec<(String, Vec<&USBDevice>)> = match settings.group_devices {
+        Group::Bus => sp_usb
+            .buses
+            .iter()
+            .map(|bus| {
+                (
+                    format!("Bus {:03}", bus.get_bus_number()),
+                    bus.flattened_devices(),
+                )
+            })
+            .collect(),
+        Group::Vendor => vendor_groups(sp_usb.flatten_devices()),
+        Group::NoGroup => vec![(String::from("devices"), sp_usb.flatten_devices())],
+    };
+
+    let mut map = serde_json::Map::new();
+    for (name, devices) in groups {
+        let value = match settings.json_fields.as_ref() {
+            Some(fields) => devices
+                .iter()
+                .map(|d| project_device(d, fields, settings))
+                .collect(),
+            None => serde_json::to_value(&devices)?,
+        };
+        map.insert(name, value);
+    }
+
+    Ok(serde_json::Value::Object(map))
+}
+
+/// Prints `sp_usb` as the classic `lsusb -v` descriptor dump, so cyme can replace `lsusb -v` in scripts
+///
+/// Each device gets the 'Bus 001 Device 004: ID 046d:c52b ...' line followed by its indented device, configuration, interface and endpoint descriptors from `extra`. Devices are ordered by `settings.sort_devices`; those without `extra` data (not profiled with libusb) are skipped with a warning
+///
+/// ```
+/// use cyme::display::{print_lsusb_verbose, PrintSettings};
+/// use cyme::system_profiler::read_json_dump;
+///
+/// let sp_usb = read_json_dump("./tests/data/cyme_libusb_linux_tree.json").unwrap();
+/// print_lsusb_verbose(&sp_usb, &PrintSettings::default());
+/// ```
+pub fn print_lsusb_verbose(sp_usb: &system_profiler::SPUSBDataType, settings: &PrintSettings) {
+    let devices = sp_usb.flatten_devices();
+    let sorted = settings.sort_devices.sort_devices_ref(&devices);
+    crate::lsusb::display::print_verbose(&sorted, settings.hex);
+}
+
+/// Difference of a [`USBDevice`] between a baseline and current dump, see [`diff_devices`]
+#[derive(Debug)]
+pub enum DeviceDiff<'a> {
+    /// Device only in the current dump
+    Added(&'a USBDevice),
+    /// Device only in the baseline dump
+    Removed(&'a USBDevice),
+    /// Device in both dumps with the [`DeviceBlocks`] whose values differ: (baseline, current, blocks)
+    Changed(&'a USBDevice, &'a USBDevice, Vec<DeviceBlocks>),
+}
+
+impl<'a> DeviceDiff<'a> {
+    /// The device the diff is for; the current device if [`DeviceDiff::Changed`]
+    pub fn device(&self) -> &'a USBDevice {
+        match self {
+            DeviceDiff::Added(d) | DeviceDiff::Removed(d) | DeviceDiff::Changed(_, d, _) => d,
+        }
+    }
+}
+
+/// Unpadded value of block `b` for `d` used for comparing devices
+fn diff_value(b: &DeviceBlocks, d: &USBDevice, settings: &PrintSettings) -> String {
+    b.format_value(d, &HashMap::new(), settings)
+        .map_or(String::new(), |v| v.trim().to_string())
+}
+
+/// Compares `baseline` and `current`, matching devices by port path and VID:PID
+///
+/// Matched devices are [`DeviceDiff::Changed`] if any of the `blocks` values differ. The diffs are sorted by port path so the order does not depend on whether either dump is a tree or flattened; a device removed and added at the same port path is removed first
+pub fn diff_devices<'a>(
+    baseline: &'a system_profiler::SPUSBDataType,
+    current: &'a system_profiler::SPUSBDataType,
+    blocks: &Vec<DeviceBlocks>,
+    settings: &PrintSettings,
+) -> Vec<DeviceDiff<'a>> {
+    let current_devices: HashMap<(String, Option<u16>, Option<u16>), &USBDevice> = current
+        .iter_devices()
+        .map(|(p, d)| ((p, d.vendor_id, d.product_id), d))
+        .collect();
+    let mut baseline_keys = HashSet::new();
+    let mut ret = Vec::new();
+
+    for (p, old) in baseline.iter_devices() {
+        let key = (p, old.vendor_id, old.product_id);
+        match current_devices.get(&key) {
+            Some(new) => {
+                let changed: Vec<DeviceBlocks> = blocks
+                    .iter()
+                    .filter(|b| diff_value(b, old, settings) != diff_value(b, new, settings))
+                    .cloned()
+                    .collect();
+                if !changed.is_empty() {
+                    ret.push(DeviceDiff::Changed(old, new, changed));
+                }
+            }
+            None => ret.push(DeviceDiff::Removed(old)),
+        }
+        baseline_keys.insert(key);
+    }
+
+    for (p, new) in current.iter_devices() {
+        if !baseline_keys.contains(&(p, new.vendor_id, new.product_id)) {
+            ret.push(DeviceDiff::Added(new));
+        }
+    }
+
+    // stable so removed remains before added at the same port path
+    ret.sort_by(|a, b| port_path_cmp(a.device(), b.device()));
+
+    ret
+}
+
+/// Colours `s` with `colour` from the theme if colours are enabled
+fn colour_diff(
+    s: String,
+    colour: fn(&colour::ColourTheme) -> Option<Color>,
+    settings: &PrintSettings,
+) -> String {
+    match settings.colours.as_ref() {
+        Some(ct) => colour(ct).map_or(s.normal(), |c| s.color(c)).to_string(),
+        None => s,
+    }
+}
+
+/// Renders `diffs` with `blocks`: added lines prefixed '+' in the `diff_added` colour, removed '-' in `diff_removed` and changed '~' followed by the differing block values as 'baseline -> current'
+///
+/// Serials are masked with `settings.mask_serials` here rather than before [`diff_devices`] so that a randomised mask does not make every device differ
+pub fn render_diff(
+    diffs: &Vec<DeviceDiff>,
+    blocks: &Vec<DeviceBlocks>,
+    settings: &PrintSettings,
+) -> Vec<String> {
+    let mask = |d: &USBDevice| -> USBDevice {
+        let mut d = d.to_owned();
+        if let Some(hide) = settings.mask_serials.as_ref() {
+            let visible = settings
+                .mask_serials_visible
+                .unwrap_or(DEFAULT_MASK_VISIBLE);
+            mask_serial_visible(&mut d, hide, visible, false);
+        }
+        d
+    };
+    // (baseline if changed, shown device) for each diff
+    let shown: Vec<(Option<USBDevice>, USBDevice)> = diffs
+        .iter()
+        .map(|diff| match diff {
+            DeviceDiff::Added(d) | DeviceDiff::Removed(d) => (None, mask(d)),
+            DeviceDiff::Changed(old, new, _) => (Some(mask(old)), mask(new)),
+        })
+        .collect();
+    let devices: Vec<&USBDevice> = shown
+        .iter()
+        .flat_map(|(old, new)| old.iter().chain(std::iter::once(new)))
+        .collect();
+    let pad = flattened_device_padding(&devices, settings);
+    let plain = |d: &USBDevice| {
+        blocks
+            .iter()
+            .filter_map(|b| pad_value(b, d, &pad, settings))
+            .collect::<Vec<String>>()
+            .join(" ")
+    };
+
+    let mut ret = Vec::new();
+    for (diff, (old, new)) in diffs.iter().zip(shown.iter()) {
+        match diff {
+            DeviceDiff::Added(_) => ret.push(colour_diff(
+                format!("+ {}", plain(new)),
+                |ct| ct.diff_added,
+                settings,
+            )),
+            DeviceDiff::Removed(_) => ret.push(colour_diff(
+                format!("- {}", plain(new)),
+                |ct| ct.diff_removed,
+                settings,
+            )),
+            DeviceDiff::Changed(_, _, changed) => {
+                let old = old.as_ref().unwrap_or(new);
+                ret.push(format!(
+                    "~ {}",
+                    render_value(new, blocks, &pad, settings).join(" ")
+                ));
+                for b in changed {
+                    ret.push(format!(
+                        "    {}: {} -> {}",
+                        b.heading(&HashMap::new()).trim(),
+                        colour_diff(diff_value(b, old, settings), |ct| ct.diff_removed, settings),
+                        colour_diff(diff_value(b, new, settings), |ct| ct.diff_added, settings),
+                    ));
+                }
+            }
+        }
+    }
+
+    ret
+}
+
+/// Prints the differences of devices between `baseline` and `current` using the device blocks in `settings`, see [`diff_devices`] and [`render_diff`]
+pub fn print_diff(
+    baseline: &system_profiler::SPUSBDataType,
+    current: &system_profiler::SPUSBDataType,
+    settings: &PrintSettings,
+    writer: &mut impl io::Write,
+) -> Result<(), io::Error> {
+    let blocks = settings
+        .device_blocks
+        .to_owned()
+        .unwrap_or(DeviceBlocks::default_blocks_for(settings));
+    let diffs = diff_devices(baseline, current, &blocks, settings);
+    write_lines(writer, render_diff(&diffs, &blocks, settings))?;
+
+    writer.flush()
+}
+
+/// Render a legend of what the tree and device icons and the colours mean with the [`icon::IconTheme`] and [`colour::ColourTheme`] in `settings`
+pub fn render_legend(settings: &PrintSettings) -> Vec<String> {
+    let mut ret = Vec::new();
+
+    let tree: Vec<(String, String, String)> = icon::Icon::tree_icons()
+        .into_iter()
+        .map(|i| {
+            let value = tree_icon(&i, settings);
+            (value, i.to_string(), i.description())
+        })
+        .collect();
+    let icons: Vec<(String, String, String)> = settings.icons.as_ref().map_or(Vec::new(), |t| {
+        t.icons()
+            .into_iter()
+            .map(|(i, value)| (value, i.to_string(), i.description()))
+            .collect()
+    });
+
+    let icon_pad = tree
+        .iter()
+        .chain(icons.iter())
+        .map(|(v, _, _)| v.width())
+        .max()
+        .unwrap_or(0);
+    let key_pad = tree
+        .iter()
+        .chain(icons.iter())
+        .map(|(_, k, _)| k.width())
+        .max()
+        .unwrap_or(0);
+
+    for (heading, entries) in [("Tree", tree), ("Icons", icons)] {
+        if entries.is_empty() {
+            continue;
+        }
+        ret.push(heading.bold().underline().to_string());
+        for (value, key, description) in entries {
+            ret.push(format!(
+                "{} {} {}",
+                pad_width(&value, icon_pad),
+                pad_width(&key, key_pad),
+                description
+            ));
+        }
+        ret.push(String::new());
+    }
+
+    ret.push("Colours".bold().underline().to_string());
+    match settings.colours.as_ref() {
+        Some(ct) => {
+            let legend = ct.legend();
+            let pad = legend.iter().map(|(k, _, _)| k.width()).max().unwrap_or(0);
+            for (key, colour, description) in legend {
+                let key = pad_width(&key, pad);
+                ret.push(format!(
+                    "{} {}",
+                    colour.map_or(key.normal(), |c| key.color(c)),
+                    description
+                ));
+            }
+        }
+        None => ret.push("Colours are disabled".into()),
+    }
+
+    ret
+}
+
+/// Prints [`render_legend`] to `writer`
+pub fn print_legend(
+    settings: &PrintSettings,
+    writer: &mut impl io::Write,
+) -> Result<(), io::Error> {
+    write_lines(writer, render_legend(settings))?;
+
+    writer.flush()
+}
+
+/// Main cyme bin print function, writing `sp_usb` to `writer` in the format selected by `settings`
+///
+/// Serialization and write errors are returned rather than causing a panic
+pub fn print(
+    sp_usb: &system_profiler::SPUSBDataType,
+    settings: &PrintSettings,
+    writer: &mut impl io::Write,
+) -> Result<(), io::Error> {
     log::debug!("Printing with {:?}", settings);
 
-    if settings.tree || settings.group_devices == Group::Bus {
-        if settings.json {
-            println!("{}", serde_json::to_string_pretty(&sp_usb).unwrap());
+    if let Some(port_path) = settings.root_path.as_ref() {
+        return print_device_path(sp_usb, port_path, settings, writer);
+    }
+
+    if settings.count_only {
+        writeln!(writer, "{}", sp_usb.flatten_devices().len())?;
+        return writer.flush();
+    }
+
+    if settings.html {
+        let devices = sp_usb.flatten_devices();
+        let db = settings
+            .device_blocks
+            .to_owned()
+            .unwrap_or(DeviceBlocks::default_blocks_for(settings));
+        write_lines(writer, render_html_table(&devices, &db, settings))?;
+    } else if settings.ndjson && !settings.tree {
+        for device in sp_usb.flatten_devices() {
+            serde_json::to_writer(&mut *writer, device)?;
+            writeln!(writer)?;
+        }
+    } else if settings.json && settings.flatten_json {
+        let devs = sp_usb.flatten_devices();
+        if let Some(fields) = settings.json_fields.as_ref() {
+            let projected: Vec<serde_json::Value> = devs
+                .iter()
+                .map(|d| project_device(d, fields, settings))
+                .collect();
+            write_json(writer, &projected, settings)?;
         } else {
-            print_sp_usb(sp_usb, settings);
+            write_json(writer, &devs, settings)?;
+        }
+        writeln!(writer)?;
+    } else if settings.json && !settings.tree && settings.group_devices != Group::NoGroup {
+        write_json(writer, &group_devices_json(sp_usb, settings)?, settings)?;
+        writeln!(writer)?;
+    } else if settings.tree || settings.group_devices == Group::Bus {
+        if let (true, Some(fields)) = (settings.json, settings.json_fields.as_ref()) {
+            write_json(writer, &project_sp_usb(sp_usb, fields, settings), settings)?;
+            writeln!(writer)?;
+        } else if settings.json {
+            write_json(writer, &sp_usb, settings)?;
+            writeln!(writer)?;
+        } else if settings.yaml {
+            serde_yaml::to_writer(&mut *writer, &sp_usb)
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        } else {
+            write_lines(writer, render_sp_usb(sp_usb, settings))?;
         }
     } else {
         match settings.group_devices {
+            Group::Vendor if !(settings.json || settings.yaml) => {
+                write_lines(
+                    writer,
+                    render_vendor_grouped(sp_usb.flatten_devices(), settings),
+                )?;
+            }
             // completely flatten the bus and only print devices
             _ => {
                 // get a list of all devices
                 let devs = sp_usb.flatten_devices();
 
-                if settings.json {
-                    println!("{}", serde_json::to_string_pretty(&devs).unwrap());
+                if let (true, Some(fields)) = (settings.json, settings.json_fields.as_ref()) {
+                    let projected: Vec<serde_json::Value> = devs
+                        .iter()
+                        .map(|d| project_device(d, fields, settings))
+                        .collect();
+                    write_json(writer, &projected, settings)?;
+                    writeln!(writer)?;
+                } else if settings.json {
+                    write_json(writer, &devs, settings)?;
+                    writeln!(writer)?;
+                } else if settings.yaml {
+                    serde_yaml::to_writer(&mut *writer, &devs)
+                        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
                 } else {
-                    print_flattened_devices(&devs, settings);
+                    write_lines(writer, iter_flattened_devices(&devs, settings))?;
                 }
             }
         }
     }
+
+    // footer would make serialized output invalid
+    if settings.summary && !(settings.json || settings.yaml || settings.ndjson || settings.html) {
+        writeln!(writer, "{}", render_summary(sp_usb, settings))?;
+    }
+
+    writer.flush()
+}
+
+/// Renders a summary line of the number of buses, devices and hubs in `sp_usb`; bold if `settings` has colours
+///
+/// Counts are of `sp_usb` as is so will reflect any filtering applied by [`prepare`]
+///
+/// ```
+/// use cyme::display::{render_summary, PrintSettings};
+/// use cyme::system_profiler::read_json_dump;
+///
+/// let spusb = read_json_dump("./tests/data/cyme_libusb_linux_tree.json").unwrap();
+/// assert_eq!(
+///     render_summary(&spusb, &PrintSettings::default()),
+///     "4 buses, 9 devices, 5 hubs"
+/// );
+/// ```
+pub fn render_summary(sp_usb: &system_profiler::SPUSBDataType, settings: &PrintSettings) -> String {
+    summary_line(sp_usb, settings).to_string()
+}
+
+/// Styled summary line for [`render_summary`]; bold if `settings` has colours otherwise plain
+fn summary_line(
+    sp_usb: &system_profiler::SPUSBDataType,
+    settings: &PrintSettings,
+) -> ColoredString {
+    let devices = sp_usb.flatten_devices();
+    let hubs = devices.iter().filter(|d| d.is_hub()).count();
+    let summary = format!(
+        "{} buses, {} devices, {} hubs",
+        sp_usb.buses.len(),
+        devices.len(),
+        hubs
+    );
+
+    match settings.colours {
+        Some(_) => summary.bold(),
+        None => summary.normal(),
+    }
+}
+
+/// Writes the device at `port_path` within `sp_usb` and its descendants to `writer` as a tree rooted at that device
+///
+/// The device is written as json (projected to [`PrintSettings::json_fields`] if set) or yaml if those are set in `settings`, otherwise rendered with the `settings` device blocks, or [`DeviceBlocks::compact_blocks`] if `compact`. Returns a `NotFound` error if no device has `port_path`
+///
+/// ```
+/// use cyme::display::{print_device_path, DeviceBlocks, PrintSettings};
+/// use cyme::system_profiler::read_json_dump;
+///
+/// let sp_usb = read_json_dump("./tests/data/cyme_libusb_linux_tree.json").unwrap();
+/// let settings = PrintSettings {
+///     tree: true,
+///     device_blocks: Some(vec![DeviceBlocks::PortPath]),
+///     ..Default::default()
+/// };
+/// let mut out = Vec::new();
+/// assert!(print_device_path(&sp_usb, "9-9", &settings, &mut out).is_err());
+/// ```
+pub fn print_device_path(
+    sp_usb: &system_profiler::SPUSBDataType,
+    port_path: &str,
+    settings: &PrintSettings,
+    writer: &mut impl io::Write,
+) -> Result<(), io::Error> {
+    let device = sp_usb.get_node(port_path).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("No device found at port path '{}'", port_path),
+        )
+    })?;
+
+    if let (true, Some(fields)) = (settings.json, settings.json_fields.as_ref()) {
+        write_json(
+            writer,
+            &project_device_tree(device, fields, settings),
+            settings,
+        )?;
+        writeln!(writer)?;
+    } else if settings.json {
+        write_json(writer, device, settings)?;
+        writeln!(writer)?;
+    } else if settings.yaml {
+        serde_yaml::to_writer(&mut *writer, device)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+    } else {
+        let db = if settings.compact {
+            DeviceBlocks::compact_blocks()
+        } else {
+            settings
+                .device_blocks
+                .to_owned()
+                .unwrap_or(DeviceBlocks::default_blocks_for(settings))
+        };
+        write_lines(
+            writer,
+            render_devices(&vec![device.to_owned()], &db, settings, &Default::default()),
+        )?;
+    }
+
+    writer.flush()
+}
+
+/// Writes each of `lines` to `writer` with a new line
+fn write_lines(
+    writer: &mut impl io::Write,
+    lines: impl IntoIterator<Item = String>,
+) -> Result<(), io::Error> {
+    for line in lines {
+        writeln!(writer, "{}", line)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Linux libusb dump with [`crate::usb::USBDeviceExtra`] used by tests needing real devices
+    fn sp_data() -> system_profiler::SPUSBDataType {
+        system_profiler::read_json_dump("./tests/data/cyme_libusb_linux_tree.json").unwrap()
+    }
+
+    /// Interrupt IN endpoint 0x81 without speed or descriptors for tests to update the fields they need
+    fn endpoint() -> USBEndpoint {
+        USBEndpoint {
+            address: crate::usb::EndpointAddress {
+                address: 0x81,
+                number: 1,
+                direction: Direction::In,
+            },
+            transfer_type: crate::usb::TransferType::Interrupt,
+            sync_type: crate::usb::SyncType::None,
+            usage_type: crate::usb::UsageType::Data,
+            max_packet_size: 64,
+            interval: 1,
+            raw: None,
+            device_speed: None,
+            companion: None,
+        }
+    }
+
+    /// Every interface of every configuration of the devices in `sp_usb`
+    fn all_interfaces(sp_usb: &system_profiler::SPUSBDataType) -> Vec<&USBInterface> {
+        sp_usb
+            .flatten_devices()
+            .into_iter()
+            .filter_map(|d| d.extra.as_ref())
+            .flat_map(|e| e.configurations.iter())
+            .flat_map(|c| c.interfaces.iter())
+            .collect()
+    }
+
+    #[test]
+    fn test_no_color_env_render_value() {
+        let device = USBDevice {
+            name: "Black Magic Probe".into(),
+            vendor_id: Some(0x1d50),
+            product_id: Some(0x6018),
+            ..Default::default()
+        };
+        let blocks = vec![
+            DeviceBlocks::VendorId,
+            DeviceBlocks::ProductId,
+            DeviceBlocks::Name,
+        ];
+        let mut settings = PrintSettings {
+            colours: Some(colour::ColourTheme::new()),
+            ..Default::default()
+        };
+        let pad = DeviceBlocks::generate_padding(&vec![&device]);
+
+        // unset keeps colours
+        settings.honour_no_color(None);
+        let ct = settings.colours.as_ref().unwrap();
+        assert!(blocks.iter().all(|b| {
+            let value = pad_value(b, &device, &pad, &settings).unwrap();
+            block_colour(b, &device, &value, ct, &settings).is_some()
+        }));
+
+        // set to anything, even empty, disables them so values are rendered plain
+        settings.honour_no_color(Some(OsStr::new("")));
+        assert!(settings.colours.is_none());
+        let plain: Vec<String> = blocks
+            .iter()
+            .filter_map(|b| pad_value(b, &device, &pad, &settings))
+            .collect();
+        assert_eq!(render_value(&device, &blocks, &pad, &settings), plain);
+    }
+
+    #[test]
+    fn test_html_colour_when_auto() {
+        let device = USBDevice {
+            name: "Black Magic Probe".into(),
+            ..Default::default()
+        };
+        let blocks = vec![DeviceBlocks::Name];
+
+        // writing to a file disables escape code colours but not html inline styles
+        let mut settings = PrintSettings {
+            output: Some(PathBuf::from("report.html")),
+            colours: Some(colour::ColourTheme::new()),
+            ..Default::default()
+        };
+        settings.apply_colour_when(ColourWhen::Auto);
+        assert!(settings.colours.is_none());
+
+        let mut settings = PrintSettings {
+            html: true,
+            output: Some(PathBuf::from("report.html")),
+            colours: Some(colour::ColourTheme::new()),
+            ..Default::default()
+        };
+        settings.apply_colour_when(ColourWhen::Auto);
+        let html = render_html_table(&vec![&device], &blocks, &settings).join("\n");
+        assert!(html.contains(&format!(
+            "<span style=\"color:{}\">Black Magic Probe</span>",
+            colour::color_to_css(colour::ColourTheme::new().name.unwrap())
+        )));
+    }
+
+    #[test]
+    fn test_padding_wide_chars() {
+        let wide = USBDevice {
+            name: "USB デバイス".into(),
+            serial_num: Some("0001".into()),
+            ..Default::default()
+        };
+        let narrow = USBDevice {
+            name: "USB Device".into(),
+            serial_num: Some("0002".into()),
+            ..Default::default()
+        };
+        let blocks = vec![DeviceBlocks::Name, DeviceBlocks::Serial];
+        let settings = PrintSettings::default();
+        let pad = DeviceBlocks::generate_padding(&vec![&wide, &narrow]);
+
+        assert_eq!(pad.get(&DeviceBlocks::Name), Some(&"USB デバイス".width()));
+
+        let wide_line = render_value(&wide, &blocks, &pad, &settings).join(" ");
+        let narrow_line = render_value(&narrow, &blocks, &pad, &settings).join(" ");
+        // serial column should start at the same display column
+        let wide_col = wide_line.find("0001").map(|i| wide_line[..i].width());
+        let narrow_col = narrow_line.find("0002").map(|i| narrow_line[..i].width());
+        assert_eq!(wide_col, narrow_col);
+    }
+
+    #[test]
+    fn test_print_to_writer() {
+        let spusb = sp_data();
+        let settings = PrintSettings {
+            tree: true,
+            ..Default::default()
+        };
+        let mut buf: Vec<u8> = Vec::new();
+        print(&spusb, &settings, &mut buf).unwrap();
+        let mut expected = render_sp_usb(&spusb, &settings).join("\n");
+        expected.push('\n');
+        assert_eq!(String::from_utf8(buf).unwrap(), expected);
+
+        let settings = PrintSettings {
+            tree: true,
+            json: true,
+            ..Default::default()
+        };
+        let mut buf: Vec<u8> = Vec::new();
+        print(&spusb, &settings, &mut buf).unwrap();
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            format!("{}\n", serde_json::to_string_pretty(&spusb).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_bus_num_devices() {
+        let spusb = sp_data();
+        let buses: Vec<&USBBus> = spusb.buses.iter().collect();
+        let pad = BusBlocks::generate_padding(&buses);
+        let settings = PrintSettings::default();
+
+        for bus in buses {
+            let num = pad_value(&BusBlocks::NumDevices, bus, &pad, &settings).unwrap();
+            let total = pad_value(&BusBlocks::NumTotalDevices, bus, &pad, &settings).unwrap();
+            assert_eq!(num.width(), BusBlocks::NumDevices.heading(&pad).width());
+            assert_eq!(
+                num.trim().parse::<usize>().unwrap(),
+                bus.devices.as_ref().map_or(0, |d| d.len())
+            );
+            assert_eq!(
+                total.trim().parse::<usize>().unwrap(),
+                bus.flattened_devices().len()
+            );
+        }
+    }
+
+    #[test]
+    fn test_compact_device_lines() {
+        let device = USBDevice {
+            name: "Logitech Receiver".into(),
+            vendor_id: Some(0x046d),
+            product_id: Some(0xc52b),
+            location_id: system_profiler::DeviceLocation {
+                bus: 1,
+                number: 4,
+                tree_positions: vec![2, 3],
+            },
+            serial_num: Some("0001".into()),
+            ..Default::default()
+        };
+        let settings = PrintSettings {
+            compact: true,
+            device_blocks: Some(vec![DeviceBlocks::Serial]),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            render_flattened_devices(&vec![&device], &settings),
+            vec!["1-2.3 046d:c52b Logitech Receiver"]
+        );
+    }
+
+    #[test]
+    fn test_max_depth_truncates_tree() {
+        let leaf = USBDevice {
+            name: "Leaf".into(),
+            ..Default::default()
+        };
+        let hub = USBDevice {
+            name: "Hub".into(),
+            devices: Some(vec![leaf.clone(), leaf]),
+            ..Default::default()
+        };
+        let root = USBDevice {
+            name: "Root".into(),
+            devices: Some(vec![hub]),
+            ..Default::default()
+        };
+        let db = vec![DeviceBlocks::Name];
+        let tree = TreeData {
+            depth: 1,
+            branch_length: 1,
+            ..Default::default()
+        };
+        let mut settings = PrintSettings {
+            tree: true,
+            no_padding: true,
+            max_depth: Some(2),
+            ..Default::default()
+        };
+
+        let lines = render_devices(&vec![root.clone()], &db, &settings, &tree);
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].ends_with("Hub"));
+        assert!(lines[2].ends_with("..."));
+
+        settings.max_depth = None;
+        let lines = render_devices(&vec![root], &db, &settings, &tree);
+        assert_eq!(lines.len(), 4);
+        assert!(lines.iter().all(|l| !l.ends_with("...")));
+    }
+
+    #[test]
+    fn test_attributes_to_icons_ascii_fallback() {
+        let attributes = vec![
+            ConfigAttributes::SelfPowered,
+            ConfigAttributes::RemoteWakeup,
+        ];
+        let settings = PrintSettings {
+            icons: None,
+            ..Default::default()
+        };
+        assert_eq!(attributes_to_icons(&attributes, &settings), "SP RW");
+
+        let settings = PrintSettings {
+            icons: Some(Default::default()),
+            ..Default::default()
+        };
+        let icons = attributes_to_icons(&attributes, &settings);
+        assert!(!icons.is_empty());
+        assert!(!icons.contains("SP"));
+    }
+
+    #[test]
+    fn test_vendor_grouped() {
+        let device = |name: &str, manufacturer: Option<&str>, vid: Option<u16>| USBDevice {
+            name: name.into(),
+            manufacturer: manufacturer.map(String::from),
+            vendor_id: vid,
+            ..Default::default()
+        };
+        let devices = vec![
+            device("Mystery", None, None),
+            device("Probe", Some("black magic"), Some(0x1d50)),
+            device("Receiver", Some("Logitech"), Some(0x046d)),
+            device("Blank", Some(""), Some(0x1234)),
+            device("Keyboard", Some("Logitech"), Some(0x046d)),
+        ];
+        let settings = PrintSettings {
+            no_padding: true,
+            device_blocks: Some(vec![DeviceBlocks::Name]),
+            sort_devices: Sort::NoSort,
+            ..Default::default()
+        };
+
+        let lines = render_vendor_grouped(devices.iter().collect(), &settings);
+        let headings: Vec<&String> = lines
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i == 0 || lines[i - 1].is_empty())
+            .map(|(_, l)| l)
+            .filter(|l| !l.is_empty())
+            .collect();
+        assert_eq!(vendor_group_name(&devices[3]), Some(String::from("0x1234")));
+        assert_eq!(headings.len(), 4);
+        assert!(headings[0].contains("0x1234"));
+        assert!(headings[1].contains("black magic"));
+        assert!(headings[2].contains("Logitech"));
+        assert!(headings[3].contains("Unknown"));
+        assert!(lines.contains(&String::from("Receiver")));
+        assert!(lines.contains(&String::from("Keyboard")));
+    }
+
+    #[test]
+    fn test_num_configurations_and_active() {
+        let sp_usb = sp_data();
+        let settings = PrintSettings::default();
+        let pad = HashMap::new();
+        let device = sp_usb
+            .flatten_devices()
+            .into_iter()
+            .find(|d| d.extra.is_some())
+            .unwrap()
+            .to_owned();
+        let num = device.extra.as_ref().unwrap().configurations.len();
+
+        assert_eq!(
+            pad_value(&DeviceBlocks::NumConfigurations, &device, &pad, &settings),
+            Some(format!("{:4}", num))
+        );
+        let no_extra = USBDevice::default();
+        assert_eq!(
+            pad_value(&DeviceBlocks::NumConfigurations, &no_extra, &pad, &settings),
+            Some(String::from("   -"))
+        );
+
+        let mut config = device.extra.as_ref().unwrap().configurations[0].clone();
+        config.active = false;
+        assert_eq!(
+            pad_value(
+                &ConfigurationBlocks::Number,
+                &config,
+                &HashMap::new(),
+                &settings
+            ),
+            Some(format!("{:2} ", config.number))
+        );
+        config.active = true;
+        assert_eq!(
+            pad_value(
+                &ConfigurationBlocks::Number,
+                &config,
+                &HashMap::new(),
+                &settings
+            ),
+            Some(format!("{:2}*", config.number))
+        );
+    }
+
+    #[test]
+    fn test_speed_vs_max() {
+        let settings = PrintSettings::default();
+        let pad = HashMap::new();
+        let mut device = USBDevice {
+            device_speed: Some(system_profiler::DeviceSpeed::SpeedValue(Speed::SuperSpeed)),
+            bcd_usb: Some(crate::usb::Version::from_bcd(0x0310)),
+            ..Default::default()
+        };
+        assert_eq!(
+            pad_value(&DeviceBlocks::SpeedVsMax, &device, &pad, &settings),
+            Some(format!("{:>11}", "5Gb/10Gb"))
+        );
+        assert_eq!(
+            DeviceBlocks::SpeedVsMax.raw_value(&device, &settings),
+            BlockValue::List(vec![
+                BlockValue::Speed(system_profiler::DeviceSpeed::SpeedValue(Speed::SuperSpeed)),
+                BlockValue::Speed(system_profiler::DeviceSpeed::SpeedValue(
+                    Speed::SuperSpeedPlus
+                )),
+            ])
+        );
+
+        device.bcd_usb = None;
+        assert_eq!(
+            pad_value(&DeviceBlocks::SpeedVsMax, &device, &pad, &settings),
+            Some(format!("{:>11}", "5Gb"))
+        );
+        assert_eq!(
+            DeviceBlocks::SpeedVsMax.raw_value(&device, &settings),
+            BlockValue::List(vec![
+                BlockValue::Speed(system_profiler::DeviceSpeed::SpeedValue(Speed::SuperSpeed)),
+                BlockValue::None,
+            ])
+        );
+
+        device.device_speed = None;
+        assert_eq!(
+            pad_value(&DeviceBlocks::SpeedVsMax, &device, &pad, &settings),
+            Some(format!("{:>11}", "-"))
+        );
+        assert_eq!(
+            DeviceBlocks::SpeedVsMax.raw_value(&device, &settings),
+            BlockValue::None
+        );
+    }
+
+    #[test]
+    fn test_format_value_unpadded() {
+        let device = USBDevice {
+            name: "Hub".into(),
+            ..Default::default()
+        };
+        let pad = HashMap::from([(DeviceBlocks::Name, 8)]);
+        let settings = PrintSettings::default();
+
+        // padding and alignment are left to render_value
+        assert_eq!(
+            DeviceBlocks::Name.format_value(&device, &pad, &settings),
+            Some("Hub".into())
+        );
+        assert_eq!(
+            DeviceBlocks::BusNumber.format_value(&device, &pad, &settings),
+            Some("0".into())
+        );
+        assert_eq!(
+            render_value(
+                &device,
+                &vec![DeviceBlocks::BusNumber, DeviceBlocks::Name],
+                &pad,
+                &settings
+            ),
+            vec!["  0", "Hub     "]
+        );
+    }
+
+    #[test]
+    fn test_alignment_override() {
+        let device = USBDevice {
+            name: "Hub".into(),
+            ..Default::default()
+        };
+        let blocks = vec![DeviceBlocks::Name];
+        let pad = HashMap::from([(DeviceBlocks::Name, 8)]);
+        let mut settings = PrintSettings::default();
+
+        assert_eq!(
+            render_value(&device, &blocks, &pad, &settings),
+            vec!["Hub     "]
+        );
+
+        settings.device_alignment = Some(HashMap::from([(DeviceBlocks::Name, Alignment::Right)]));
+        assert_eq!(
+            render_value(&device, &blocks, &pad, &settings),
+            vec!["     Hub"]
+        );
+
+        settings.device_alignment = Some(HashMap::from([(DeviceBlocks::Name, Alignment::Center)]));
+        assert_eq!(
+            render_value(&device, &blocks, &pad, &settings),
+            vec!["  Hub   "]
+        );
+    }
+
+    #[test]
+    fn test_headings_once_bus_grouped() {
+        let sp_usb = sp_data();
+        let bus_devices = || {
+            sp_usb
+                .buses
+                .iter()
+                .map(|b| (b, b.flattened_devices()))
+                .collect::<Vec<_>>()
+        };
+        let mut settings = PrintSettings {
+            headings: true,
+            device_blocks: Some(vec![DeviceBlocks::VendorId]),
+            bus_blocks: Some(vec![BusBlocks::Name]),
+            ..Default::default()
+        };
+        let device_heading =
+            |lines: &Vec<String>| lines.iter().filter(|l| l.contains("VID")).count();
+
+        let lines = render_bus_grouped(bus_devices(), &settings);
+        assert_eq!(device_heading(&lines), sp_usb.buses.len());
+
+        settings.headings_once = true;
+        let lines = render_bus_grouped(bus_devices(), &settings);
+        assert_eq!(device_heading(&lines), 1);
+        assert!(lines[0].contains("VID"));
+    }
+
+    #[test]
+    fn test_masked_serial_colour() {
+        let ct = colour::ColourTheme::new();
+        let mut device = USBDevice {
+            serial_num: Some("97B6A11D".into()),
+            ..Default::default()
+        };
+        let serial = String::from("97B6A11D");
+        assert_eq!(
+            DeviceBlocks::Serial.value_colour(&device, &serial, &ct),
+            ct.serial
+        );
+
+        mask_serial(&mut device, &MaskSerial::Hide, false);
+        assert!(device.serial_masked);
+        assert_eq!(device.serial_num, Some(String::from("********")));
+        assert_eq!(
+            DeviceBlocks::Serial.value_colour(&device, &serial, &ct),
+            ct.masked_serial
+        );
+        assert_ne!(ct.masked_serial, ct.serial);
+    }
+
+    #[test]
+    fn test_interface_class_name() {
+        let sp_usb = sp_data();
+        let interfaces = all_interfaces(&sp_usb);
+        let settings = PrintSettings::default();
+        let pad = InterfaceBlocks::generate_padding(&interfaces);
+        let longest = interfaces
+            .iter()
+            .map(|i| i.class.to_descriptive_string().width())
+            .max()
+            .unwrap();
+
+        assert_eq!(pad.get(&InterfaceBlocks::ClassName), Some(&longest));
+        for interface in interfaces {
+            let value = pad_value(&InterfaceBlocks::ClassName, interface, &pad, &settings).unwrap();
+            assert_eq!(value.width(), longest);
+            assert_eq!(value.trim_end(), interface.class.to_descriptive_string());
+        }
+    }
+
+    #[test]
+    fn test_truncate_max_widths() {
+        assert_eq!(truncate_width("Black Magic Probe".into(), 8), "Black M…");
+        assert_eq!(truncate_width("Short".into(), 8), "Short");
+        // wide chars are not split and the result never exceeds max
+        assert_eq!(truncate_width("日本語デバイス".into(), 6), "日本…");
+
+        let long = USBDevice {
+            name: "Black Magic Probe".into(),
+            ..Default::default()
+        };
+        let short = USBDevice {
+            name: "Hub".into(),
+            ..Default::default()
+        };
+        let settings = PrintSettings {
+            max_widths: Some(HashMap::from([(DeviceBlocks::Name, 10)])),
+            ..Default::default()
+        };
+        let devices = vec![&long, &short];
+        let pad = flattened_device_padding(&devices, &settings);
+        assert_eq!(pad.get(&DeviceBlocks::Name), Some(&10));
+
+        let blocks = vec![DeviceBlocks::Name];
+        let values: Vec<String> = devices
+            .iter()
+            .map(|d| render_value(*d, &blocks, &pad, &settings).join(""))
+            .collect();
+        assert_eq!(values, vec!["Black Mag…", "Hub       "]);
+    }
+
+    #[test]
+    fn test_indent_guides() {
+        let settings = PrintSettings::default();
+        assert_eq!(render_indent(2, &settings), "    ");
+
+        let settings = PrintSettings {
+            indent_guides: true,
+            ascii_tree: true,
+            ..Default::default()
+        };
+        assert_eq!(render_indent(1, &settings), "| ");
+        assert_eq!(render_indent(3, &settings), "| | | ");
+
+        let settings = PrintSettings {
+            indent_guides: true,
+            icons: Some(icon::IconTheme::new()),
+            ..Default::default()
+        };
+        assert_eq!(render_indent(2, &settings), "\u{2502} \u{2502} ");
+    }
+
+    #[test]
+    fn test_endpoint_address() {
+        let endpoint = USBEndpoint {
+            interval: 10,
+            ..endpoint()
+        };
+        let pad = EndpointBlocks::generate_padding(&vec![&endpoint]);
+        let settings = PrintSettings::default();
+        assert_eq!(
+            pad_value(&EndpointBlocks::Address, &endpoint, &pad, &settings),
+            Some("0x81".into())
+        );
+        assert_eq!(EndpointBlocks::Address.heading(&pad), "Addr");
+
+        let settings = PrintSettings {
+            number_format: NumberFormat::all_decimal(),
+            ..Default::default()
+        };
+        assert_eq!(
+            pad_value(&EndpointBlocks::Address, &endpoint, &pad, &settings)
+                .unwrap()
+                .trim(),
+            "129"
+        );
+    }
+
+    #[test]
+    fn test_diff_devices() {
+        let baseline = sp_data();
+        let mut current = sp_data();
+        current.flatten();
+        let devices = current
+            .buses
+            .iter_mut()
+            .filter_map(|b| b.devices.as_mut())
+            .find(|d| d.len() >= 2)
+            .unwrap();
+        let mut replaced = devices.pop().unwrap();
+        let replaced_path = replaced.port_path();
+        devices[0].name = "Renamed".into();
+        replaced.vendor_id = Some(0xffff);
+        devices.push(replaced);
+
+        let blocks = vec![DeviceBlocks::PortPath, DeviceBlocks::Name];
+        let settings = PrintSettings::default();
+        let diffs = diff_devices(&baseline, &current, &blocks, &settings);
+        assert_eq!(diffs.len(), 3);
+        assert!(diffs
+            .windows(2)
+            .all(|w| port_path_cmp(w[0].device(), w[1].device()) != cmp::Ordering::Greater));
+
+        let at_path = |path: &str| -> Vec<&DeviceDiff> {
+            diffs
+                .iter()
+                .filter(|d| d.device().port_path() == path)
+                .collect()
+        };
+        let replaced_diffs = at_path(&replaced_path);
+        assert_eq!(replaced_diffs.len(), 2);
+        assert!(matches!(replaced_diffs[0], DeviceDiff::Removed(d) if d.vendor_id != Some(0xffff)));
+        assert!(matches!(replaced_diffs[1], DeviceDiff::Added(d) if d.vendor_id == Some(0xffff)));
+        let renamed = diffs
+            .iter()
+            .find(|d| matches!(d, DeviceDiff::Changed(..)))
+            .unwrap();
+        assert!(
+            matches!(renamed, DeviceDiff::Changed(_, new, c) if new.name == "Renamed" && *c == vec![DeviceBlocks::Name])
+        );
+        assert_eq!(at_path(&renamed.device().port_path()).len(), 1);
+
+        let lines = render_diff(&diffs, &blocks, &settings);
+        let changed_line = lines.iter().position(|l| l.starts_with("~ ")).unwrap();
+        assert!(lines[changed_line + 1].starts_with("    Name: "));
+        assert!(lines[changed_line + 1].ends_with(" -> Renamed"));
+        let removed_line = lines.iter().position(|l| l.starts_with("- ")).unwrap();
+        let added_line = lines.iter().position(|l| l.starts_with("+ ")).unwrap();
+        assert!(removed_line < added_line);
+
+        assert!(diff_devices(&baseline, &baseline, &blocks, &settings).is_empty());
+
+        // serials are masked when rendered rather than compared
+        let bmp = baseline
+            .flatten_devices()
+            .into_iter()
+            .find(|d| d.serial_num.as_deref() == Some("97B6A11D"))
+            .unwrap();
+        let settings = PrintSettings {
+            mask_serials: Some(MaskSerial::Hide),
+            ..Default::default()
+        };
+        let lines = render_diff(
+            &vec![DeviceDiff::Removed(bmp)],
+            &vec![DeviceBlocks::Serial],
+            &settings,
+        );
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].trim_end(), "- ********");
+    }
+
+    #[test]
+    fn test_bus_usb_version() {
+        let sp_usb = sp_data();
+        let settings = PrintSettings::default();
+        let pad = HashMap::new();
+        let bus = sp_usb.get_bus(4).unwrap();
+        let value = pad_value(&BusBlocks::UsbVersion, bus, &pad, &settings).unwrap();
+        assert_eq!(
+            value.trim(),
+            bus.get_root_hub_device()
+                .unwrap()
+                .bcd_usb
+                .unwrap()
+                .to_string()
+        );
+        assert_eq!(value.width(), BusBlocks::UsbVersion.heading(&pad).width());
+
+        let bus = USBBus::default();
+        assert_eq!(
+            pad_value(&BusBlocks::UsbVersion, &bus, &pad, &settings),
+            Some("    -".into())
+        );
+        assert!(BusBlocks::default_blocks(true).contains(&BusBlocks::UsbVersion));
+    }
+
+    #[test]
+    fn test_sort_interfaces_endpoints() {
+        let sp_usb = sp_data();
+        let mut interfaces: Vec<USBInterface> = all_interfaces(&sp_usb)
+            .into_iter()
+            .find(|i| i.endpoints.len() >= 2)
+            .cloned()
+            .into_iter()
+            .collect();
+        assert_eq!(interfaces.len(), 1);
+        // descending rather than reversed so the fixture is out of order whatever the dump order
+        interfaces[0]
+            .endpoints
+            .sort_by_key(|e| cmp::Reverse(e.address.address));
+        let mut second = interfaces[0].clone();
+        second.alt_setting = 1;
+        interfaces.insert(0, second);
+        assert!(interfaces
+            .windows(2)
+            .any(|w| (w[0].number, w[0].alt_setting) > (w[1].number, w[1].alt_setting)));
+        assert!(interfaces[0]
+            .endpoints
+            .windows(2)
+            .any(|w| w[0].address.address > w[1].address.address));
+
+        let blocks = (
+            &vec![InterfaceBlocks::AltSetting],
+            &vec![EndpointBlocks::Address],
+        );
+        let settings = PrintSettings {
+            verbosity: 3,
+            no_padding: true,
+            ..Default::default()
+        };
+        let unsorted = join_tree_lines(render_interfaces(
+            &interfaces,
+            blocks,
+            &settings,
+            &Default::default(),
+        ));
+        let settings = PrintSettings {
+            sort_interfaces: true,
+            sort_endpoints: true,
+            ..settings
+        };
+        let sorted = join_tree_lines(render_interfaces(
+            &interfaces,
+            blocks,
+            &settings,
+            &Default::default(),
+        ));
+
+        assert_eq!(
+            unsorted.iter().map(|l| l.trim()).collect::<Vec<&str>>(),
+            vec!["0x01", "0x81", "0x01", "0x00", "0x81", "0x01"]
+        );
+        assert_eq!(
+            sorted.iter().map(|l| l.trim()).collect::<Vec<&str>>(),
+            vec!["0x00", "0x01", "0x81", "0x01", "0x01", "0x81"]
+        );
+    }
+
+    #[test]
+    fn test_human_max_packet_size() {
+        use crate::usb::TransferType;
+
+        let endpoints: Vec<USBEndpoint> = [0x0008, 0x1400]
+            .iter()
+            .map(|&max_packet_size| USBEndpoint {
+                transfer_type: TransferType::Isochronous,
+                max_packet_size,
+                ..endpoint()
+            })
+            .collect();
+        let pad = EndpointBlocks::generate_padding(&endpoints.iter().collect());
+        let blocks = vec![EndpointBlocks::MaxPacketSize];
+
+        let settings = PrintSettings::default();
+        assert_eq!(
+            render_value(&endpoints[1], &blocks, &pad, &settings)[0].trim(),
+            "3x 1024"
+        );
+
+        let settings = PrintSettings {
+            human_sizes: true,
+            ..Default::default()
+        };
+        let values: Vec<String> = endpoints
+            .iter()
+            .map(|e| render_value(e, &blocks, &pad, &settings).join(""))
+            .collect();
+        assert_eq!(values[0].trim(), "1x 8 B");
+        assert_eq!(values[1].trim(), "3x 1.00 KiB");
+        assert_eq!(values[0].width(), values[1].width());
+    }
+
+    #[test]
+    fn test_interface_string_index() {
+        let sp_usb = sp_data();
+        let mut interfaces: Vec<USBInterface> = all_interfaces(&sp_usb)
+            .into_iter()
+            .take(2)
+            .cloned()
+            .collect();
+        interfaces[0].string_index = 0x05;
+        interfaces[1].string_index = 0;
+        let pad = InterfaceBlocks::generate_padding(&interfaces.iter().collect());
+        let settings = PrintSettings::default();
+
+        let values: Vec<String> = interfaces
+            .iter()
+            .map(|i| pad_value(&InterfaceBlocks::StringIndex, i, &pad, &settings).unwrap())
+            .collect();
+        assert_eq!(values, vec!["0x05", "-   "]);
+        assert_eq!(InterfaceBlocks::StringIndex.heading(&pad), "iIf ");
+    }
+
+    #[test]
+    fn test_auto_width_drops_blocks() {
+        let sp_usb = sp_data();
+        let devices = sp_usb.flatten_devices();
+        let settings = PrintSettings {
+            more: true,
+            ..Default::default()
+        };
+        let full = render_flattened_devices(&devices, &settings);
+        assert!(full.iter().any(|l| l.width() > 80));
+
+        let settings = PrintSettings {
+            more: true,
+            auto_width: Some(80),
+            ..Default::default()
+        };
+        let pad = flattened_device_padding(&devices, &settings);
+        let db = fit_device_blocks(
+            flattened_device_blocks(&settings),
+            &devices,
+            &pad,
+            &settings,
+        );
+        assert!(db.contains(&DeviceBlocks::Name));
+        assert!(!db.contains(&DeviceBlocks::Protocol));
+
+        let fitted = render_flattened_devices(&devices, &settings);
+        assert_eq!(fitted.len(), full.len());
+        assert!(fitted.iter().all(|l| l.width() <= 80));
+    }
+
+    #[test]
+    fn test_interface_endpoint_summary() {
+        let sp_usb = sp_data();
+        let bmp = sp_usb
+            .flatten_devices()
+            .into_iter()
+            .find(|d| d.name.starts_with("Black Magic Probe"))
+            .unwrap();
+        let interfaces = &bmp.extra.as_ref().unwrap().configurations[0].interfaces;
+        let pad = InterfaceBlocks::generate_padding(&interfaces.iter().collect());
+        let settings = PrintSettings::default();
+
+        let values: Vec<String> = interfaces
+            .iter()
+            .map(|i| pad_value(&InterfaceBlocks::EndpointSummary, i, &pad, &settings).unwrap())
+            .collect();
+        assert_eq!(
+            values,
+            vec!["1IN     ", "1IN 1OUT", "1IN     ", "1IN 1OUT", "-       ", "1IN     "]
+        );
+        assert_eq!(InterfaceBlocks::EndpointSummary.heading(&pad), "  EPs   ");
+    }
+
+    #[test]
+    fn test_tree_style() {
+        let sp_usb = sp_data();
+        let settings = PrintSettings {
+            tree: true,
+            verbosity: 3,
+            icons: Some(icon::IconTheme::new()),
+            tree_style: icon::TreeStyle::Rounded,
+            ..Default::default()
+        };
+        let lines = render_sp_usb(&sp_usb, &settings);
+        assert!(lines.iter().any(|l| l.starts_with("\u{256D}")));
+        assert!(lines.iter().any(|l| l.contains("\u{2570}\u{2500}\u{2500}")));
+        assert!(!lines.iter().any(|l| l.contains("\u{2514}")));
+
+        let settings = PrintSettings {
+            tree_style: icon::TreeStyle::Heavy,
+            ..settings
+        };
+        let lines = render_sp_usb(&sp_usb, &settings);
+        assert!(lines.iter().any(|l| l.contains("\u{2517}\u{2501}\u{2501}")));
+        assert!(lines.iter().any(|l| l.contains("\u{21D2}")));
+        assert!(!lines.iter().any(|l| l.contains("\u{2502}")));
+    }
+
+    #[test]
+    fn test_number_format_categories() {
+        let device = USBDevice {
+            vendor_id: Some(0x1d50),
+            sub_class: Some(0x02),
+            ..Default::default()
+        };
+        let pad = HashMap::new();
+        let settings = PrintSettings {
+            number_format: NumberFormat::new(&[NumberCategory::Codes]),
+            ..Default::default()
+        };
+        assert_eq!(
+            pad_value(&DeviceBlocks::VendorId, &device, &pad, &settings),
+            Some("0x1d50".into())
+        );
+        assert_eq!(
+            pad_value(&DeviceBlocks::SubClass, &device, &pad, &settings),
+            Some("  2".into())
+        );
+
+        let settings = PrintSettings {
+            number_format: NumberFormat::new(&[NumberCategory::Ids]),
+            ..Default::default()
+        };
+        assert_eq!(
+            pad_value(&DeviceBlocks::VendorId, &device, &pad, &settings),
+            Some("  7504".into())
+        );
+        assert_eq!(
+            pad_value(&DeviceBlocks::SubClass, &device, &pad, &settings),
+            Some("0x02".into())
+        );
+    }
+
+    #[test]
+    fn test_connected_since() {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        // future timestamps saturate
+        assert_eq!(format_age(now + 10), "0s ago");
+        assert_eq!(format_age(now - 60 * 60 * 2 - 30), "2h ago");
+        assert_eq!(format_age(now - 60 * 60 * 24 * 3), "3d ago");
+
+        let mut device = USBDevice {
+            extra: Some(crate::usb::USBDeviceExtra {
+                connected_since: Some(now - 180),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let pad = HashMap::new();
+        let settings = PrintSettings::default();
+        assert_eq!(
+            pad_value(&DeviceBlocks::ConnectedSince, &device, &pad, &settings),
+            Some("  3m ago".into())
+        );
+        assert_eq!(DeviceBlocks::ConnectedSince.heading(&pad), "  Age   ");
+
+        device.extra = None;
+        assert_eq!(
+            pad_value(&DeviceBlocks::ConnectedSince, &device, &pad, &settings),
+            Some("       -".into())
+        );
+    }
+
+    #[test]
+    fn test_align_tree_padding() {
+        let sp_usb = sp_data();
+        // bus with the hub so devices at two depths
+        let devices = sp_usb.buses[1].devices.as_ref().unwrap();
+        let db = vec![DeviceBlocks::Name, DeviceBlocks::Serial];
+        let widths = |settings: &PrintSettings| -> Vec<usize> {
+            render_device_tree(devices, &db, settings, &Default::default())
+                .into_iter()
+                .map(|(_, line)| line.width())
+                .sorted()
+                .collect()
+        };
+
+        let settings = PrintSettings {
+            tree: true,
+            ..Default::default()
+        };
+        assert_eq!(widths(&settings), vec![33, 33, 38, 38]);
+
+        let settings = PrintSettings {
+            tree: true,
+            align_tree: true,
+            ..Default::default()
+        };
+        // serial, the last block, starts at the same column at every depth
+        let serial_offsets: Vec<usize> = join_tree_lines(render_device_tree(
+            devices,
+            &db,
+            &settings,
+            &Default::default(),
+        ))
+        .iter()
+        .map(|l| {
+            let l = l.trim_end();
+            l[..l.rfind(' ').unwrap() + 1].width()
+        })
+        .collect();
+        assert_eq!(serial_offsets.len(), 4);
+        assert!(serial_offsets.iter().all(|&o| o == serial_offsets[0]));
+
+        // and across buses when rendering the whole tree
+        let lines = render_sp_usb(
+            &sp_usb,
+            &PrintSettings {
+                device_blocks: Some(db.clone()),
+                ..settings
+            },
+        );
+        let serial_offsets: Vec<usize> = ["PW3.0", "001050027328", "97B6A11D"]
+            .iter()
+            .map(|s| {
+                let l = lines.iter().find(|l| l.contains(s)).unwrap();
+                l[..l.find(s).unwrap()].width()
+            })
+            .collect();
+        assert!(serial_offsets.iter().all(|&o| o == serial_offsets[0]));
+    }
+
+    #[test]
+    fn test_render_legend() {
+        let mut colours = colour::ColourTheme::new();
+        colours.tree_bus_start = None;
+        let settings = PrintSettings {
+            icons: Some(icon::IconTheme::new()),
+            colours: Some(colours),
+            tree_style: icon::TreeStyle::Rounded,
+            ..Default::default()
+        };
+        let lines = render_legend(&settings);
+        assert!(lines
+            .iter()
+            .any(|l| l.starts_with("\u{2570}\u{2500}\u{2500}")
+                && l.ends_with("Branch to the last item")));
+        assert!(lines
+            .iter()
+            .any(|l| l.ends_with("Unknown vendor without its own icon")));
+        assert!(lines
+            .iter()
+            .any(|l| l.starts_with("tree_bus_start ") && l.ends_with("Tree start of a bus")));
+
+        let settings = PrintSettings {
+            ascii_tree: true,
+            ..Default::default()
+        };
+        let lines = render_legend(&settings);
+        // ascii tree and no device icons
+        assert!(lines
+            .iter()
+            .any(|l| l.starts_with("|__") && l.ends_with("Branch to the last item")));
+        assert!(!lines
+            .iter()
+            .any(|l| l.ends_with("Unknown vendor without its own icon")));
+        assert_eq!(lines.last().unwrap(), "Colours are disabled");
+    }
+
+    #[test]
+    fn test_config_attribute_flags() {
+        let sp_usb = sp_data();
+        let mut config = sp_usb
+            .flatten_devices()
+            .iter()
+            .find_map(|d| d.extra.as_ref())
+            .unwrap()
+            .configurations[0]
+            .clone();
+        let pad = HashMap::new();
+        let settings = PrintSettings::default();
+        let flags = |config: &USBConfiguration| -> (String, String) {
+            (
+                pad_value(&ConfigurationBlocks::SelfPowered, config, &pad, &settings).unwrap(),
+                pad_value(&ConfigurationBlocks::RemoteWakeup, config, &pad, &settings).unwrap(),
+            )
+        };
+
+        config.attributes = vec![ConfigAttributes::SelfPowered];
+        assert_eq!(flags(&config), ("Y ".into(), "- ".into()));
+        config.attributes = vec![
+            ConfigAttributes::RemoteWakeup,
+            ConfigAttributes::SelfPowered,
+        ];
+        assert_eq!(flags(&config), ("Y ".into(), "Y ".into()));
+        config.attributes = vec![];
+        assert_eq!(flags(&config), ("- ".into(), "- ".into()));
+        assert_eq!(ConfigurationBlocks::SelfPowered.heading(&pad), "SP");
+        assert_eq!(ConfigurationBlocks::RemoteWakeup.heading(&pad), "RW");
+    }
+
+    #[test]
+    fn test_hex_dump() {
+        let sp_usb = sp_data();
+        let mut config = sp_usb
+            .flatten_devices()
+            .iter()
+            .find_map(|d| d.extra.as_ref())
+            .unwrap()
+            .configurations[0]
+            .clone();
+        config.interfaces.truncate(1);
+        config.interfaces[0].endpoints.clear();
+        let (number, alt) = (
+            config.interfaces[0].number,
+            config.interfaces[0].alt_setting,
+        );
+        config.set_raw_descriptors(&[
+            0x09, 0x02, 0x12, 0x00, 0x01, 0x01, 0x00, 0x80, 0x32, 0x09, 0x04, number, alt, 0x00,
+            0x09, 0x00, 0x00, 0x00,
+        ]);
+        let settings = PrintSettings {
+            hex: true,
+            verbosity: 2,
+            ..Default::default()
+        };
+        let lines = join_tree_lines(render_configurations(
+            &vec![config],
+            (&vec![ConfigurationBlocks::Number], &vec![], &vec![]),
+            &settings,
+            &TreeData::default(),
+        ));
+        assert_eq!(lines[1], "    0000: 09 02 12 00 01 01 00 80 32");
+        assert!(lines[3].starts_with("      0000: 09 04"));
+
+        // not captured
+        let lines = join_tree_lines(render_hex(&None, &TreeData::default(), 0, 1, &settings));
+        assert_eq!(lines, vec![format!("    {}", HEX_UNAVAILABLE)]);
+    }
+
+    #[test]
+    fn test_semantic_colours() {
+        let sp_usb = sp_data();
+        let devices = sp_usb.flatten_devices();
+        let device = devices
+            .iter()
+            .find(|d| {
+                matches!(
+                    d.device_speed,
+                    Some(system_profiler::DeviceSpeed::SpeedValue(Speed::FullSpeed))
+                )
+            })
+            .unwrap();
+        let ct = colour::ColourTheme::default();
+        let blocks = DeviceBlocks::Speed;
+        let s = String::from("12 Mb/s");
+
+        let settings = PrintSettings::default();
+        assert_eq!(block_colour(&blocks, *device, &s, &ct, &settings), ct.speed);
+        let settings = PrintSettings {
+            semantic_colours: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            block_colour(&blocks, *device, &s, &ct, &settings),
+            Some(Color::Yellow)
+        );
+        // blocks without a gradient keep the theme colour
+        assert_eq!(
+            block_colour(&DeviceBlocks::Name, *device, &s, &ct, &settings),
+            ct.name
+        );
+    }
+
+    #[test]
+    fn test_limit_devices() {
+        let sp_usb = sp_data();
+        let settings = PrintSettings {
+            limit: Some(2),
+            ..Default::default()
+        };
+        let lines = render_flattened_devices(&sp_usb.flatten_devices(), &settings);
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[2], "... (7 more)");
+
+        // per branch in the tree after root hubs are filtered; the hub keeps the first of its two devices
+        let mut sp_usb = sp_usb;
+        let settings = PrintSettings {
+            tree: true,
+            limit: Some(1),
+            ..Default::default()
+        };
+        prepare(
+            &mut sp_usb,
+            Some(system_profiler::USBFilter::new()),
+            &settings,
+        );
+        let lines = render_sp_usb(&sp_usb, &settings);
+        assert!(lines.iter().any(|l| l.contains("J-Link")));
+        assert!(!lines.iter().any(|l| l.contains("Black Magic Probe")));
+        assert!(lines
+            .iter()
+            .any(|l| l.ends_with("... (1 more)") && l.starts_with("  ")));
+    }
+
+    #[test]
+    fn test_interface_association_id() {
+        let sp_usb = sp_data();
+        let mut config = sp_usb
+            .flatten_devices()
+            .iter()
+            .find_map(|d| d.extra.as_ref())
+            .unwrap()
+            .configurations[0]
+            .clone();
+        let first = config.interfaces[0].clone();
+        let mut second = first.clone();
+        second.number = first.number + 1;
+        let mut other = first.clone();
+        other.number = first.number + 2;
+        config.interfaces = vec![first.clone(), second, other];
+        // IAD grouping the first two interfaces as a video function
+        config.set_raw_descriptors(&[
+            0x09,
+            0x02,
+            0x11,
+            0x00,
+            0x03,
+            0x01,
+            0x00,
+            0x80,
+            0x32,
+            0x08,
+            0x0b,
+            first.number,
+            0x02,
+            0x0e,
+            0x03,
+            0x00,
+            0x00,
+        ]);
+
+        let blocks = vec![InterfaceBlocks::AssociationId];
+        let pad = InterfaceBlocks::generate_padding(&config.interfaces.iter().collect());
+        let settings = PrintSettings::default();
+        let values: Vec<String> = config
+            .interfaces
+            .iter()
+            .map(|i| render_value(i, &blocks, &pad, &settings).join(""))
+            .collect();
+        let id = format!("Video/{}", first.number);
+        assert_eq!(values[0].trim(), id);
+        assert_eq!(values[1].trim(), id);
+        assert_eq!(values[2].trim(), "-");
+        assert_eq!(InterfaceBlocks::AssociationId.heading(&pad).trim(), "Assoc");
+    }
+
+    #[test]
+    fn test_num_alt_settings_block() {
+        let sp_usb = sp_data();
+        // counted from the siblings when the configuration is read
+        assert!(all_interfaces(&sp_usb)
+            .iter()
+            .all(|i| i.num_alt_settings > 0));
+
+        let interface = all_interfaces(&sp_usb)[0].clone();
+        let mut interfaces: Vec<USBInterface> = [(0, 0), (0, 1), (0, 2), (1, 0)]
+            .iter()
+            .map(|&(number, alt_setting)| {
+                let mut i = interface.clone();
+                i.number = number;
+                i.alt_setting = alt_setting;
+                i
+            })
+            .collect();
+        crate::usb::set_num_alt_settings(&mut interfaces);
+
+        let settings = PrintSettings::default();
+        let pad = InterfaceBlocks::generate_padding(&interfaces.iter().collect());
+        let values: Vec<String> = interfaces
+            .iter()
+            .filter_map(|i| pad_value(&InterfaceBlocks::NumAltSettings, i, &pad, &settings))
+            .map(|v| v.trim().to_string())
+            .collect();
+        assert_eq!(values, vec!["3", "3", "3", "1"]);
+        assert_eq!(
+            InterfaceBlocks::NumAltSettings.raw_value(&interfaces[0], &settings),
+            BlockValue::U64(3)
+        );
+    }
+
+    #[test]
+    fn test_collapse_alt_settings() {
+        let sp_usb = sp_data();
+        let interface = all_interfaces(&sp_usb)[0].clone();
+        let interfaces: Vec<USBInterface> = [(0, 0), (0, 1), (0, 2), (1, 0)]
+            .iter()
+            .map(|&(number, alt_setting)| {
+                let mut i = interface.clone();
+                i.number = number;
+                i.alt_setting = alt_setting;
+                i
+            })
+            .collect();
+        let blocks = (&vec![InterfaceBlocks::Number], &vec![]);
+
+        let settings = PrintSettings {
+            collapse_alt_settings: true,
+            verbosity: 2,
+            ..Default::default()
+        };
+        let lines = join_tree_lines(render_interfaces(
+            &interfaces,
+            blocks,
+            &settings,
+            &TreeData::default(),
+        ));
+        assert_eq!(lines, vec!["     0 (+2 alt)", "     1"]);
+
+        // expanded at maximum verbosity
+        let settings = PrintSettings {
+            collapse_alt_settings: true,
+            verbosity: 4,
+            ..Default::default()
+        };
+        // endpoint rows are empty without endpoint blocks
+        let lines = render_interfaces(&interfaces, blocks, &settings, &TreeData::default());
+        assert_eq!(lines.iter().filter(|(_, l)| !l.is_empty()).count(), 4);
+    }
+
+    #[test]
+    fn test_output_file() {
+        use std::io::Write;
+        let path = env::temp_dir().join("cyme_test_output_file.txt");
+        let mut settings = PrintSettings {
+            colours: Some(colour::ColourTheme::new()),
+            output: Some(path.clone()),
+            ..Default::default()
+        };
+        settings.apply_colour_when(ColourWhen::Auto);
+        assert!(settings.colours.is_none());
+
+        let mut writer = settings.output_writer().unwrap();
+        writeln!(writer, "cyme").unwrap();
+        writer.flush().unwrap();
+        drop(writer);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "cyme\n");
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_vendor_block() {
+        let mut device = USBDevice {
+            vendor_id: Some(0x1d50),
+            manufacturer: Some("Black Sphere Technologies".into()),
+            extra: Some(crate::usb::USBDeviceExtra {
+                vendor: Some("OpenMoko, Inc.".into()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let pad = HashMap::new();
+        let settings = PrintSettings::default();
+        assert_eq!(
+            pad_value(&DeviceBlocks::Vendor, &device, &pad, &settings),
+            Some("Black Sphere Technologies".into())
+        );
+
+        device.manufacturer = None;
+        assert_eq!(
+            pad_value(&DeviceBlocks::Vendor, &device, &pad, &settings),
+            Some("OpenMoko, Inc.".into())
+        );
+
+        device.extra = None;
+        assert_eq!(
+            pad_value(&DeviceBlocks::Vendor, &device, &pad, &settings),
+            Some("0x1d50".into())
+        );
+        assert_eq!(DeviceBlocks::Vendor.heading(&pad), "Vendor");
+    }
+
+    #[test]
+    fn test_power_state_block() {
+        let mut device = USBDevice {
+            extra: Some(crate::usb::USBDeviceExtra {
+                power_state: Some("suspended".into()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let pad = HashMap::new();
+        let settings = PrintSettings::default();
+        assert_eq!(
+            pad_value(&DeviceBlocks::PowerState, &device, &pad, &settings),
+            Some("suspended ".into())
+        );
+        assert_eq!(DeviceBlocks::PowerState.heading(&pad), "  PwrSt   ");
+
+        device.extra = None;
+        assert_eq!(
+            pad_value(&DeviceBlocks::PowerState, &device, &pad, &settings),
+            Some("-         ".into())
+        );
+    }
+
+    #[test]
+    fn test_ascii_tree_independent_of_icons() {
+        let sp_usb = sp_data();
+        // icons with ascii tree
+        let settings = PrintSettings {
+            tree: true,
+            icons: Some(icon::IconTheme::new()),
+            ascii_tree: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            tree_icon(&icon::Icon::TreeCorner, &settings),
+            icon::get_ascii_tree_icon(&icon::Icon::TreeCorner)
+        );
+        let lines = render_sp_usb(&sp_usb, &settings);
+        assert!(!lines.iter().any(|l| l.contains("\u{2514}")));
+
+        // no icons with utf-8 tree
+        let settings = PrintSettings {
+            icons: None,
+            ascii_tree: false,
+            ..settings
+        };
+        assert_eq!(
+            tree_icon(&icon::Icon::TreeCorner, &settings),
+            "\u{2514}\u{2500}\u{2500}"
+        );
+        let lines = render_sp_usb(&sp_usb, &settings);
+        assert!(lines.iter().any(|l| l.contains("\u{2514}")));
+    }
+
+    #[test]
+    fn test_interval_time_block() {
+        let mut endpoint = USBEndpoint {
+            max_packet_size: 0x0040,
+            device_speed: Some(Speed::HighSpeed),
+            ..endpoint()
+        };
+        let pad = HashMap::new();
+        let settings = PrintSettings::default();
+        assert_eq!(
+            pad_value(&EndpointBlocks::IntervalTime, &endpoint, &pad, &settings),
+            Some("  125 µs".into())
+        );
+        endpoint.interval = 6;
+        assert_eq!(
+            pad_value(&EndpointBlocks::IntervalTime, &endpoint, &pad, &settings),
+            Some("    4 ms".into())
+        );
+        endpoint.device_speed = Some(Speed::FullSpeed);
+        assert_eq!(
+            pad_value(&EndpointBlocks::IntervalTime, &endpoint, &pad, &settings),
+            Some("    6 ms".into())
+        );
+        // speed unknown without libusb
+        endpoint.device_speed = None;
+        assert_eq!(
+            pad_value(&EndpointBlocks::IntervalTime, &endpoint, &pad, &settings),
+            Some("       -".into())
+        );
+        assert_eq!(EndpointBlocks::IntervalTime.heading(&pad), " IvTime ");
+    }
+
+    #[test]
+    fn test_pad_positions() {
+        let shallow = USBDevice {
+            location_id: system_profiler::DeviceLocation {
+                tree_positions: vec![2],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let deep = USBDevice {
+            location_id: system_profiler::DeviceLocation {
+                tree_positions: vec![2, 10, 3],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let devices = vec![&shallow, &deep];
+        let settings = PrintSettings {
+            pad_positions: true,
+            ..Default::default()
+        };
+        let pad = flattened_device_padding(&devices, &settings);
+        assert_eq!(pad.get(&DeviceBlocks::TreePositions), Some(&8));
+        let values: Vec<String> = devices
+            .iter()
+            .map(|d| pad_value(&DeviceBlocks::TreePositions, d, &pad, &settings).unwrap())
+            .collect();
+        assert_eq!(values, vec!["02      ", "02-10-03"]);
+
+        let settings = PrintSettings::default();
+        assert_eq!(
+            pad_value(
+                &DeviceBlocks::TreePositions,
+                &deep,
+                &HashMap::new(),
+                &settings
+            ),
+            Some("2-10-3".into())
+        );
+    }
+
+    #[test]
+    fn test_bus_driver_block() {
+        let mut bus = USBBus {
+            name: "xHCI Host Controller".into(),
+            driver: Some("xhci_hcd".into()),
+            ..Default::default()
+        };
+        let pad = BusBlocks::generate_padding(&vec![&bus]);
+        let settings = PrintSettings::default();
+        assert_eq!(
+            pad_value(&BusBlocks::Driver, &bus, &pad, &settings),
+            Some("xhci_hcd".into())
+        );
+        assert_eq!(BusBlocks::Driver.heading(&pad), " Driver ");
+
+        bus.driver = None;
+        assert_eq!(
+            pad_value(&BusBlocks::Driver, &bus, &HashMap::new(), &settings),
+            Some("-".into())
+        );
+
+        let settings = PrintSettings {
+            verbosity: 1,
+            ..Default::default()
+        };
+        assert!(BusBlocks::default_blocks_for(&settings).contains(&BusBlocks::Driver));
+        assert!(
+            !BusBlocks::default_blocks_for(&PrintSettings::default()).contains(&BusBlocks::Driver)
+        );
+    }
+
+    #[test]
+    fn test_sort_port_path() {
+        let device = |bus: u8, tree_positions: Vec<u8>| USBDevice {
+            location_id: system_profiler::DeviceLocation {
+                bus,
+                tree_positions,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let devices = vec![
+            device(1, vec![10]),
+            device(2, vec![1]),
+            device(1, vec![2, 3]),
+            device(1, vec![2]),
+        ];
+        let sorted: Vec<String> = Sort::PortPath
+            .sort_devices(&devices)
+            .iter()
+            .map(|d| d.port_path())
+            .collect();
+        assert_eq!(sorted, vec!["1-2", "1-2.3", "1-10", "2-1"]);
+
+        let refs: Vec<&USBDevice> = devices.iter().collect();
+        let sorted_refs: Vec<String> = Sort::PortPath
+            .sort_devices_ref(&refs)
+            .iter()
+            .map(|d| d.port_path())
+            .collect();
+        assert_eq!(sorted, sorted_refs);
+
+        let mut buses = vec![
+            USBBus {
+                usb_bus_number: Some(10),
+                ..Default::default()
+            },
+            USBBus {
+                usb_bus_number: Some(2),
+                ..Default::default()
+            },
+        ];
+        BusSort::PortPath.sort_buses(&mut buses);
+        assert_eq!(buses[0].get_bus_number(), 2);
+    }
+
+    #[test]
+    fn test_bus_sort_flat_listing() {
+        let mut sp_usb = sp_data();
+        // names sort in reverse bus number order
+        for bus in sp_usb.buses.iter_mut() {
+            bus.name = format!("{}", 9 - bus.get_bus_number());
+        }
+        let settings = PrintSettings {
+            json: true,
+            sort_buses: BusSort::Name,
+            ..Default::default()
+        };
+        prepare(&mut sp_usb, None, &settings);
+
+        let mut buses: Vec<u8> = sp_usb
+            .flatten_devices()
+            .iter()
+            .map(|d| d.location_id.bus)
+            .collect();
+        buses.dedup();
+        assert_eq!(buses, vec![4, 3, 2, 1]);
+    }
+
+    #[test]
+    fn test_pager_writer() {
+        use std::io::Write;
+        let mut pager = PagerWriter::spawn(Some(OsStr::new("cat"))).unwrap();
+        writeln!(pager, "cyme").unwrap();
+        pager.flush().unwrap();
+        drop(pager);
+
+        let settings = PrintSettings {
+            pager: true,
+            ..Default::default()
+        };
+        // not a terminal so falls back to stdout without spawning
+        assert!(settings
+            .output_writer_with(Some(OsStr::new("cat")), false)
+            .is_ok());
+        // terminal spawns the pager
+        let mut writer = settings
+            .output_writer_with(Some(OsStr::new("cat")), true)
+            .unwrap();
+        writeln!(writer, "cyme").unwrap();
+        writer.flush().unwrap();
+    }
+
+    #[test]
+    fn test_prune_interfaces() {
+        let sp_usb = sp_data();
+        let interface = all_interfaces(&sp_usb)[0].clone();
+        let interfaces: Vec<USBInterface> =
+            [ClassCode::HID, ClassCode::MassStorage, ClassCode::HID]
+                .iter()
+                .enumerate()
+                .map(|(n, c)| {
+                    let mut i = interface.clone();
+                    i.number = n as u8;
+                    i.class = c.to_owned();
+                    i
+                })
+                .collect();
+
+        let settings = PrintSettings {
+            prune_interfaces: Some(ClassCode::HID),
+            ..Default::default()
+        };
+        let pruned = prune_interfaces(&interfaces, &settings);
+        assert_eq!(
+            pruned.iter().map(|i| i.number).collect::<Vec<u8>>(),
+            vec![0, 2]
+        );
+
+        // kept as is without
+        assert_eq!(
+            prune_interfaces(&interfaces, &PrintSettings::default()).len(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_full_path_block() {
+        let mut device = USBDevice {
+            location_id: system_profiler::DeviceLocation {
+                bus: 2,
+                number: 5,
+                tree_positions: vec![1, 4, 1],
+            },
+            ..Default::default()
+        };
+        let pad = HashMap::new();
+        let settings = PrintSettings::default();
+        assert_eq!(
+            pad_value(&DeviceBlocks::FullPath, &device, &pad, &settings),
+            Some("2-1.4.1".into())
+        );
+
+        // root_hub is the bus rather than its interface
+        device.location_id.tree_positions = vec![];
+        assert_eq!(
+            pad_value(&DeviceBlocks::FullPath, &device, &pad, &settings),
+            Some("2-0".into())
+        );
+        assert_eq!(
+            pad_value(&DeviceBlocks::PortPath, &device, &pad, &settings),
+            Some("2-0:1.0".into())
+        );
+        assert_eq!(DeviceBlocks::FullPath.heading(&pad), "FPath");
+    }
+
+    #[test]
+    fn test_prefer_usb_ids() {
+        let device = USBDevice {
+            name: "USB DEVICE".into(),
+            vendor_id: Some(0x1d50),
+            manufacturer: Some("Generic".into()),
+            extra: Some(crate::usb::USBDeviceExtra {
+                vendor: Some("OpenMoko, Inc.".into()),
+                product_name: Some("Black Magic Debug Probe".into()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let pad = HashMap::new();
+        let blocks = [
+            DeviceBlocks::Name,
+            DeviceBlocks::Manufacturer,
+            DeviceBlocks::Vendor,
+        ];
+
+        let settings = PrintSettings::default();
+        let values: Vec<String> = blocks
+            .iter()
+            .filter_map(|b| pad_value(b, &device, &pad, &settings))
+            .collect();
+        assert_eq!(values, vec!["USB DEVICE", "Generic", "Generic"]);
+
+        let settings = PrintSettings {
+            prefer_usb_ids: true,
+            ..Default::default()
+        };
+        let values: Vec<String> = blocks
+            .iter()
+            .filter_map(|b| pad_value(b, &device, &pad, &settings))
+            .collect();
+        assert_eq!(
+            values,
+            vec![
+                "Black Magic Debug Probe",
+                "OpenMoko, Inc.",
+                "OpenMoko, Inc."
+            ]
+        );
+        assert_eq!(
+            DeviceBlocks::Name.raw_value(&device, &settings),
+            BlockValue::Str("Black Magic Debug Probe".into())
+        );
+
+        // padded to the usb_ids names
+        let pad = flattened_device_padding(&vec![&device], &settings);
+        assert_eq!(pad.get(&DeviceBlocks::Name), Some(&23));
+    }
+
+    #[test]
+    fn test_raw_value() {
+        let sp_usb = sp_data();
+        let devices = sp_usb.flatten_devices();
+        let device = devices.iter().find(|d| d.extra.is_some()).unwrap();
+        let settings = PrintSettings::default();
+
+        assert_eq!(
+            DeviceBlocks::VendorId.raw_value(device, &settings),
+            BlockValue::from(device.vendor_id)
+        );
+        assert_eq!(
+            DeviceBlocks::Name.raw_value(device, &settings),
+            BlockValue::Str(device.name.to_owned())
+        );
+        assert_eq!(
+            DeviceBlocks::Icon.raw_value(device, &settings),
+            BlockValue::None
+        );
+
+        let config = &device.extra.as_ref().unwrap().configurations[0];
+        assert_eq!(
+            ConfigurationBlocks::MaxPower.raw_value(config, &settings),
+            BlockValue::U32(config.max_power.value)
+        );
+        let interface = &config.interfaces[0];
+        assert_eq!(
+            InterfaceBlocks::ClassCode.raw_value(interface, &settings),
+            BlockValue::ClassCode(interface.class.to_owned())
+        );
+
+        // numbers are json numbers rather than padded strings
+        let endpoint = USBEndpoint {
+            interval: 4,
+            device_speed: Some(Speed::FullSpeed),
+            ..endpoint()
+        };
+        assert_eq!(
+            serde_json::to_value(EndpointBlocks::MaxPacketSize.raw_value(&endpoint, &settings))
+                .unwrap(),
+            serde_json::json!(64)
+        );
+        assert_eq!(
+            EndpointBlocks::IntervalTime.raw_value(&endpoint, &settings),
+            BlockValue::U32(4000)
+        );
+        assert_eq!(
+            serde_json::to_value(BlockValue::None).unwrap(),
+            serde_json::Value::Null
+        );
+    }
+
+    #[test]
+    fn test_summary_bold_only_with_colours() {
+        let sp_usb = sp_data();
+        let mut settings = PrintSettings {
+            colours: Some(colour::ColourTheme::new()),
+            ..Default::default()
+        };
+        assert!(summary_line(&sp_usb, &settings)
+            .style()
+            .contains(colored::Styles::Bold));
+        // --color never or writing to a file
+        settings.colours = None;
+        assert!(summary_line(&sp_usb, &settings).is_plain());
+        assert_eq!(
+            render_summary(&sp_usb, &settings),
+            "4 buses, 9 devices, 5 hubs"
+        );
+    }
+
+    #[test]
+    fn test_rainbow_path() {
+        let mut ct = colour::ColourTheme::preset(colour::ColourPreset::Mono);
+        ct.path = Some(Color::Red);
+        ct.location = Some(Color::Green);
+        ct.vid = Some(Color::Blue);
+        let colours = ct.depth_colours();
+        let segment = |s: &str, c: Option<Color>| (String::from(s), c);
+
+        // bus and each depth coloured in turn, separators and padding left
+        assert_eq!(
+            path_depth_segments("2-1.4.1 ", 0, &colours),
+            vec![
+                segment("2", Some(Color::Red)),
+                segment("-", None),
+                segment("1", Some(Color::Green)),
+                segment(".", None),
+                segment("4", Some(Color::Blue)),
+                segment(".", None),
+                segment("1", Some(Color::Red)),
+                segment(" ", None),
+            ]
+        );
+        // positions start at the first hub depth so match the port path colours
+        assert_eq!(
+            path_depth_segments("1-4", 1, &colours),
+            vec![
+                segment("1", Some(Color::Green)),
+                segment("-", None),
+                segment("4", Some(Color::Blue)),
+            ]
+        );
+        // nothing to rotate through
+        assert_eq!(
+            colour_path_depths(
+                "2-1.4",
+                0,
+                &colour::ColourTheme::preset(colour::ColourPreset::Mono)
+            ),
+            "2-1.4"
+        );
+    }
+
+    #[test]
+    fn test_max_burst_block() {
+        use crate::usb::{EndpointCompanion, TransferType};
+
+        let mut endpoint = USBEndpoint {
+            transfer_type: TransferType::Bulk,
+            max_packet_size: 1024,
+            interval: 0,
+            device_speed: Some(Speed::HighSpeed),
+            ..endpoint()
+        };
+        let pad = HashMap::new();
+        let settings = PrintSettings::default();
+        assert_eq!(
+            pad_value(&EndpointBlocks::MaxBurst, &endpoint, &pad, &settings),
+            Some("    -".into())
+        );
+
+        endpoint.device_speed = Some(Speed::SuperSpeed);
+        endpoint.companion =
+            EndpointCompanion::from_descriptors(&[0x06, 0x30, 0x0f, 0x00, 0x00, 0x00]);
+        assert_eq!(
+            pad_value(&EndpointBlocks::MaxBurst, &endpoint, &pad, &settings),
+            Some("   15".into())
+        );
+        assert_eq!(
+            EndpointBlocks::MaxBurst.raw_value(&endpoint, &settings),
+            BlockValue::U8(15)
+        );
+        assert_eq!(EndpointBlocks::MaxBurst.heading(&pad), "Burst");
+    }
+
+    #[test]
+    fn test_total_length_block() {
+        use crate::types::NumericalUnit;
+
+        let mut config = USBConfiguration {
+            name: String::new(),
+            string_index: 0,
+            number: 1,
+            interfaces: Vec::new(),
+            attributes: Vec::new(),
+            max_power: NumericalUnit {
+                value: 100,
+                unit: "mA".into(),
+                description: None,
+            },
+            active: true,
+            raw: None,
+        };
+        let pad = HashMap::new();
+        let settings = PrintSettings::default();
+        assert_eq!(
+            pad_value(&ConfigurationBlocks::TotalLength, &config, &pad, &settings),
+            Some("   -".into())
+        );
+        assert_eq!(
+            ConfigurationBlocks::TotalLength.raw_value(&config, &settings),
+            BlockValue::None
+        );
+
+        config.raw = Some(vec![0x09, 0x02, 0x20, 0x01, 0x01, 0x01, 0x00, 0x80, 0x32]);
+        assert_eq!(
+            pad_value(&ConfigurationBlocks::TotalLength, &config, &pad, &settings),
+            Some(" 288".into())
+        );
+        assert_eq!(
+            ConfigurationBlocks::TotalLength.raw_value(&config, &settings),
+            BlockValue::U16(288)
+        );
+        assert_eq!(ConfigurationBlocks::TotalLength.heading(&pad), " Len");
+    }
+
+    #[test]
+    fn test_indent_step() {
+        let settings = PrintSettings {
+            indent_step: Some(4),
+            ..Default::default()
+        };
+        assert_eq!(render_indent(1, &settings), "    ");
+        assert_eq!(render_indent(3, &settings).len(), 12);
+
+        let settings = PrintSettings {
+            indent_step: Some(0),
+            indent_guides: true,
+            ..Default::default()
+        };
+        assert_eq!(render_indent(2, &settings), "");
+
+        let settings = PrintSettings {
+            indent_step: Some(3),
+            indent_guides: true,
+            ascii_tree: true,
+            ..Default::default()
+        };
+        assert_eq!(render_indent(2, &settings), "|  |  ");
+    }
+
+    #[test]
+    fn test_name_source_block() {
+        use crate::system_profiler::NameSource;
+
+        let mut device = USBDevice {
+            name: "USB DEVICE".into(),
+            name_source: Some(NameSource::Descriptor),
+            extra: Some(crate::usb::USBDeviceExtra {
+                product_name: Some("Black Magic Debug Probe".into()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let pad = HashMap::new();
+        let mut settings = PrintSettings::default();
+        assert_eq!(
+            pad_value(&DeviceBlocks::NameSource, &device, &pad, &settings),
+            Some("desc".into())
+        );
+        assert_eq!(
+            DeviceBlocks::NameSource.raw_value(&device, &settings),
+            BlockValue::Str("desc".into())
+        );
+
+        // name shown is then the usb_ids product name
+        settings.prefer_usb_ids = true;
+        assert_eq!(
+            pad_value(&DeviceBlocks::NameSource, &device, &pad, &settings),
+            Some("ids ".into())
+        );
+
+        settings.prefer_usb_ids = false;
+        device.name_source = None;
+        assert_eq!(
+            pad_value(&DeviceBlocks::NameSource, &device, &pad, &settings),
+            Some("-   ".into())
+        );
+        device.name_source = Some(NameSource::Unknown);
+        assert_eq!(
+            DeviceBlocks::NameSource.raw_value(&device, &settings),
+            BlockValue::None
+        );
+        assert_eq!(DeviceBlocks::NameSource.heading(&pad), "Src ");
+    }
+
+    #[test]
+    fn test_current_alignment() {
+        let devices = [
+            USBDevice {
+                bus_power: Some(500),
+                bus_power_used: Some(2),
+                ..Default::default()
+            },
+            USBDevice {
+                bus_power: None,
+                bus_power_used: Some(1500),
+                ..Default::default()
+            },
+        ];
+        let refs: Vec<&USBDevice> = devices.iter().collect();
+        let pad = DeviceBlocks::generate_padding(&refs);
+        let settings = PrintSettings::default();
+
+        let column = |b: DeviceBlocks| -> Vec<String> {
+            devices
+                .iter()
+                .filter_map(|d| pad_value(&b, d, &pad, &settings))
+                .collect()
+        };
+        assert_eq!(column(DeviceBlocks::BusPower), vec!["500 mA", "     -"]);
+        assert_eq!(
+            column(DeviceBlocks::BusPowerUsed),
+            vec!["   2 mA", "1500 mA"]
+        );
+        assert_eq!(
+            column(DeviceBlocks::ExtraCurrentUsed),
+            vec!["     -", "     -"]
+        );
+        assert_eq!(DeviceBlocks::BusPowerUsed.heading(&pad), "   PUsd");
+
+        let config = USBConfiguration {
+            name: String::new(),
+            string_index: 0,
+            number: 1,
+            interfaces: Vec::new(),
+            attributes: Vec::new(),
+            max_power: crate::types::NumericalUnit {
+                value: 98,
+                unit: "mA".into(),
+                description: None,
+            },
+            active: true,
+            raw: None,
+        };
+        let pad = ConfigurationBlocks::generate_padding(&vec![&config]);
+        assert_eq!(
+            pad_value(&ConfigurationBlocks::MaxPower, &config, &pad, &settings),
+            Some(" 98 mA".into())
+        );
+        assert_eq!(ConfigurationBlocks::MaxPower.heading(&pad), "  PMax");
+    }
+
+    #[test]
+    fn test_tags_block() {
+        let mut device = USBDevice {
+            extra: Some(crate::usb::USBDeviceExtra {
+                tags: Some(vec!["seat".into(), "uaccess".into()]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let settings = PrintSettings::default();
+        let pad = DeviceBlocks::generate_padding(&vec![&device]);
+        assert_eq!(
+            pad_value(&DeviceBlocks::Tags, &device, &pad, &settings),
+            Some("seat,uaccess".into())
+        );
+        assert_eq!(
+            DeviceBlocks::Tags.raw_value(&device, &settings),
+            BlockValue::Str("seat,uaccess".into())
+        );
+        assert_eq!(DeviceBlocks::Tags.heading(&pad), "    Tags    ");
+
+        device.extra.as_mut().unwrap().tags = Some(Vec::new());
+        let pad = DeviceBlocks::generate_padding(&vec![&device]);
+        assert_eq!(
+            pad_value(&DeviceBlocks::Tags, &device, &pad, &settings),
+            Some("-   ".into())
+        );
+        assert_eq!(
+            DeviceBlocks::Tags.raw_value(&device, &settings),
+            BlockValue::None
+        );
+    }
+
+    #[test]
+    fn test_group_sort() {
+        let spusb = sp_data();
+
+        let mut buses: Vec<&USBBus> = spusb.buses.iter().collect();
+        GroupSort::CountDesc.sort_buses(&mut buses);
+        let counts: Vec<usize> = buses.iter().map(|b| b.flattened_devices().len()).collect();
+        assert!(counts.windows(2).all(|w| w[0] >= w[1]));
+        GroupSort::Count.sort_buses(&mut buses);
+        let counts: Vec<usize> = buses.iter().map(|b| b.flattened_devices().len()).collect();
+        assert!(counts.windows(2).all(|w| w[0] <= w[1]));
+
+        // busiest vendor heading first
+        let mut groups = vendor_groups(spusb.flatten_devices());
+        GroupSort::CountDesc.sort_groups(&mut groups);
+        let settings = PrintSettings {
+            group_devices: Group::Vendor,
+            group_sort: Some(GroupSort::CountDesc),
+            ..Default::default()
+        };
+        let lines = render_vendor_grouped(spusb.flatten_devices(), &settings);
+        assert_eq!(lines[0], groups[0].0.bold().to_string());
+        assert!(groups.windows(2).all(|w| w[0].1.len() >= w[1].1.len()));
+    }
 }
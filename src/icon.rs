@@ -1,4 +1,6 @@
 //! Icons and themeing of cyme output
+use clap::ValueEnum;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_with::{DeserializeFromStr, SerializeDisplay};
 use std::collections::HashMap;
@@ -18,6 +20,8 @@ pub enum Icon {
     VidPid((u16, u16)),
     /// Use to mask on msb of product ID
     VidPidMsb((u16, u8)),
+    /// Vendor ID, Product ID and serial number exact match; allows otherwise identical devices to have unique icons
+    VidPidSerial((u16, u16, String)),
     /// Class classifier icon
     Classifier(ClassCode),
     /// Class classifier lookup with SubClass and Protocol
@@ -73,6 +77,30 @@ impl FromStr for Icon {
                     "Invalid Icon enum name or valued enum without value",
                 )),
             }
+        // serial is a string so parse separately; splitn as serial could contain ':'
+        } else if enum_name == "vid-pid-serial" {
+            let value_str = value_split[1..].join("#");
+            let values: Vec<&str> = value_str.splitn(3, ":").collect();
+
+            match values.as_slice() {
+                [vid, pid, serial] if !serial.is_empty() => {
+                    let (vid, pid) = u16::from_str_radix(vid.trim_start_matches("0x"), 16)
+                        .and_then(|v| {
+                            u16::from_str_radix(pid.trim_start_matches("0x"), 16).map(|p| (v, p))
+                        })
+                        .map_err(|_| {
+                            io::Error::new(
+                                io::ErrorKind::Other,
+                                "Invalid value in enum string after #",
+                            )
+                        })?;
+                    Ok(Icon::VidPidSerial((vid, pid, serial.to_string())))
+                }
+                _ => Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    "No value for enum after $",
+                )),
+            }
         // enum contains value
         } else {
             let (parse_ints, errors): (Vec<Result<u32, _>>, Vec<_>) = value_split[1]
@@ -146,6 +174,9 @@ impl fmt::Display for Icon {
     /// let icon: Icon = Icon::VidPid((0x1d50, 0x6018));
     /// assert_eq!(format!("{}", icon), "vid-pid#1d50:6018");
     ///
+    /// let icon: Icon = Icon::VidPidSerial((0x1d50, 0x6018, "97B6A11D".into()));
+    /// assert_eq!(format!("{}", icon), "vid-pid-serial#1d50:6018:97B6A11D");
+    ///
     /// let icon: Icon = Icon::UnknownVendor;
     /// assert_eq!(format!("{}", icon), "unknown-vendor");
     /// ```
@@ -155,6 +186,7 @@ impl fmt::Display for Icon {
             Icon::Vid(v) => write!(f, "vid#{:04x}", v),
             Icon::VidPid((v, p)) => write!(f, "vid-pid#{:04x}:{:04x}", v, p),
             Icon::VidPidMsb((v, p)) => write!(f, "vid-pid-msb#{:04x}:{:02x}", v, p),
+            Icon::VidPidSerial((v, p, s)) => write!(f, "vid-pid-serial#{:04x}:{:04x}:{}", v, p, s),
             Icon::Classifier(c) => write!(f, "classifier#{:02x}", c.to_owned() as u8),
             Icon::ClassifierSubProtocol(c) => write!(
                 f,
@@ -173,10 +205,69 @@ impl fmt::Display for Icon {
     }
 }
 
+impl Icon {
+    /// Icons used to draw the tree in order of a tree branch
+    pub fn tree_icons() -> Vec<Icon> {
+        vec![
+            Icon::TreeBusStart,
+            Icon::TreeEdge,
+            Icon::TreeLine,
+            Icon::TreeCorner,
+            Icon::TreeBlank,
+            Icon::TreeDeviceTerminator,
+            Icon::TreeConfigurationTerminator,
+            Icon::TreeInterfaceTerminator,
+            Icon::Endpoint(Direction::In),
+            Icon::Endpoint(Direction::Out),
+        ]
+    }
+
+    /// Description of what the icon represents for a legend
+    ///
+    /// ```
+    /// use cyme::icon::Icon;
+    ///
+    /// assert_eq!(Icon::Vid(0x1d50).description(), "Vendor ID 1d50");
+    /// assert_eq!(Icon::TreeBusStart.description(), "Start of a bus");
+    /// ```
+    pub fn description(&self) -> String {
+        match self {
+            Icon::Vid(v) => format!("Vendor ID {:04x}", v),
+            Icon::VidPid((v, p)) => format!("Vendor ID {:04x} and Product ID {:04x}", v, p),
+            Icon::VidPidMsb((v, p)) => {
+                format!("Vendor ID {:04x} and Product ID MSB {:02x}", v, p)
+            }
+            Icon::VidPidSerial((v, p, s)) => {
+                format!("Vendor ID {:04x}, Product ID {:04x} and serial {}", v, p, s)
+            }
+            Icon::Classifier(c) => format!("{} class", c.to_descriptive_string()),
+            Icon::ClassifierSubProtocol((c, s, p)) => format!(
+                "{} class with sub-class {:02x} and protocol {:02x}",
+                c.to_descriptive_string(),
+                s,
+                p
+            ),
+            Icon::UnknownVendor => "Unknown vendor without its own icon".into(),
+            Icon::UndefinedClassifier => "Undefined class without its own icon".into(),
+            Icon::TreeEdge => "Branch to an item with more items below".into(),
+            Icon::TreeLine => "Branch continuing past a nested item".into(),
+            Icon::TreeCorner => "Branch to the last item".into(),
+            Icon::TreeBlank => "Inset below the last item".into(),
+            Icon::TreeBusStart => "Start of a bus".into(),
+            Icon::TreeDeviceTerminator => "Device".into(),
+            Icon::TreeConfigurationTerminator => "Configuration".into(),
+            Icon::TreeInterfaceTerminator => "Interface".into(),
+            Icon::Endpoint(Direction::In) => "IN endpoint; device to host".into(),
+            Icon::Endpoint(Direction::Out) => "OUT endpoint; host to device".into(),
+        }
+    }
+}
+
 /// Allows user supplied icons to replace or add to `DEFAULT_ICONS` and `DEFAULT_TREE`
-#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+///
+/// User icons are always checked before defaults and the most specific [`Icon`] wins. For devices the order is `VidPidSerial` -> `VidPid` -> `VidPidMsb` -> `Vid` -> `UnknownVendor`, with keys in the format "vid-pid-serial#1d50:6018:97B6A11D", "vid-pid#1d50:6018", "vid-pid-msb#0483:37" and "vid#1d50"
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
-#[serde(deny_unknown_fields)]
 #[serde(default)]
 pub struct IconTheme {
     /// Will merge with `DEFAULT_ICONS` for user supplied
@@ -214,6 +305,38 @@ lazy_static! {
         ])
     };
 
+    /// Rounded corners variant of `DEFAULT_TREE`
+    static ref ROUNDED_TREE: HashMap<Icon, &'static str> = {
+        HashMap::from([
+            (Icon::TreeEdge, "\u{251c}\u{2500}\u{2500}".into()), // "├──"
+            (Icon::TreeLine, "\u{2502}  ".into()), // "│  "
+            (Icon::TreeCorner, "\u{2570}\u{2500}\u{2500}".into()), // "╰──"
+            (Icon::TreeBlank, "   ".into()), // should be same char width as above
+            (Icon::TreeBusStart, "\u{256D}".into()), // "╭"
+            (Icon::TreeDeviceTerminator, "\u{25CB}".into()), // "○"
+            (Icon::TreeConfigurationTerminator, "\u{2022}".into()), // "•"
+            (Icon::TreeInterfaceTerminator, "\u{25E6}".into()), // "◦"
+            (Icon::Endpoint(Direction::In), "\u{2192}".into()), // →
+            (Icon::Endpoint(Direction::Out), "\u{2190}".into()), // ←
+        ])
+    };
+
+    /// Heavy line variant of `DEFAULT_TREE`
+    static ref HEAVY_TREE: HashMap<Icon, &'static str> = {
+        HashMap::from([
+            (Icon::TreeEdge, "\u{2523}\u{2501}\u{2501}".into()), // "┣━━"
+            (Icon::TreeLine, "\u{2503}  ".into()), // "┃  "
+            (Icon::TreeCorner, "\u{2517}\u{2501}\u{2501}".into()), // "┗━━"
+            (Icon::TreeBlank, "   ".into()), // should be same char width as above
+            (Icon::TreeBusStart, "\u{25A0}".into()), // "■"
+            (Icon::TreeDeviceTerminator, "\u{25A1}".into()), // "□"
+            (Icon::TreeConfigurationTerminator, "\u{25AA}".into()), // "▪"
+            (Icon::TreeInterfaceTerminator, "\u{25AB}".into()), // "▫"
+            (Icon::Endpoint(Direction::In), "\u{21D2}".into()), // ⇒
+            (Icon::Endpoint(Direction::Out), "\u{21D0}".into()), // ⇐
+        ])
+    };
+
     /// Ascii chars used by lsusb compatible mode or no utf-8
     static ref ASCII_TREE: HashMap<Icon, &'static str> = {
         HashMap::from([
@@ -296,17 +419,68 @@ impl IconTheme {
         Default::default()
     }
 
-    /// Get tree building icon checks `Self` for user `tree` and tries to find `icon` there, otherwise uses `DEFAULT_TREE`
-    pub fn get_tree_icon(&self, icon: &Icon) -> String {
-        // unwrap on DEFAULT_TREE is ok here since should panic if missing from static list
+    /// Checks user icon keys against the known [`ClassCode`] enumeration and returns those with unrecognised class codes
+    ///
+    /// Class codes not in the enumeration are parsed as [`ClassCode::UseInterfaceDescriptor`] so a typo would otherwise silently never match
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use cyme::icon::*;
+    ///
+    /// let theme = IconTheme {
+    ///     user: Some(HashMap::from([
+    ///         ("classifier#03".parse().unwrap(), "H".into()),
+    ///         ("classifier#04".parse().unwrap(), "?".into()),
+    ///     ])),
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(theme.validate(), vec!["classifier#00"]);
+    /// ```
+    pub fn validate(&self) -> Vec<String> {
+        let mut ret: Vec<String> = self
+            .user
+            .iter()
+            .flat_map(|u| u.keys())
+            .filter(|i| {
+                matches!(
+                    i,
+                    Icon::Classifier(ClassCode::UseInterfaceDescriptor)
+                        | Icon::ClassifierSubProtocol((ClassCode::UseInterfaceDescriptor, _, _))
+                )
+            })
+            .map(|i| i.to_string())
+            .collect();
+        ret.sort();
+
+        ret
+    }
+
+    /// Every resolvable device and classifier icon; `DEFAULT_ICONS` merged with user icons, sorted by [`Icon`] key string
+    pub fn icons(&self) -> Vec<(Icon, String)> {
+        let mut merged: HashMap<Icon, String> = DEFAULT_ICONS
+            .iter()
+            .map(|(k, v)| (k.to_owned(), v.to_string()))
+            .collect();
+        if let Some(user) = self.user.as_ref() {
+            merged.extend(user.iter().map(|(k, v)| (k.to_owned(), v.to_owned())));
+        }
+        let mut ret: Vec<(Icon, String)> = merged.into_iter().collect();
+        ret.sort_by_key(|(k, _)| k.to_string());
+
+        ret
+    }
+
+    /// Get tree building icon checks `Self` for user `tree` and tries to find `icon` there, otherwise uses the tree of `style`
+    pub fn get_tree_icon(&self, icon: &Icon, style: &TreeStyle) -> String {
+        // unwrap on style tree is ok here since should panic if missing from static list
         if let Some(user_tree) = self.tree.as_ref() {
             user_tree
                 .get(icon)
-                .unwrap_or(&DEFAULT_TREE.get(icon).unwrap().to_string())
+                .unwrap_or(&get_style_tree_icon(icon, style))
                 .to_string()
                 .to_owned()
         } else {
-            get_default_tree_icon(&icon)
+            get_style_tree_icon(&icon, style)
         }
     }
 
@@ -359,9 +533,39 @@ impl IconTheme {
         }
     }
 
-    /// Get icon for USBDevice `d` by checking `Self` using Vendor ID and Product ID
+    /// Get icon for USBDevice `d` by checking `Self` using Vendor ID, Product ID and serial number; `VidPidSerial` -> `get_vidpid_icon`
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use cyme::icon::*;
+    /// use cyme::system_profiler::USBDevice;
+    ///
+    /// let theme = IconTheme {
+    ///     user: Some(HashMap::from([
+    ///         (Icon::VidPidSerial((0x1d50, 0x6018, "97B6A11D".into())), "S".into()),
+    ///         (Icon::VidPid((0x1d50, 0x6018)), "P".into()),
+    ///     ])),
+    ///     ..Default::default()
+    /// };
+    /// let mut device = USBDevice {
+    ///     vendor_id: Some(0x1d50),
+    ///     product_id: Some(0x6018),
+    ///     serial_num: Some("97B6A11D".into()),
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(theme.get_device_icon(&device), "S");
+    /// device.serial_num = Some("00000001".into());
+    /// assert_eq!(theme.get_device_icon(&device), "P");
+    /// ```
     pub fn get_device_icon(&self, d: &USBDevice) -> String {
         if let (Some(vid), Some(pid)) = (d.vendor_id, d.product_id) {
+            if let (Some(user_icons), Some(serial)) = (self.user.as_ref(), d.serial_num.as_ref()) {
+                if let Some(icon) =
+                    user_icons.get(&Icon::VidPidSerial((vid, pid, serial.to_owned())))
+                {
+                    return icon.to_owned();
+                }
+            }
             self.get_vidpid_icon(vid, pid)
         } else {
             String::new()
@@ -427,6 +631,42 @@ pub fn get_ascii_tree_icon(i: &Icon) -> String {
     ASCII_TREE.get(i).unwrap().to_string()
 }
 
+/// Gets tree icon from the static tree of `style` as `String` with `unwrap` because should panic if missing from there
+///
+/// ```
+/// use cyme::icon::{get_style_tree_icon, Icon, TreeStyle};
+///
+/// assert_eq!(get_style_tree_icon(&Icon::TreeCorner, &TreeStyle::Utf8), "└──");
+/// assert_eq!(get_style_tree_icon(&Icon::TreeCorner, &TreeStyle::Rounded), "╰──");
+/// assert_eq!(get_style_tree_icon(&Icon::TreeCorner, &TreeStyle::Heavy), "┗━━");
+/// assert_eq!(get_style_tree_icon(&Icon::TreeCorner, &TreeStyle::Ascii), "|__");
+/// ```
+pub fn get_style_tree_icon(i: &Icon, style: &TreeStyle) -> String {
+    match style {
+        TreeStyle::Utf8 => get_default_tree_icon(i),
+        TreeStyle::Ascii => get_ascii_tree_icon(i),
+        TreeStyle::Rounded => ROUNDED_TREE.get(i).unwrap().to_string(),
+        TreeStyle::Heavy => HEAVY_TREE.get(i).unwrap().to_string(),
+    }
+}
+
+/// Character set used to draw the tree; user `tree` icons in [`IconTheme`] take precedence
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum TreeStyle {
+    /// Default utf-8 box drawing lines
+    #[default]
+    Utf8,
+    /// Ascii chars as used with lsusb and when utf-8 is disabled
+    Ascii,
+    /// utf-8 with rounded corners
+    Rounded,
+    /// utf-8 heavy box drawing lines
+    Heavy,
+}
+
 /// Returns clone of lazy_static defaults
 pub fn defaults() -> HashMap<Icon, &'static str> {
     DEFAULT_ICONS.clone()
@@ -439,6 +679,10 @@ pub fn example() -> HashMap<Icon, String> {
         (Icon::Vid(0x05ac), "\u{f179}".into()),   // apple 
         (Icon::VidPid((0x1d50, 0x6018)), "\u{f188}".into()), // black magic probe 
         (Icon::VidPidMsb((0x0483, 0x37)), "\u{f188}".into()), // st-link 
+        (
+            Icon::VidPidSerial((0x1d50, 0x6018, "97B6A11D".into())),
+            "\u{f5a2}".into(),
+        ), // black magic probe by serial 
         (
             Icon::ClassifierSubProtocol((ClassCode::ApplicationSpecificInterface, 0x01, 0x01)),
             "\u{f188}".into(),
@@ -454,7 +698,10 @@ pub fn example() -> HashMap<Icon, String> {
 
 /// Returns example theme with [`Icon`] types and default tree
 pub fn example_theme() -> IconTheme {
-    let tree_strings: HashMap<Icon, String> = DEFAULT_TREE.iter().map(|(k, v)| (k.to_owned(), v.to_string())).collect();
+    let tree_strings: HashMap<Icon, String> = DEFAULT_TREE
+        .iter()
+        .map(|(k, v)| (k.to_owned(), v.to_string()))
+        .collect();
 
     IconTheme {
         user: Some(example()),
@@ -542,6 +789,17 @@ mod tests {
             Icon::ClassifierSubProtocol((ClassCode::HID, 01, 10))
         );
 
+        let str = "vid-pid-serial#1d50:6018:97B6A11D";
+        let icon = Icon::from_str(str);
+        assert_eq!(
+            icon.unwrap(),
+            Icon::VidPidSerial((7504, 24600, "97B6A11D".into()))
+        );
+
+        let str = "vid-pid-serial#1d50:6018:";
+        let icon = Icon::from_str(str);
+        assert!(icon.is_err());
+
         let str = "endpoint_in";
         let icon = Icon::from_str(str);
         assert_eq!(icon.unwrap(), Icon::Endpoint(Direction::In));
@@ -12,12 +12,14 @@ pub mod config;
 pub mod display;
 pub mod icon;
 pub mod lsusb;
+pub mod select;
 pub mod system_profiler;
 pub mod types;
 #[cfg(target_os = "linux")]
 #[cfg(feature = "udev")]
 pub mod udev;
 pub mod usb;
+pub mod watch;
 
 /// Set cyme module and binary log level
 pub fn set_log_level(debug: u8) -> Result<(), Error> {
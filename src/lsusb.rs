@@ -167,6 +167,7 @@ pub mod profiler {
 
     fn build_endpoints(
         interface_desc: &libusb::InterfaceDescriptor,
+        speed: &usb::Speed,
     ) -> libusb::Result<Vec<usb::USBEndpoint>> {
         let mut ret: Vec<usb::USBEndpoint> = Vec::new();
 
@@ -182,6 +183,11 @@ pub mod profiler {
                 usage_type: usb::UsageType::from(endpoint_desc.usage_type()),
                 max_packet_size: endpoint_desc.max_packet_size(),
                 interval: endpoint_desc.interval(),
+                raw: None,
+                device_speed: Some(speed.to_owned()),
+                companion: endpoint_desc
+                    .extra()
+                    .and_then(usb::EndpointCompanion::from_descriptors),
             });
         }
 
@@ -195,6 +201,7 @@ pub mod profiler {
         _with_udev: bool,
     ) -> libusb::Result<Vec<usb::USBInterface>> {
         let mut ret: Vec<usb::USBInterface> = Vec::new();
+        let speed = usb::Speed::from(device.speed());
 
         for interface in config_desc.interfaces() {
             for interface_desc in interface.descriptors() {
@@ -214,7 +221,10 @@ pub mod profiler {
                     alt_setting: interface_desc.setting_number(),
                     driver: None,
                     syspath: None,
-                    endpoints: build_endpoints(&interface_desc)?,
+                    endpoints: build_endpoints(&interface_desc, &speed)?,
+                    raw: None,
+                    association: None,
+                    num_alt_settings: 0,
                 };
 
                 #[cfg(all(target_os = "linux", feature = "udev"))]
@@ -230,10 +240,48 @@ pub mod profiler {
                 ret.push(_interface);
             }
         }
+        usb::set_num_alt_settings(&mut ret);
 
         Ok(ret)
     }
 
+    /// Reads the full configuration descriptor at `index` with a GET_DESCRIPTOR control transfer; requires an open device
+    fn get_raw_config_descriptor<T: libusb::UsbContext>(
+        index: u8,
+        handle: &Option<UsbDevice<T>>,
+    ) -> Option<Vec<u8>> {
+        const GET_DESCRIPTOR: u8 = 0x06;
+        const DT_CONFIG: u16 = 0x02;
+        let h = handle.as_ref()?;
+        let request_type = libusb::request_type(
+            libusb::Direction::In,
+            libusb::RequestType::Standard,
+            libusb::Recipient::Device,
+        );
+        let value = (DT_CONFIG << 8) | index as u16;
+
+        // header first for wTotalLength
+        let mut header = [0u8; 9];
+        h.handle
+            .read_control(
+                request_type,
+                GET_DESCRIPTOR,
+                value,
+                0,
+                &mut header,
+                h.timeout,
+            )
+            .ok()?;
+        let mut buf = vec![0u8; u16::from_le_bytes([header[2], header[3]]) as usize];
+        let len = h
+            .handle
+            .read_control(request_type, GET_DESCRIPTOR, value, 0, &mut buf, h.timeout)
+            .ok()?;
+        buf.truncate(len);
+
+        Some(buf)
+    }
+
     fn build_configurations<T: libusb::UsbContext>(
         device: &libusb::Device<T>,
         handle: &mut Option<UsbDevice<T>>,
@@ -241,6 +289,8 @@ pub mod profiler {
         with_udev: bool,
     ) -> libusb::Result<Vec<usb::USBConfiguration>> {
         let mut ret: Vec<usb::USBConfiguration> = Vec::new();
+        // does not require open device
+        let active = device.active_config_descriptor().ok().map(|c| c.number());
 
         for n in 0..device_desc.num_configurations() {
             let config_desc = match device.config_descriptor(n) {
@@ -257,7 +307,7 @@ pub mod profiler {
                 attributes.push(usb::ConfigAttributes::SelfPowered);
             }
 
-            ret.push(usb::USBConfiguration {
+            let mut config = usb::USBConfiguration {
                 name: get_configuration_string(&config_desc, handle),
                 string_index: config_desc.description_string_index().unwrap_or(0),
                 number: config_desc.number(),
@@ -268,7 +318,15 @@ pub mod profiler {
                     description: None,
                 },
                 interfaces: build_interfaces(device, handle, &config_desc, with_udev)?,
-            });
+                active: active == Some(config_desc.number()),
+                raw: None,
+            };
+
+            if let Some(raw) = get_raw_config_descriptor(n, handle) {
+                config.set_raw_descriptors(&raw);
+            }
+
+            ret.push(config);
         }
 
         Ok(ret)
@@ -298,6 +356,9 @@ pub mod profiler {
             )
             .map_or(None, |v| Some(v.name().to_owned())),
             configurations: build_configurations(device, handle, device_desc, _with_udev)?,
+            connected_since: None,
+            power_state: None,
+            tags: None,
         };
 
         #[cfg(all(target_os = "linux", feature = "udev"))]
@@ -308,6 +369,12 @@ pub mod profiler {
                 &_sp_device.port_path(),
             )
             .or(Err(libusb::Error::Other))?;
+            // not critical so don't fail if missing
+            _extra.connected_since =
+                udev::get_udev_connected_since(&_sp_device.port_path()).unwrap_or(None);
+            _extra.power_state =
+                udev::get_sysfs_power_state(&_sp_device.port_path()).unwrap_or(None);
+            _extra.tags = udev::get_udev_tags(&_sp_device.port_path()).unwrap_or(None);
         }
 
         Ok(_extra)
@@ -372,15 +439,20 @@ pub mod profiler {
             };
         }
 
+        let mut name_source = system_profiler::NameSource::Descriptor;
         if name.is_empty() {
             match usb_ids::Device::from_vid_pid(device_desc.vendor_id(), device_desc.product_id()) {
-                Some(product) => name = product.name().to_owned(),
-                None => (),
+                Some(product) => {
+                    name = product.name().to_owned();
+                    name_source = system_profiler::NameSource::UsbIds;
+                }
+                None => name_source = system_profiler::NameSource::Unknown,
             };
         }
 
         let mut sp_device = system_profiler::USBDevice {
             name,
+            name_source: Some(name_source),
             manufacturer: Some(manufacturer),
             serial_num: Some(get_serial_string(&device_desc, &mut usb_device)),
             vendor_id: Some(device_desc.vendor_id()),
@@ -492,6 +564,12 @@ pub mod profiler {
                     .to_owned();
                 new_bus.pci_vendor = root_hub.vendor_id;
                 new_bus.pci_device = root_hub.product_id;
+
+                // not critical so don't fail if missing
+                #[cfg(all(target_os = "linux", feature = "udev"))]
+                if with_extra {
+                    new_bus.driver = udev::get_udev_controller_driver(key).unwrap_or(None);
+                }
             }
 
             // group into parent groups with parent path as key or trunk devices so they end up in same place
@@ -676,22 +754,41 @@ pub mod display {
                 println!("{}", device.to_lsusb_string());
             }
         } else {
-            for device in devices {
-                match device.extra.as_ref() {
-                    None => log::warn!("Skipping {} because it does not contain extra data required for verbose print", device),
-                    Some(device_extra) => {
-                        println!(""); // new lines separate in verbose lsusb
-                        println!("{}", device.to_lsusb_string());
-                        print_device(&device);
+            print_verbose(devices, false);
+        }
+    }
 
-                        for config in &device_extra.configurations {
-                            print_config(&config);
+    /// Print USB devices in lsusb style verbose dump
+    ///
+    /// `hex` flag prints the raw descriptor bytes as hex rows under each configuration, interface and endpoint descriptor
+    pub fn print_verbose(devices: &Vec<&system_profiler::USBDevice>, hex: bool) -> () {
+        for device in devices {
+            match device.extra.as_ref() {
+                None => log::warn!(
+                    "Skipping {} because it does not contain extra data required for verbose print",
+                    device
+                ),
+                Some(device_extra) => {
+                    println!(""); // new lines separate in verbose lsusb
+                    println!("{}", device.to_lsusb_string());
+                    print_device(&device);
+
+                    for config in &device_extra.configurations {
+                        print_config(&config);
+                        if hex {
+                            print_hex(&config.raw, 4);
+                        }
 
-                            for interface in &config.interfaces {
-                                print_interface(&interface);
+                        for interface in &config.interfaces {
+                            print_interface(&interface);
+                            if hex {
+                                print_hex(&interface.raw, 6);
+                            }
 
-                                for endpoint in &interface.endpoints {
-                                    print_endpoint(&endpoint);
+                            for endpoint in &interface.endpoints {
+                                print_endpoint(&endpoint);
+                                if hex {
+                                    print_hex(&endpoint.raw, 8);
                                 }
                             }
                         }
@@ -701,6 +798,17 @@ pub mod display {
         }
     }
 
+    fn print_hex(raw: &Option<Vec<u8>>, indent: usize) {
+        match raw {
+            Some(bytes) => {
+                for line in usb::hex_dump_lines(bytes) {
+                    println!("{:indent$}{}", "", line, indent = indent);
+                }
+            }
+            None => println!("{:indent$}{}", "", usb::HEX_UNAVAILABLE, indent = indent),
+        }
+    }
+
     fn print_device(device: &system_profiler::USBDevice) {
         let device_extra = device
             .extra
@@ -4,13 +4,17 @@ use colored::*;
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
 use std::env;
-use std::io::{Error, ErrorKind};
+use std::io::{Error, ErrorKind, Write};
 
+use cyme::colour;
 use cyme::config::Config;
 use cyme::display;
+use cyme::display::Block;
+use cyme::icon;
 use cyme::lsusb;
+use cyme::select::Select;
 use cyme::system_profiler;
-use cyme::usb::ClassCode;
+use cyme::usb::{ClassCode, TransferType};
 
 #[derive(Parser, Debug, Default, Serialize, Deserialize)]
 #[skip_serializing_none]
@@ -24,6 +28,14 @@ struct Args {
     #[arg(short, long, default_value_t = false)]
     tree: bool,
 
+    /// Maximum depth of devices to print in tree; 1 is only devices attached to buses. Deeper devices are shown as an ellipsis
+    #[arg(long, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+    max_depth: Option<usize>,
+
+    /// Print at most N devices after filtering and sorting: N in total when listing or N per branch with --tree; the rest are counted on a '... (X more)' line
+    #[arg(long)]
+    limit: Option<usize>,
+
     /// Show only devices with the specified vendor and product ID numbers (in hexadecimal) in format VID:[PID]
     #[arg(short = 'd', long)]
     vidpid: Option<String>,
@@ -36,10 +48,26 @@ struct Args {
     #[arg(short = 'D', long)]
     device: Option<String>,
 
+    /// Print only the device at port path, such as '1-2.3', and its descendants as a tree
+    #[arg(long, conflicts_with_all = ["lsusb", "device"])]
+    path: Option<String>,
+
+    /// Filter on devices with a vid:pid listed in file at this path; one hex 'vid:pid' per line, '#' comments and blank lines are ignored
+    #[arg(long, value_name = "FILE")]
+    filter_list: Option<String>,
+
+    /// Filter on an expression such as 'class==hid && speed>=high' of vid, pid, class, speed, driver and name compared with ==, !=, <, <=, >, >= or ~ (contains), combined with &&, || and !
+    #[arg(long, value_name = "EXPR")]
+    select: Option<String>,
+
     /// Filter on string contained in name
     #[arg(long)]
     filter_name: Option<String>,
 
+    /// Filter on case-insensitive regex matching name, manufacturer or usb_ids product name
+    #[arg(long)]
+    filter_name_regex: Option<String>,
+
     /// Filter on string contained in serial
     #[arg(long)]
     filter_serial: Option<String>,
@@ -48,29 +76,57 @@ struct Args {
     #[arg(long)]
     filter_class: Option<ClassCode>,
 
+    /// Hide devices of USB class code; devices attached to them are shown in their place with --tree
+    #[arg(long)]
+    hide_class: Option<ClassCode>,
+
+    /// Filter on devices with an interface endpoint of transfer type
+    #[arg(long)]
+    filter_transfer_type: Option<TransferType>,
+
+    /// Filter on devices bound to udev driver name, either device or interface driver (Linux only)
+    #[arg(long)]
+    filter_driver: Option<String>,
+
+    /// Only list devices which match the filters themselves, not parents retained to keep the tree connected; ignored with --tree
+    #[arg(long, default_value_t = false)]
+    only_matching: bool,
+
     /// Verbosity level: 1 prints device configurations; 2 prints interfaces; 3 prints interface endpoints; 4 prints everything and all blocks
     #[arg(short = 'v', long, default_value_t = 0, action = clap::ArgAction::Count)]
     verbose: u8,
 
-    /// Specify the blocks which will be displayed for each device and in what order
-    #[arg(short, long, value_enum)]
-    blocks: Option<Vec<display::DeviceBlocks>>,
+    /// Specify the blocks which will be displayed for each device and in what order; prefix with '+' or '-' to add to or remove from the defaults
+    #[arg(short, long, value_delimiter = ',', allow_hyphen_values = true)]
+    blocks: Option<Vec<display::BlockArg<display::DeviceBlocks>>>,
 
-    /// Specify the blocks which will be displayed for each bus and in what order
-    #[arg(long, value_enum)]
-    bus_blocks: Option<Vec<display::BusBlocks>>,
+    /// Specify the blocks which will be displayed for each bus and in what order; prefix with '+' or '-' to add to or remove from the defaults
+    #[arg(long, value_delimiter = ',', allow_hyphen_values = true)]
+    bus_blocks: Option<Vec<display::BlockArg<display::BusBlocks>>>,
 
-    /// Specify the blocks which will be displayed for each configuration and in what order
-    #[arg(long, value_enum)]
-    config_blocks: Option<Vec<display::ConfigurationBlocks>>,
+    /// Specify the blocks which will be displayed for each configuration and in what order; prefix with '+' or '-' to add to or remove from the defaults
+    #[arg(long, value_delimiter = ',', allow_hyphen_values = true)]
+    config_blocks: Option<Vec<display::BlockArg<display::ConfigurationBlocks>>>,
 
-    /// Specify the blocks which will be displayed for each interface and in what order
-    #[arg(long, value_enum)]
-    interface_blocks: Option<Vec<display::InterfaceBlocks>>,
+    /// Specify the blocks which will be displayed for each interface and in what order; prefix with '+' or '-' to add to or remove from the defaults
+    #[arg(long, value_delimiter = ',', allow_hyphen_values = true)]
+    interface_blocks: Option<Vec<display::BlockArg<display::InterfaceBlocks>>>,
 
-    /// Specify the blocks which will be displayed for each endpoint and in what order
-    #[arg(long, value_enum)]
-    endpoint_blocks: Option<Vec<display::EndpointBlocks>>,
+    /// Specify the blocks which will be displayed for each endpoint and in what order; prefix with '+' or '-' to add to or remove from the defaults
+    #[arg(long, value_delimiter = ',', allow_hyphen_values = true)]
+    endpoint_blocks: Option<Vec<display::BlockArg<display::EndpointBlocks>>>,
+
+    /// With --json, only output these device blocks for each device rather than all data; bus name and number are kept for --tree
+    #[arg(long, value_delimiter = ',')]
+    fields: Option<Vec<display::DeviceBlocks>>,
+
+    /// Cap device block columns to a maximum display width, truncating with '…': BLOCK=WIDTH, e.g. 'name=20,serial=8'
+    #[arg(long, value_delimiter = ',')]
+    truncate: Option<Vec<String>>,
+
+    /// Drop the least important device blocks until rows fit the terminal width; no effect if the width cannot be detected
+    #[arg(long, default_value_t = false)]
+    auto_width: bool,
 
     /// Print more blocks by default at each verbosity
     #[arg(short, long, default_value_t = false)]
@@ -80,14 +136,30 @@ struct Args {
     #[arg(long, value_enum)]
     sort_devices: Option<display::Sort>,
 
-    /// Sort devices by bus number
+    /// Sort buses by value; without a value sorts by bus number
+    #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "number")]
+    sort_buses: Option<display::BusSort>,
+
+    /// Show sizes like endpoint max packet size with binary unit suffixes (B/KiB) rather than raw numbers
     #[arg(long, default_value_t = false)]
-    sort_buses: bool,
+    human_sizes: bool,
+
+    /// Sort interfaces by number then alternate setting rather than the order returned by the profiler
+    #[arg(long, default_value_t = false)]
+    sort_interfaces: bool,
+
+    /// Sort endpoints by address rather than the order returned by the profiler
+    #[arg(long, default_value_t = false)]
+    sort_endpoints: bool,
 
     /// Group devices by value when listing
     #[arg(long, value_enum, default_value_t = Default::default())]
     group_devices: display::Group,
 
+    /// Order the groups of --group-devices by name or number of devices; count-desc shows the busiest bus or vendor first
+    #[arg(long, value_enum)]
+    group_sort: Option<display::GroupSort>,
+
     /// Hide empty buses when printing tree; those with no devices. When listing will hide Linux root_hubs
     // these are a bit confusing, could make value enum with hide_empty, hide...
     #[arg(long, default_value_t = false)]
@@ -97,34 +169,146 @@ struct Args {
     #[arg(long, default_value_t = false)]
     hide_hubs: bool,
 
-    /// Show base16 values as base10 decimal instead
+    /// Remove hubs left without any non-hub devices after filtering, including hubs of only empty hubs; does not require --hide-buses or another filter
     #[arg(long, default_value_t = false)]
-    decimal: bool,
+    exclude_empty_hub: bool,
+
+    /// Show base16 values as base10 decimal instead; optionally only for these categories, e.g. 'codes' keeps IDs in base16
+    #[arg(long, value_delimiter = ',', num_args = 0..=1, default_missing_value = "all")]
+    decimal: Option<Vec<display::NumberCategory>>,
 
     /// Disable padding to align blocks
     #[arg(long, default_value_t = false)]
     no_padding: bool,
 
-    /// Disable coloured output, can also use NO_COLOR environment variable
+    /// Print a footer with the number of buses, devices and hubs printed
+    #[arg(long, default_value_t = false)]
+    summary: bool,
+
+    /// Print only the number of devices matching filters
+    #[arg(long, default_value_t = false, conflicts_with_all = ["lsusb", "tree", "path"])]
+    count: bool,
+
+    /// Terse single line per device: port path, vid:pid and name; overrides device blocks
+    #[arg(long, default_value_t = false)]
+    compact: bool,
+
+    /// Disable coloured output, can also use NO_COLOR environment variable; same as --color never
     #[arg(long, default_value_t = false)]
     no_colour: bool,
 
+    /// When to colour output: auto only colours if stdout is a terminal and NO_COLOR is not set
+    #[arg(long, value_enum, visible_alias = "colour", default_value_t = Default::default())]
+    color: display::ColourWhen,
+
     /// Disables icons and utf-8 charactors
     #[arg(long, default_value_t = false)]
     ascii: bool,
 
+    /// Character set to draw the tree with; ignored with --ascii or --ascii-tree
+    #[arg(long, value_enum, default_value_t = icon::TreeStyle::Utf8)]
+    tree_style: icon::TreeStyle,
+
+    /// Draw the tree with ascii characters but keep icons; --ascii sets this and --no-icons
+    #[arg(long, default_value_t = false)]
+    ascii_tree: bool,
+
+    /// Disables device icons but keep the utf-8 tree; --ascii sets this and --ascii-tree
+    #[arg(long, default_value_t = false)]
+    no_icons: bool,
+
     /// Show block headings
     #[arg(long, default_value_t = false)]
     headings: bool,
 
+    /// Draw indent guides for configurations, interfaces and endpoints with --verbose when not using --tree
+    #[arg(long, default_value_t = false)]
+    indent_guides: bool,
+
+    /// Number of spaces configurations, interfaces and endpoints are indented per level with --verbose when not using --tree
+    #[arg(long, value_name = "N")]
+    indent_step: Option<usize>,
+
+    /// Pad device blocks across the whole --tree rather than per level so that columns are the same width at every depth
+    #[arg(long, default_value_t = false)]
+    align_tree: bool,
+
+    /// Colour speed and current blocks by value: red for low-speed through to green for super-speed+, green for low current through to red for high
+    #[arg(long, default_value_t = false)]
+    semantic_colours: bool,
+
+    /// Colour each segment of port paths and tree positions by its depth in the tree to help follow a branch
+    #[arg(long, default_value_t = false)]
+    rainbow_path: bool,
+
+    /// Show only the first alternate setting of each interface with a count of hidden alternates; all are shown with maximum --verbose
+    #[arg(long, default_value_t = false)]
+    collapse_alt_settings: bool,
+
+    /// Hide interfaces not matching --filter-class under each configuration; devices are still shown
+    #[arg(long, default_value_t = false, requires = "filter_class")]
+    prune_interfaces: bool,
+
+    /// Show device names, manufacturers and vendors from the usb_ids database before the descriptor strings
+    #[arg(long, default_value_t = false)]
+    prefer_usb_ids: bool,
+
+    /// Page output through $PAGER, or 'less -R' if unset, when stdout is a terminal
+    #[arg(long, default_value_t = false, conflicts_with_all = ["lsusb", "watch", "output_file"])]
+    pager: bool,
+
+    /// Zero-pad tree positions to two digits like '01-02-03' so they align and sort
+    #[arg(long, default_value_t = false)]
+    pad_positions: bool,
+
+    /// Write output to this file rather than stdout; colours are off unless --color always
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["lsusb", "watch"])]
+    output_file: Option<std::path::PathBuf>,
+
+    /// Dump raw descriptor bytes as hex rows under each configuration, interface and endpoint with --verbose; only captured when profiling with libusb
+    #[arg(long, default_value_t = false)]
+    hex: bool,
+
+    /// With --headings and grouping, show the device heading once at the top rather than for every group
+    #[arg(long, default_value_t = false)]
+    no_headings_repeat: bool,
+
     /// Output as json format after sorting, filters and tree settings are applied; without -tree will be flattened dump of devices
     #[arg(long, default_value_t = false, overrides_with = "lsusb")]
     json: bool,
 
+    /// With --json always output the flat array of devices, regardless of --tree and --group-devices, for a stable shape; with --tree parents of filter matches are included
+    #[arg(long, default_value_t = false, requires = "json")]
+    flatten_json: bool,
+
+    /// With --json print minified JSON on a single line rather than pretty-printed
+    #[arg(long, default_value_t = false, requires = "json")]
+    json_compact: bool,
+
+    /// Output as yaml format after sorting, filters and tree settings are applied; without -tree will be flattened dump of devices
+    #[arg(long, default_value_t = false, overrides_with = "lsusb", conflicts_with = "json")]
+    yaml: bool,
+
+    /// Output flattened devices as newline-delimited json; one compact json device per line after sorting and filters are applied
+    #[arg(long, default_value_t = false, overrides_with = "lsusb", conflicts_with_all = ["json", "yaml", "tree"])]
+    ndjson: bool,
+
+    /// Output flattened devices as a self-contained HTML table with colours as inline styles
+    #[arg(long, default_value_t = false, overrides_with = "lsusb", conflicts_with_all = ["json", "yaml", "ndjson"])]
+    html: bool,
+
     /// Read from json output rather than profiling system - must use --tree json dump
     #[arg(long)]
     from_json: Option<String>,
 
+    /// Read a json dump piped to stdin rather than profiling system - must use --tree json dump
+    #[arg(long, default_value_t = false, conflicts_with_all = ["from_json", "watch"])]
+    from_stdin: bool,
+
+    /// Show only the differences of devices between a baseline json dump at this path and the current devices; either a --tree or flattened json dump
+    #[arg(long, conflicts_with_all = ["lsusb", "watch"])]
+    diff: Option<String>,
+
     /// Force libusb profiler on macOS rather than using/combining system_profiler output
     #[arg(short = 'F', long, default_value_t = false)]
     force_libusb: bool,
@@ -133,6 +317,14 @@ struct Args {
     #[arg(short = 'c', long)]
     config: Option<String>,
 
+    /// Name of colour theme to load from 'themes/<name>.json' within the cyme config directory; replaces config colours
+    #[arg(long)]
+    theme: Option<String>,
+
+    /// Built-in colour theme preset to use; light avoids colours that are hard to read on light backgrounds
+    #[arg(long, value_enum, conflicts_with = "theme")]
+    theme_preset: Option<colour::ColourPreset>,
+
     /// Turn debugging information on. Alternatively can use RUST_LOG env: INFO, DEBUG, TRACE
     #[arg(short = 'z', long, action = clap::ArgAction::Count)]
     // short -d taken by lsusb compat vid:pid
@@ -142,6 +334,38 @@ struct Args {
     #[arg(long)]
     mask_serials: Option<display::MaskSerial>,
 
+    /// Number of chars kept at each end of the serial with '--mask-serials partial'
+    #[arg(long)]
+    mask_serials_visible: Option<usize>,
+
+    /// Keep running and re-print each time USB devices are plugged or unplugged; udev events on Linux, re-profiles every second otherwise
+    #[arg(short = 'w', long, default_value_t = false, conflicts_with_all = ["lsusb", "device"], group = "refresh")]
+    watch: bool,
+
+    /// Re-profile and re-print every SECS seconds like 'watch cyme' until Ctrl-C, whether devices changed or not
+    #[arg(long, value_name = "SECS", value_parser = clap::value_parser!(u64).range(1..), conflicts_with_all = ["lsusb", "watch", "from_stdin", "diff", "output_file", "pager"], group = "refresh")]
+    interval: Option<u64>,
+
+    /// With --watch or --interval, ring the terminal bell when devices are added or removed; 'desktop' also sends a desktop notification naming them
+    #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "bell", requires = "refresh")]
+    notify: Option<display::Notify>,
+
+    /// List every resolvable icon with its key and class then exit
+    #[arg(long, default_value_t = false)]
+    list_icons: bool,
+
+    /// Print a legend of what the tree and device icons and colours of the current theme mean then exit
+    #[arg(long, default_value_t = false)]
+    legend: bool,
+
+    /// Print the JSON Schema of the config file for editor validation and autocomplete then exit
+    #[arg(long, default_value_t = false)]
+    dump_schema: bool,
+
+    /// Print the effective config as JSON after merging the config file, args and defaults then exit; a starting point for a config file
+    #[arg(long, default_value_t = false)]
+    dump_config: bool,
+
     /// Generate cli completions and man page
     #[arg(long, hide = true, exclusive = true)]
     gen: bool,
@@ -173,7 +397,13 @@ fn merge_config(c: &Config, a: &mut Args) {
     a.more |= c.more;
     a.hide_buses |= c.hide_buses;
     a.hide_hubs |= c.hide_hubs;
-    a.decimal |= c.decimal;
+    if a.decimal.is_none() && c.decimal {
+        a.decimal = Some(
+            c.decimal_categories
+                .to_owned()
+                .unwrap_or_else(|| vec![display::NumberCategory::All]),
+        );
+    }
     a.no_padding |= c.no_padding;
     a.ascii |= c.ascii;
     a.headings |= c.headings;
@@ -183,6 +413,70 @@ fn merge_config(c: &Config, a: &mut Args) {
     }
 }
 
+/// Effective [`Config`] from the merged `config`, resolved blocks in `settings` and `Args`; inverse of [`merge_config`]
+///
+/// Themes come from `config` rather than `settings` so that they are kept when colours or icons are turned off for this run
+fn effective_config(config: Config, settings: &display::PrintSettings, a: &Args) -> Config {
+    Config {
+        version: cyme::config::CONFIG_VERSION,
+        blocks: Some(
+            settings
+                .device_blocks
+                .to_owned()
+                .unwrap_or_else(|| display::DeviceBlocks::default_blocks_for(settings)),
+        ),
+        bus_blocks: Some(
+            settings
+                .bus_blocks
+                .to_owned()
+                .unwrap_or_else(|| display::BusBlocks::default_blocks_for(settings)),
+        ),
+        config_blocks: Some(
+            settings
+                .config_blocks
+                .to_owned()
+                .unwrap_or_else(|| display::ConfigurationBlocks::default_blocks_for(settings)),
+        ),
+        interface_blocks: Some(
+            settings
+                .interface_blocks
+                .to_owned()
+                .unwrap_or_else(|| display::InterfaceBlocks::default_blocks_for(settings)),
+        ),
+        endpoint_blocks: Some(
+            settings
+                .endpoint_blocks
+                .to_owned()
+                .unwrap_or_else(|| display::EndpointBlocks::default_blocks_for(settings)),
+        ),
+        mask_serials: settings.mask_serials.to_owned(),
+        mask_serials_visible: settings.mask_serials_visible,
+        lsusb: a.lsusb,
+        tree: a.tree,
+        verbose: a.verbose,
+        more: a.more,
+        hide_buses: a.hide_buses,
+        hide_hubs: a.hide_hubs,
+        decimal: a.decimal.is_some(),
+        decimal_categories: a.decimal.to_owned(),
+        no_padding: a.no_padding,
+        ascii: a.ascii,
+        headings: a.headings,
+        force_libusb: a.force_libusb,
+        ..config
+    }
+}
+
+/// Opens [`display::PrintSettings::output_writer`], exiting if the output file cannot be created
+fn open_writer(settings: &display::PrintSettings) -> Box<dyn Write> {
+    settings.output_writer().unwrap_or_else(|e| {
+        eprintexit!(std::io::Error::new(
+            e.kind(),
+            format!("Failed to open output file: Error({})", e)
+        ));
+    })
+}
+
 /// Parse the vidpid filter lsusb format: vid:Option<pid>
 fn parse_vidpid(s: &str) -> Result<(Option<u16>, Option<u16>), Error> {
     if s.contains(":") {
@@ -214,6 +508,21 @@ fn parse_vidpid(s: &str) -> Result<(Option<u16>, Option<u16>), Error> {
     }
 }
 
+/// Parse the truncate BLOCK=WIDTH format
+fn parse_truncate(s: &str) -> Result<(display::DeviceBlocks, usize), Error> {
+    let (block, width) = s
+        .split_once('=')
+        .ok_or_else(|| Error::new(ErrorKind::Other, "missing '='"))?;
+    let block = <display::DeviceBlocks as clap::ValueEnum>::from_str(block.trim(), true)
+        .map_err(|e| Error::new(ErrorKind::Other, e))?;
+    let width = width
+        .trim()
+        .parse::<usize>()
+        .map_err(|e| Error::new(ErrorKind::Other, e))?;
+
+    Ok((block, width))
+}
+
 /// Parse the show Option<bus>:device lsusb format
 fn parse_show(s: &str) -> Result<(Option<u8>, Option<u8>), Error> {
     if s.contains(":") {
@@ -272,36 +581,84 @@ fn parse_devpath(s: &str) -> Result<(Option<u8>, Option<u8>), Error> {
     }
 }
 
-/// Abort with exit code before trying to call libusb feature if not present
+/// Error before trying to call libusb feature if not present
 #[cfg(not(feature = "libusb"))]
-fn get_libusb_spusb(_args: &Args) -> system_profiler::SPUSBDataType {
-    eprintexit!(Error::new(
+fn get_libusb_spusb(_args: &Args) -> Result<system_profiler::SPUSBDataType, Error> {
+    Err(Error::new(
         ErrorKind::Other,
-        "libusb feature is required to do this, install with `cargo install --features libusb`"
-    ));
+        "libusb feature is required to do this, install with `cargo install --features libusb`",
+    ))
 }
 
 #[cfg(feature = "libusb")]
-fn get_libusb_spusb(args: &Args) -> system_profiler::SPUSBDataType {
+fn get_libusb_spusb(args: &Args) -> Result<system_profiler::SPUSBDataType, Error> {
     if args.verbose > 0
             || args.tree
             || args.device.is_some()
             || args.lsusb
             || args.json
+            || args.yaml
+            || args.ndjson
+            || args.filter_transfer_type.is_some()
+            || args.filter_driver.is_some()
             || args.more {
-        lsusb::profiler::get_spusb_with_extra().unwrap_or_else(|e| {
-            eprintexit!(std::io::Error::new(
-                std::io::ErrorKind::Other,
+        lsusb::profiler::get_spusb_with_extra().map_err(|e| {
+            Error::new(
+                ErrorKind::Other,
                 format!("Failed to gather system USB data with extra from libusb: Error({})", e)
-            ));
+            )
         })
     } else {
-        lsusb::profiler::get_spusb().unwrap_or_else(|e| {
-            eprintexit!(std::io::Error::new(
-                std::io::ErrorKind::Other,
+        lsusb::profiler::get_spusb().map_err(|e| {
+            Error::new(
+                ErrorKind::Other,
                 format!("Failed to gather system USB data from libusb: Error({})", e)
-            ));
+            )
+        })
+    }
+}
+
+/// Gets the [`system_profiler::SPUSBDataType`] from the source required by `args`
+///
+/// Returns rather than exits on error so that watch mode can unwind and restore the terminal
+fn get_spusb(args: &Args) -> Result<system_profiler::SPUSBDataType, Error> {
+    if let Some(file_path) = &args.from_json {
+        system_profiler::read_json_dump(&file_path.as_str()).map_err(|e| {
+            Error::new(
+                ErrorKind::Other,
+                format!("Failed to parse system_profiler dump: Error({})", e)
+            )
+        })
+    } else if args.from_stdin {
+        system_profiler::read_json_dump_from_reader(std::io::stdin().lock()).map_err(|e| {
+            Error::new(
+                e.kind(),
+                format!("Failed to parse json dump from stdin: Error({})", e)
+            )
+        })
+    } else if cfg!(target_os = "macos") 
+        && !args.force_libusb
+        && args.device.is_none() // device path requires extra
+        && args.filter_class.is_none() // class filter requires extra
+        && args.hide_class.is_none() // class filter requires extra
+        && args.filter_transfer_type.is_none() // transfer type filter requires extra
+        && args.filter_driver.is_none() // driver filter requires extra
+        && !((args.tree && args.lsusb) || args.verbose > 0 || args.more)
+    {
+        system_profiler::get_spusb().map_err(|e| {
+            Error::new(
+                ErrorKind::Other,
+                format!("Failed to parse system_profiler output: Error({})", e)
+            )
         })
+    } else {
+        // if not forcing libusb, get system_profiler and the merge with libusb
+        if cfg!(target_os = "macos") && !args.force_libusb {
+            log::warn!("Merging macOS system_profiler output with libusb for verbose data. Apple internal devices will not be obtained");
+            system_profiler::get_spusb_with_extra()
+        } else {
+            get_libusb_spusb(args)
+        }
     }
 }
 
@@ -330,9 +687,11 @@ fn print_lsusb(
             lsusb::display::dump_one_device(&devices, dev_path).unwrap_or_else(|e| {
                 eprintexit!(std::io::Error::new(std::io::ErrorKind::Other, e));
             });
+        } else if settings.verbosity > 0 {
+            display::print_lsusb_verbose(&sp_usb, &settings);
         } else {
             let sorted = settings.sort_devices.sort_devices_ref(&devices);
-            lsusb::display::print(&sorted, settings.verbosity > 0);
+            lsusb::display::print(&sorted, false);
         }
     }
 }
@@ -412,56 +771,97 @@ fn main() {
     };
 
     merge_config(&config, &mut args);
-    let colours = if args.no_colour {
-        // set env to be sure too
-        env::set_var("NO_COLOR", "1");
-        None
+    // printing from a port path is a tree rooted at that device
+    if args.path.is_some() {
+        args.tree = true;
+    }
+
+    if args.list_icons {
+        for (i, value) in config.icons.icons() {
+            let class = match &i {
+                icon::Icon::Classifier(c) | icon::Icon::ClassifierSubProtocol((c, _, _)) => {
+                    c.to_string()
+                }
+                _ => String::new(),
+            };
+            println!("{} {:40} {}", value, i.to_string(), class);
+        }
+        std::process::exit(0);
+    }
+    if args.dump_schema {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&Config::schema()).unwrap()
+        );
+        std::process::exit(0);
+    }
+    let colour_when = if args.no_colour {
+        display::ColourWhen::Never
     } else {
-        Some(config.colours)
+        args.color
     };
-
-    let icons = if args.ascii { None } else { Some(config.icons) };
-
-    let mut spusb = if let Some(file_path) = args.from_json {
-        system_profiler::read_json_dump(&file_path.as_str()).unwrap_or_else(|e| {
-            eprintexit!(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!("Failed to parse system_profiler dump: Error({})", e)
+    // resolve the theme even if colour is off so that --dump-config keeps it
+    let theme = if let Some(name) = args.theme.as_ref() {
+        let path = Config::theme_file_path(name).unwrap_or_else(|| {
+            eprintexit!(Error::new(
+                ErrorKind::NotFound,
+                format!("Unable to find config directory for theme '{}'", name)
             ));
-        })
-    } else if cfg!(target_os = "macos") 
-        && !args.force_libusb
-        && args.device.is_none() // device path requires extra
-        && args.filter_class.is_none() // class filter requires extra
-        && !((args.tree && args.lsusb) || args.verbose > 0 || args.more)
-    {
-        system_profiler::get_spusb().unwrap_or_else(|e| {
-            eprintexit!(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!("Failed to parse system_profiler output: Error({})", e)
+        });
+        colour::ColourTheme::from_file(&path).unwrap_or_else(|e| {
+            eprintexit!(Error::new(
+                e.kind(),
+                format!(
+                    "Failed to load theme '{}' from {:?}: Error({})",
+                    name, path, e
+                )
             ));
         })
     } else {
-        // if not forcing libusb, get system_profiler and the merge with libusb
-        if cfg!(target_os = "macos") && !args.force_libusb {
-            log::warn!("Merging macOS system_profiler output with libusb for verbose data. Apple internal devices will not be obtained");
-            system_profiler::get_spusb_with_extra().unwrap_or_else(|e| {
-                eprintexit!(e);
-            })
-        } else {
-            get_libusb_spusb(&args)
-        }
+        config.colours.clone()
+    };
+    // a theme file replaces any preset
+    let colour_preset = if args.theme.is_none() {
+        args.theme_preset.or(config.colour_preset)
+    } else {
+        None
     };
+    let colours = if colour_when == display::ColourWhen::Never {
+        // set env to be sure too
+        env::set_var("NO_COLOR", "1");
+        None
+    } else if let Some(preset) = colour_preset {
+        Some(colour::ColourTheme::preset(preset))
+    } else {
+        Some(theme.clone())
+    };
+    // merged config before colour and icons are resolved for this run
+    let merged_config = args.dump_config.then(|| Config {
+        colours: theme,
+        colour_preset,
+        ..config.clone()
+    });
 
-    log::trace!("Returned system_profiler data\n\r{:#?}", spusb);
+    let icons = if args.ascii || args.no_icons {
+        None
+    } else {
+        Some(config.icons)
+    };
 
     let filter = if args.hide_hubs
+        || args.exclude_empty_hub
         || args.vidpid.is_some()
         || args.show.is_some()
         || args.device.is_some()
+        || args.filter_list.is_some()
+        || args.select.is_some()
         || args.filter_name.is_some()
+        || args.filter_name_regex.is_some()
         || args.filter_serial.is_some()
         || args.filter_class.is_some()
+        || args.hide_class.is_some()
+        || args.filter_transfer_type.is_some()
+        || args.filter_driver.is_some()
     {
         let mut f = system_profiler::USBFilter::new();
 
@@ -500,14 +900,56 @@ fn main() {
             f.number = number;
         }
 
+        if let Some(file_path) = &args.filter_list {
+            f.vidpid_list = Some(
+                system_profiler::USBFilter::vidpid_list_from_file(file_path.as_str())
+                    .unwrap_or_else(|e| {
+                        eprintexit!(Error::new(
+                            ErrorKind::Other,
+                            format!("Failed to read vid:pid list '{}': Error({})", file_path, e)
+                        ));
+                    }),
+            );
+        }
+
+        if let Some(expr) = &args.select {
+            f.select = Some(expr.parse::<Select>().unwrap_or_else(|e| {
+                eprintexit!(Error::new(
+                    ErrorKind::Other,
+                    format!("Failed to parse select expression '{}': Error({})", expr, e)
+                ));
+            }));
+        }
+
+        if let Some(regex) = &args.filter_name_regex {
+            f.name_regex = Some(
+                system_profiler::USBFilter::name_regex_from_str(regex.as_str()).unwrap_or_else(
+                    |e| {
+                        eprintexit!(Error::new(
+                            ErrorKind::Other,
+                            format!("Failed to parse name regex '{}': Error({})", regex, e)
+                        ));
+                    },
+                ),
+            );
+        }
+
         // no need to unwrap as these are Option
-        f.name = args.filter_name;
-        f.serial = args.filter_serial;
-        f.class = args.filter_class;
-        f.exclude_empty_hub = args.hide_hubs;
+        f.name = args.filter_name.clone();
+        f.serial = args.filter_serial.clone();
+        f.class = args.filter_class.clone();
+        f.exclude_class = args.hide_class.clone();
+        f.transfer_type = args.filter_transfer_type.clone();
+        f.driver = args.filter_driver.clone();
+        f.exclude_empty_hub = args.hide_hubs || args.exclude_empty_hub;
+        // when listing hide hubs regardless of whether empty
+        f.exclude_hubs = args.hide_hubs && !args.tree;
         // exclude root hubs unless dumping a list or json
-        f.no_exclude_root_hub =
-            args.lsusb || args.json || !(args.tree || args.group_devices == display::Group::Bus);
+        f.no_exclude_root_hub = args.lsusb
+            || args.json
+            || args.yaml
+            || args.ndjson
+            || !(args.tree || args.group_devices == display::Group::Bus);
 
         Some(f)
     } else {
@@ -517,6 +959,8 @@ fn main() {
             Some(system_profiler::USBFilter {
                 no_exclude_root_hub: args.lsusb
                     || args.json
+                    || args.yaml
+                    || args.ndjson
                     || !(args.tree || args.group_devices == display::Group::Bus),
                 ..Default::default()
             })
@@ -528,8 +972,8 @@ fn main() {
     log::info!("Filtering with {:?}", filter);
 
     // no sort if just dumping because it looks wierd with buses out of order
-    let sort_devices = match args.sort_devices {
-        Some(v) => v,
+    let sort_devices = match &args.sort_devices {
+        Some(v) => v.clone(),
         None => {
             if args.tree || args.group_devices != display::Group::NoGroup {
                 display::Sort::default()
@@ -539,39 +983,232 @@ fn main() {
         }
     };
 
-    let group_devices = if args.group_devices == display::Group::Bus && args.tree {
+    let group_devices = if args.group_devices != display::Group::NoGroup && args.tree {
         eprintln!("--group-devices with --tree is ignored; will print as tree");
         display::Group::NoGroup
     } else {
-        args.group_devices
+        args.group_devices.clone()
     };
 
-    let settings = display::PrintSettings {
+    let max_widths = args.truncate.as_ref().map(|truncate| {
+        truncate
+            .iter()
+            .map(|t| {
+                parse_truncate(t).unwrap_or_else(|e| {
+                    eprintexit!(Error::new(
+                        ErrorKind::Other,
+                        format!(
+                            "Failed to parse truncate '{}', should be 'BLOCK=WIDTH': Error({})",
+                            t, e
+                        )
+                    ));
+                })
+            })
+            .collect()
+    });
+
+    let mut settings = display::PrintSettings {
         no_padding: args.no_padding,
-        decimal: args.decimal,
+        compact: args.compact,
+        only_matching: args.only_matching,
+        number_format: args
+            .decimal
+            .as_ref()
+            .map_or(Default::default(), |d| display::NumberFormat::new(d)),
         tree: args.tree,
+        tree_style: args.tree_style,
+        ascii_tree: args.ascii || args.ascii_tree,
+        root_path: args.path.clone(),
         hide_buses: args.hide_buses,
         sort_devices,
-        sort_buses: args.sort_buses,
+        sort_buses: args.sort_buses.clone().unwrap_or_default(),
+        sort_interfaces: args.sort_interfaces,
+        human_sizes: args.human_sizes,
+        sort_endpoints: args.sort_endpoints,
         group_devices,
+        group_sort: args.group_sort,
         json: args.json,
+        yaml: args.yaml,
+        ndjson: args.ndjson,
+        flatten_json: args.flatten_json,
+        json_compact: args.json_compact,
+        html: args.html,
         headings: args.headings,
+        headings_once: args.no_headings_repeat,
+        indent_guides: args.indent_guides,
+        indent_step: args.indent_step,
+        align_tree: args.align_tree,
+        hex: args.hex,
+        semantic_colours: args.semantic_colours,
+        rainbow_path: args.rainbow_path,
+        collapse_alt_settings: args.collapse_alt_settings,
+        prefer_usb_ids: args.prefer_usb_ids,
+        prune_interfaces: if args.prune_interfaces {
+            args.filter_class.clone()
+        } else {
+            None
+        },
+        output: args.output_file.clone(),
+        pad_positions: args.pad_positions,
+        pager: args.pager,
+        notify: args.notify,
         verbosity: args.verbose,
         more: args.more,
-        mask_serials: args.mask_serials.map_or(config.mask_serials, Some),
-        device_blocks: args.blocks.map_or(config.blocks, Some),
-        bus_blocks: args.bus_blocks.map_or(config.bus_blocks, Some),
-        config_blocks: args.config_blocks.map_or(config.config_blocks, Some),
-        interface_blocks: args.interface_blocks.map_or(config.interface_blocks, Some),
-        endpoint_blocks: args.endpoint_blocks.map_or(config.endpoint_blocks, Some),
+        max_depth: args.max_depth,
+        limit: args.limit,
+        summary: args.summary,
+        count_only: args.count,
+        mask_serials: args.mask_serials.clone().map_or(config.mask_serials, Some),
+        mask_serials_visible: args.mask_serials_visible.or(config.mask_serials_visible),
+        device_blocks: config.blocks,
+        bus_blocks: config.bus_blocks,
+        config_blocks: config.config_blocks,
+        interface_blocks: config.interface_blocks,
+        endpoint_blocks: config.endpoint_blocks,
+        device_alignment: config.device_alignment,
+        bus_alignment: config.bus_alignment,
+        config_alignment: config.config_alignment,
+        interface_alignment: config.interface_alignment,
+        endpoint_alignment: config.endpoint_alignment,
+        json_fields: args.fields.clone(),
+        max_widths,
+        auto_width: if args.auto_width {
+            terminal_size::terminal_size().map(|(terminal_size::Width(w), _)| w as usize)
+        } else {
+            None
+        },
         icons,
         colours,
         ..Default::default()
     };
+    settings.apply_colour_when(colour_when);
+
+    if args.legend {
+        let mut writer = open_writer(&settings);
+        display::print_legend(&settings, &mut writer)
+            .and_then(|_| writer.flush())
+            .unwrap_or_else(|e| {
+                eprintexit!(std::io::Error::new(
+                    e.kind(),
+                    format!("Failed to print legend: Error({})", e)
+                ));
+            });
+        // exit does not drop so close any pager first
+        drop(writer);
+        std::process::exit(0);
+    }
+
+    // resolve block args against config or default blocks now other settings are known
+    if let Some(blocks) = args.blocks.as_ref() {
+        let defaults = settings
+            .device_blocks
+            .take()
+            .unwrap_or_else(|| display::DeviceBlocks::default_blocks_for(&settings));
+        settings.device_blocks = Some(display::resolve_blocks(blocks, defaults));
+    }
+    if let Some(blocks) = args.bus_blocks.as_ref() {
+        let defaults = settings
+            .bus_blocks
+            .take()
+            .unwrap_or_else(|| display::BusBlocks::default_blocks_for(&settings));
+        settings.bus_blocks = Some(display::resolve_blocks(blocks, defaults));
+    }
+    if let Some(blocks) = args.config_blocks.as_ref() {
+        let defaults = settings
+            .config_blocks
+            .take()
+            .unwrap_or_else(|| display::ConfigurationBlocks::default_blocks_for(&settings));
+        settings.config_blocks = Some(display::resolve_blocks(blocks, defaults));
+    }
+    if let Some(blocks) = args.interface_blocks.as_ref() {
+        let defaults = settings
+            .interface_blocks
+            .take()
+            .unwrap_or_else(|| display::InterfaceBlocks::default_blocks_for(&settings));
+        settings.interface_blocks = Some(display::resolve_blocks(blocks, defaults));
+    }
+    if let Some(blocks) = args.endpoint_blocks.as_ref() {
+        let defaults = settings
+            .endpoint_blocks
+            .take()
+            .unwrap_or_else(|| display::EndpointBlocks::default_blocks_for(&settings));
+        settings.endpoint_blocks = Some(display::resolve_blocks(blocks, defaults));
+    }
+
+    if let Some(merged_config) = merged_config {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&effective_config(merged_config, &settings, &args))
+                .unwrap()
+        );
+        std::process::exit(0);
+    }
+
+    if args.watch {
+        cyme::watch::watch(|| get_spusb(&args), filter, &settings).unwrap_or_else(|e| {
+            eprintexit!(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Failed to watch USB devices: Error({})", e)
+            ));
+        });
+        return;
+    }
+
+    if let Some(secs) = args.interval {
+        cyme::watch::repeat(
+            || get_spusb(&args),
+            filter,
+            &settings,
+            std::time::Duration::from_secs(secs),
+        )
+        .unwrap_or_else(|e| {
+            eprintexit!(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Failed to repeat USB device output: Error({})", e)
+            ));
+        });
+        return;
+    }
+
+    let mut spusb = get_spusb(&args).unwrap_or_else(|e| {
+        eprintexit!(e);
+    });
+
+    log::trace!("Returned system_profiler data\n\r{:#?}", spusb);
+
+    // serials are masked when rendering the diff instead so that both dumps are compared unmasked
+    let diff_mask_serials = args
+        .diff
+        .as_ref()
+        .and_then(|_| settings.mask_serials.take());
+
+    // baseline gets the same filtering so filtered devices are not reported as removed
+    let baseline = args.diff.as_ref().map(|file_path| {
+        let mut baseline =
+            system_profiler::read_json_dump(&file_path.as_str()).unwrap_or_else(|e| {
+                eprintexit!(std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    format!("Failed to parse baseline dump: Error({})", e)
+                ));
+            });
+        display::prepare(&mut baseline, filter.clone(), &settings);
+        baseline
+    });
 
     display::prepare(&mut spusb, filter, &settings);
 
-    if args.lsusb {
+    if let Some(baseline) = baseline {
+        settings.mask_serials = diff_mask_serials;
+        let mut writer = open_writer(&settings);
+        display::print_diff(&baseline, &spusb, &settings, &mut writer)
+            .and_then(|_| writer.flush())
+            .unwrap_or_else(|e| {
+                eprintexit!(std::io::Error::new(
+                    e.kind(),
+                    format!("Failed to print differences: Error({})", e)
+                ));
+            });
+    } else if args.lsusb {
         print_lsusb(&spusb, &args.device, &settings);
     } else {
         // check and report if was looking for args.device
@@ -581,7 +1218,15 @@ fn main() {
                 format!("Unable to find {:?}", args.device.unwrap())
             ));
         }
-        display::print(&mut spusb, &settings);
+        let mut writer = open_writer(&settings);
+        display::print(&spusb, &settings, &mut writer)
+            .and_then(|_| writer.flush())
+            .unwrap_or_else(|e| {
+                eprintexit!(std::io::Error::new(
+                    e.kind(),
+                    format!("Failed to print USB devices: Error({})", e)
+                ));
+            });
     }
 }
 
@@ -595,10 +1240,53 @@ mod tests {
         let mut args = Args {
             ..Default::default()
         };
-        args.blocks = Some(vec![display::DeviceBlocks::BusNumber]);
+        args.blocks = Some(vec![display::BlockArg::Select(
+            display::DeviceBlocks::BusNumber,
+        )]);
         println!("{}", serde_json::to_string_pretty(&args).unwrap());
     }
 
+    #[test]
+    fn test_sort_buses_arg() {
+        let args = Args::try_parse_from(["cyme", "--sort-buses"]).unwrap();
+        assert_eq!(args.sort_buses, Some(display::BusSort::Number));
+        let args = Args::try_parse_from(["cyme", "--sort-buses", "name"]).unwrap();
+        assert_eq!(args.sort_buses, Some(display::BusSort::Name));
+        let args = Args::try_parse_from(["cyme"]).unwrap();
+        assert_eq!(args.sort_buses, None);
+    }
+
+    #[test]
+    fn test_decimal_arg() {
+        let args = Args::try_parse_from(["cyme", "--decimal"]).unwrap();
+        assert_eq!(args.decimal, Some(vec![display::NumberCategory::All]));
+        let args = Args::try_parse_from(["cyme", "--decimal", "ids,codes"]).unwrap();
+        assert_eq!(
+            args.decimal,
+            Some(vec![
+                display::NumberCategory::Ids,
+                display::NumberCategory::Codes
+            ])
+        );
+        let args = Args::try_parse_from(["cyme"]).unwrap();
+        assert_eq!(args.decimal, None);
+    }
+
+    #[test]
+    fn test_parse_truncate() {
+        assert_eq!(
+            parse_truncate("name=20").unwrap(),
+            (display::DeviceBlocks::Name, 20)
+        );
+        assert_eq!(
+            parse_truncate("Serial= 8").unwrap(),
+            (display::DeviceBlocks::Serial, 8)
+        );
+        assert_eq!(parse_truncate("name").is_err(), true);
+        assert_eq!(parse_truncate("nope=2").is_err(), true);
+        assert_eq!(parse_truncate("name=-1").is_err(), true);
+    }
+
     #[test]
     fn test_parse_vidpid() {
         assert_eq!(
@@ -639,4 +1327,69 @@ mod tests {
         assert_eq!(parse_devpath("004/").is_err(), true);
         assert_eq!(parse_devpath("sas/ssas").is_err(), true);
     }
+
+    #[test]
+    fn test_effective_config() {
+        let config = Config {
+            tree: true,
+            verbose: 2,
+            colour_preset: Some(colour::ColourPreset::default()),
+            ..Config::new()
+        };
+        let mut args =
+            Args::try_parse_from(["cyme", "--headings", "--decimal", "codes", "--no-icons"])
+                .unwrap();
+        merge_config(&config, &mut args);
+        // colours and icons turned off for this run
+        let settings = display::PrintSettings {
+            tree: args.tree,
+            verbosity: args.verbose,
+            colours: None,
+            icons: None,
+            ..Default::default()
+        };
+
+        let effective = effective_config(config.clone(), &settings, &args);
+        assert!(effective.tree);
+        assert!(effective.headings);
+        assert!(effective.decimal);
+        assert_eq!(
+            effective.decimal_categories,
+            Some(vec![display::NumberCategory::Codes])
+        );
+        assert_eq!(effective.verbose, 2);
+        assert_eq!(
+            effective.endpoint_blocks,
+            Some(display::EndpointBlocks::default_blocks(false))
+        );
+        assert_eq!(effective.version, cyme::config::CONFIG_VERSION);
+        assert_eq!(effective.colours, config.colours);
+        assert_eq!(effective.icons, config.icons);
+        assert_eq!(effective.colour_preset, config.colour_preset);
+
+        // and loading the dump merges back to the same args
+        let mut reloaded = Args::try_parse_from(["cyme"]).unwrap();
+        merge_config(&effective, &mut reloaded);
+        assert_eq!(reloaded.decimal, args.decimal);
+        assert_eq!(reloaded.tree, args.tree);
+    }
+
+    #[test]
+    fn test_interval_arg() {
+        let args = Args::try_parse_from(["cyme", "--interval", "2"]).unwrap();
+        assert_eq!(args.interval, Some(2));
+        assert!(Args::try_parse_from(["cyme", "--interval", "0"]).is_err());
+        assert!(Args::try_parse_from(["cyme", "--interval", "2", "--watch"]).is_err());
+    }
+
+    #[test]
+    fn test_notify_arg() {
+        let args = Args::try_parse_from(["cyme", "--watch", "--notify"]).unwrap();
+        assert_eq!(args.notify, Some(display::Notify::Bell));
+        let args =
+            Args::try_parse_from(["cyme", "--interval", "1", "--notify", "desktop"]).unwrap();
+        assert_eq!(args.notify, Some(display::Notify::Desktop));
+        // nothing to notify of without re-profiling
+        assert!(Args::try_parse_from(["cyme", "--notify"]).is_err());
+    }
 }
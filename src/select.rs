@@ -0,0 +1,506 @@
+//! Small expression language for `--select` filtering of [`USBDevice`]s such as `class==hid && speed>=high`
+//!
+//! An expression is comparisons of a field with a value combined with `&&`, `||`, `!` and parentheses; `&&` binds tighter than `||`. Fields and the operators they support:
+//!
+//! * `vid`, `pid`: hex id, optionally '0x' prefixed; `==`, `!=`, `<`, `<=`, `>`, `>=`
+//! * `speed`: `low`, `full`, `high`, `super` or `super_plus`; `==`, `!=`, `<`, `<=`, `>`, `>=`
+//! * `class`: USB class code like `hid` or `mass-storage` of the device or any of its interfaces; `==`, `!=`
+//! * `driver`: udev driver of the device or any of its interfaces; `==`, `!=`
+//! * `name`: device name, `~` for contains; `==`, `!=`, `~`
+//!
+//! Values containing spaces or operator chars can be quoted with '"' or '\''. A device without the field, such as one without a vid, never matches a comparison of it.
+//!
+//! ```
+//! use std::str::FromStr;
+//! use cyme::select::Select;
+//! use cyme::system_profiler::*;
+//!
+//! # let spusb = read_json_dump(&"./tests/data/cyme_libusb_linux_tree.json").unwrap();
+//! let select = Select::from_str("driver==cdc_acm && !name~'Black Magic'").unwrap();
+//! let matched: Vec<&USBDevice> = spusb
+//!     .flatten_devices()
+//!     .into_iter()
+//!     .filter(|d| select.is_match(d))
+//!     .collect();
+//! assert_eq!(matched.len(), 1);
+//! assert_eq!(matched[0].name, "J-Link");
+//! assert!(Select::from_str("class<hid").is_err());
+//! ```
+use clap::ValueEnum;
+use std::cmp::Ordering;
+use std::fmt;
+use std::io;
+use std::iter::Peekable;
+use std::str::{Chars, FromStr};
+
+use crate::system_profiler::{DeviceSpeed, USBDevice};
+use crate::usb::{ClassCode, Speed};
+
+/// Device field compared in a [`Select`] expression
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    /// Unique vendor identifier
+    Vid,
+    /// Vendor unique product identifier
+    Pid,
+    /// Class of the device or any of its interfaces
+    Class,
+    /// Device speed
+    Speed,
+    /// udev driver of the device or any of its interfaces
+    Driver,
+    /// Device name
+    Name,
+}
+
+impl FromStr for Field {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "vid" => Ok(Field::Vid),
+            "pid" => Ok(Field::Pid),
+            "class" => Ok(Field::Class),
+            "speed" => Ok(Field::Speed),
+            "driver" => Ok(Field::Driver),
+            "name" => Ok(Field::Name),
+            _ => Err(invalid(format!(
+                "Unknown field '{}', expected one of vid, pid, class, speed, driver or name",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Field::Vid => "vid",
+            Field::Pid => "pid",
+            Field::Class => "class",
+            Field::Speed => "speed",
+            Field::Driver => "driver",
+            Field::Name => "name",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// Comparison operator of a [`Select`] expression
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    /// `==`
+    Eq,
+    /// `!=`
+    Ne,
+    /// `<`
+    Lt,
+    /// `<=`
+    Le,
+    /// `>`
+    Gt,
+    /// `>=`
+    Ge,
+    /// `~` contains
+    Contains,
+}
+
+impl Op {
+    /// Whether `ordering` of device value to compared value satisfies the operator; always false for [`Op::Contains`]
+    fn is_satisfied(&self, ordering: Ordering) -> bool {
+        match self {
+            Op::Eq => ordering == Ordering::Equal,
+            Op::Ne => ordering != Ordering::Equal,
+            Op::Lt => ordering == Ordering::Less,
+            Op::Le => ordering != Ordering::Greater,
+            Op::Gt => ordering == Ordering::Greater,
+            Op::Ge => ordering != Ordering::Less,
+            Op::Contains => false,
+        }
+    }
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Op::Eq => "==",
+            Op::Ne => "!=",
+            Op::Lt => "<",
+            Op::Le => "<=",
+            Op::Gt => ">",
+            Op::Ge => ">=",
+            Op::Contains => "~",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// Value a [`Field`] is compared with, parsed according to the field
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// vid or pid
+    Id(u16),
+    /// USB class code
+    Class(ClassCode),
+    /// Rank of [`Speed`], see [`speed_rank`]
+    Speed(u8),
+    /// driver or name
+    Text(String),
+}
+
+/// Parsed `--select` expression evaluated per device with [`Select::is_match`]; see the [module](self) docs for the syntax
+#[derive(Debug, Clone, PartialEq)]
+pub enum Select {
+    /// `field op value`
+    Compare {
+        /// Device field compared
+        field: Field,
+        /// Comparison operator
+        op: Op,
+        /// Value compared with
+        value: Value,
+    },
+    /// `!expr`
+    Not(Box<Select>),
+    /// `lhs && rhs`
+    And(Box<Select>, Box<Select>),
+    /// `lhs || rhs`
+    Or(Box<Select>, Box<Select>),
+}
+
+impl Select {
+    /// Builds a comparison of `field` with `value`, returning an `io::Error` if `value` cannot be parsed for the field or `op` is not supported by it
+    pub fn compare(field: Field, op: Op, value: &str) -> Result<Self, io::Error> {
+        let supported = match field {
+            Field::Vid | Field::Pid | Field::Speed => op != Op::Contains,
+            Field::Class | Field::Driver => matches!(op, Op::Eq | Op::Ne),
+            Field::Name => matches!(op, Op::Eq | Op::Ne | Op::Contains),
+        };
+        if !supported {
+            return Err(invalid(format!(
+                "Operator '{}' is not supported for {}",
+                op, field
+            )));
+        }
+
+        let value = match field {
+            Field::Vid | Field::Pid => u16::from_str_radix(value.trim_start_matches("0x"), 16)
+                .map(Value::Id)
+                .map_err(|e| invalid(format!("Invalid {} '{}': {}", field, value, e)))?,
+            Field::Class => <ClassCode as ValueEnum>::from_str(value, true)
+                .map(Value::Class)
+                .map_err(|e| invalid(format!("Invalid class '{}': {}", value, e)))?,
+            Field::Speed => speed_from_str(value)
+                .map(|s| Value::Speed(speed_rank(&s)))
+                .ok_or_else(|| {
+                    invalid(format!(
+                        "Invalid speed '{}', expected low, full, high, super or super_plus",
+                        value
+                    ))
+                })?,
+            Field::Driver | Field::Name => Value::Text(value.to_owned()),
+        };
+
+        Ok(Select::Compare { field, op, value })
+    }
+
+    /// Whether `device` satisfies the expression
+    pub fn is_match(&self, device: &USBDevice) -> bool {
+        match self {
+            Select::Not(s) => !s.is_match(device),
+            Select::And(lhs, rhs) => lhs.is_match(device) && rhs.is_match(device),
+            Select::Or(lhs, rhs) => lhs.is_match(device) || rhs.is_match(device),
+            Select::Compare { field, op, value } => match (field, value) {
+                (Field::Vid, Value::Id(v)) => device
+                    .vendor_id
+                    .map_or(false, |d| op.is_satisfied(d.cmp(v))),
+                (Field::Pid, Value::Id(v)) => device
+                    .product_id
+                    .map_or(false, |d| op.is_satisfied(d.cmp(v))),
+                (Field::Speed, Value::Speed(v)) => {
+                    device_speed_rank(device).map_or(false, |d| op.is_satisfied(d.cmp(v)))
+                }
+                (Field::Class, Value::Class(c)) => device.has_class(c) == (*op == Op::Eq),
+                (Field::Driver, Value::Text(d)) => device.has_driver(d) == (*op == Op::Eq),
+                (Field::Name, Value::Text(n)) => match op {
+                    Op::Contains => device.name.contains(n.as_str()),
+                    _ => op.is_satisfied(device.name.as_str().cmp(n.as_str())),
+                },
+                _ => false,
+            },
+        }
+    }
+}
+
+impl FromStr for Select {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            tokens: tokenize(s)?,
+            pos: 0,
+        };
+        let ret = parser.or()?;
+        match parser.tokens.get(parser.pos) {
+            Some(t) => Err(invalid(format!("Unexpected {} in '{}'", t, s))),
+            None => Ok(ret),
+        }
+    }
+}
+
+/// Orders [`Speed`] from slowest to fastest so that speeds can be compared; [`Speed::Unknown`] is 0
+pub fn speed_rank(speed: &Speed) -> u8 {
+    match speed {
+        Speed::Unknown => 0,
+        Speed::LowSpeed => 1,
+        Speed::FullSpeed => 2,
+        Speed::HighSpeed | Speed::HighBandwidth => 3,
+        Speed::SuperSpeed => 4,
+        Speed::SuperSpeedPlus => 5,
+    }
+}
+
+fn speed_from_str(s: &str) -> Option<Speed> {
+    let speed = match s.to_lowercase().as_str() {
+        "low" => Speed::LowSpeed,
+        "full" => Speed::FullSpeed,
+        "high" => Speed::HighSpeed,
+        "super" => Speed::SuperSpeed,
+        "super_plus" | "super+" => Speed::SuperSpeedPlus,
+        _ => Speed::from_str(s).unwrap_or(Speed::Unknown),
+    };
+
+    match speed {
+        Speed::Unknown => None,
+        s => Some(s),
+    }
+}
+
+fn device_speed_rank(device: &USBDevice) -> Option<u8> {
+    match device.device_speed.as_ref() {
+        Some(DeviceSpeed::SpeedValue(s)) => Some(speed_rank(s)),
+        _ => None,
+    }
+}
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Op(Op),
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Word(w) => write!(f, "'{}'", w),
+            Token::Op(op) => write!(f, "'{}'", op),
+            Token::And => write!(f, "'&&'"),
+            Token::Or => write!(f, "'||'"),
+            Token::Not => write!(f, "'!'"),
+            Token::Open => write!(f, "'('"),
+            Token::Close => write!(f, "')'"),
+        }
+    }
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token>, io::Error> {
+    let mut tokens = Vec::new();
+    let mut chars = s.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '&' | '|' => {
+                if chars.next_if_eq(&c).is_none() {
+                    return Err(invalid(format!("Expected '{}{}' in '{}'", c, c, s)));
+                }
+                if c == '&' {
+                    Token::And
+                } else {
+                    Token::Or
+                }
+            }
+            '=' | '!' | '<' | '>' | '~' => match (c, chars.next_if_eq(&'=').is_some()) {
+                ('=', true) => Token::Op(Op::Eq),
+                ('!', true) => Token::Op(Op::Ne),
+                ('!', false) => Token::Not,
+                ('<', true) => Token::Op(Op::Le),
+                ('<', false) => Token::Op(Op::Lt),
+                ('>', true) => Token::Op(Op::Ge),
+                ('>', false) => Token::Op(Op::Gt),
+                ('~', false) => Token::Op(Op::Contains),
+                _ => return Err(invalid(format!("Unexpected '{}' in '{}'", c, s))),
+            },
+            '"' | '\'' => Token::Word(
+                quoted(&mut chars, c)
+                    .ok_or_else(|| invalid(format!("Unterminated {} quote in '{}'", c, s)))?,
+            ),
+            _ => {
+                let mut word = c.to_string();
+                while let Some(w) =
+                    chars.next_if(|w| !w.is_whitespace() && !"()&|=!<>~\"'".contains(*w))
+                {
+                    word.push(w);
+                }
+                Token::Word(word)
+            }
+        };
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+fn quoted(chars: &mut Peekable<Chars>, quote: char) -> Option<String> {
+    let mut ret = String::new();
+    for c in chars.by_ref() {
+        if c == quote {
+            return Some(ret);
+        }
+        ret.push(c);
+    }
+    None
+}
+
+/// Recursive descent parser of `or := and ('||' and)*`, `and := unary ('&&' unary)*`, `unary := '!' unary | '(' or ')' | field op value`
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let ret = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        ret
+    }
+
+    fn next_is(&mut self, token: &Token) -> bool {
+        if self.tokens.get(self.pos) == Some(token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn or(&mut self) -> Result<Select, io::Error> {
+        let mut ret = self.and()?;
+        while self.next_is(&Token::Or) {
+            ret = Select::Or(Box::new(ret), Box::new(self.and()?));
+        }
+        Ok(ret)
+    }
+
+    fn and(&mut self) -> Result<Select, io::Error> {
+        let mut ret = self.unary()?;
+        while self.next_is(&Token::And) {
+            ret = Select::And(Box::new(ret), Box::new(self.unary()?));
+        }
+        Ok(ret)
+    }
+
+    fn unary(&mut self) -> Result<Select, io::Error> {
+        match self.next() {
+            Some(Token::Not) => Ok(Select::Not(Box::new(self.unary()?))),
+            Some(Token::Open) => {
+                let ret = self.or()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(ret),
+                    _ => Err(invalid("Expected ')'".into())),
+                }
+            }
+            Some(Token::Word(field)) => {
+                let field = Field::from_str(&field)?;
+                let op = match self.next() {
+                    Some(Token::Op(op)) => op,
+                    _ => {
+                        return Err(invalid(format!(
+                            "Expected comparison operator after {}",
+                            field
+                        )))
+                    }
+                };
+                match self.next() {
+                    Some(Token::Word(value)) => Select::compare(field, op, &value),
+                    _ => Err(invalid(format!("Expected value after {} {}", field, op))),
+                }
+            }
+            Some(t) => Err(invalid(format!("Unexpected {}", t))),
+            None => Err(invalid("Unexpected end of expression".into())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system_profiler::read_json_dump;
+
+    fn count_matches(s: &str) -> usize {
+        let spusb = read_json_dump("./tests/data/cyme_libusb_linux_tree.json").unwrap();
+        let select = Select::from_str(s).unwrap();
+        spusb
+            .flatten_devices()
+            .into_iter()
+            .filter(|d| select.is_match(d))
+            .count()
+    }
+
+    #[test]
+    fn test_parse_precedence() {
+        let select = Select::from_str("vid==1d50 || vid==1366 && pid==1050").unwrap();
+        assert!(matches!(select, Select::Or(_, ref rhs) if matches!(**rhs, Select::And(_, _))));
+        let select = Select::from_str("(vid==1d50 || vid==1366) && pid==1050").unwrap();
+        assert!(matches!(select, Select::And(_, _)));
+        assert_eq!(
+            Select::from_str("speed>=high").unwrap(),
+            Select::Compare {
+                field: Field::Speed,
+                op: Op::Ge,
+                value: Value::Speed(3),
+            }
+        );
+    }
+
+    #[test]
+    fn test_select_devices() {
+        assert_eq!(count_matches("class==hid"), 1);
+        assert_eq!(count_matches("vid==1d50 || vid==0x1366"), 2);
+        assert_eq!(count_matches("class==hub && speed>=high"), 3);
+        assert_eq!(count_matches("driver==cdc_acm && speed==full"), 2);
+        assert_eq!(count_matches("name==\"J-Link\""), 1);
+        assert_eq!(count_matches("!(class!=printer)"), 1);
+    }
+
+    #[test]
+    fn test_select_errors() {
+        for s in [
+            "",
+            "foo==1",
+            "vid==zz",
+            "vid~1d50",
+            "class<hid",
+            "speed>=warp",
+            "vid==1d50 &&",
+            "(vid==1d50",
+            "vid==1d50)",
+            "vid=1d50",
+            "vid==1d50 & pid==6018",
+            "name=='J-Link",
+        ] {
+            assert!(Select::from_str(s).is_err(), "'{}' should not parse", s);
+        }
+    }
+}
@@ -7,11 +7,14 @@ use std::fs;
 use std::process::Command;
 use std::str::FromStr;
 use std::io::Read;
+use std::collections::HashSet;
 use serde::de::{self, MapAccess, SeqAccess, Visitor};
 use serde::{Deserialize, Deserializer, Serialize};
 use serde_with::{skip_serializing_none, DeserializeFromStr, SerializeDisplay};
 use colored::*;
+use regex::Regex;
 
+use crate::select::Select;
 use crate::types::NumericalUnit;
 use crate::usb::*;
 
@@ -24,6 +27,37 @@ pub struct SPUSBDataType {
 }
 
 impl SPUSBDataType {
+    /// Deserializes from json `s`, either from `system_profiler -json SPUSBDataType` or `cyme --json --tree`
+    ///
+    /// Must be a full tree including buses. Invalid json is returned as an `InvalidData` error
+    ///
+    /// ```
+    /// use cyme::system_profiler::SPUSBDataType;
+    ///
+    /// let json = std::fs::read_to_string("./tests/data/cyme_libusb_linux_tree.json").unwrap();
+    /// let spusb = SPUSBDataType::from_json(&json).unwrap();
+    /// assert_eq!(spusb.flatten_devices().len(), 9);
+    /// assert!(SPUSBDataType::from_json("not json").is_err());
+    /// ```
+    pub fn from_json(s: &str) -> Result<Self, io::Error> {
+        serde_json::from_str(s).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Serializes to pretty json as `cyme --json --tree` prints it, which can be read back with [`SPUSBDataType::from_json`]
+    ///
+    /// ```
+    /// use cyme::system_profiler::{read_json_dump, SPUSBDataType};
+    ///
+    /// let spusb = read_json_dump("./tests/data/cyme_libusb_linux_tree.json").unwrap();
+    /// let json = spusb.to_json_string().unwrap();
+    /// let round_trip = SPUSBDataType::from_json(&json).unwrap();
+    /// assert_eq!(round_trip.to_json_string().unwrap(), json);
+    /// ```
+    pub fn to_json_string(&self) -> Result<String, io::Error> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
     /// Flattens entire data store by cloning the `buses`, flattening them and pushing into a new `Vec` and then assigning it to `buses`
     ///
     /// Requires clone of buses so not in place - maybe a more efficient method?
@@ -38,6 +72,8 @@ impl SPUSBDataType {
     }
 
     /// Returns a flattened Vec of references to all `USBDevice`s in each of the `buses`
+    ///
+    /// Buses are in the order stored, so any [`crate::display::BusSort`] applied is kept; see [`SPUSBDataType::iter_devices`] for an order independent of parsing
     pub fn flatten_devices<'a>(&'a self) -> Vec<&'a USBDevice> {
         let mut ret = Vec::new();
         for bus in &self.buses {
@@ -47,6 +83,29 @@ impl SPUSBDataType {
         ret
     }
 
+    /// Iterator of all `USBDevice`s in each of the `buses` along with their port path
+    ///
+    /// Order is deterministic regardless of the order the data was parsed in: buses by bus number, then a depth-first walk of each bus with siblings in order of `tree_positions` (branch position). A parent is always yielded before its children.
+    ///
+    /// ```
+    /// let spusb = cyme::system_profiler::read_json_dump("./tests/data/cyme_libusb_linux_tree.json").unwrap();
+    /// let paths: Vec<String> = spusb.iter_devices().map(|(p, _)| p).collect();
+    /// assert_eq!(paths.len(), spusb.flatten_devices().len());
+    /// ```
+    pub fn iter_devices(&self) -> DeviceIter<'_> {
+        let mut buses: Vec<&USBBus> = self.buses.iter().collect();
+        buses.sort_by_key(|b| b.get_bus_number());
+        let mut stack = Vec::new();
+        // stack so push in reverse to pop in order
+        for bus in buses.into_iter().rev() {
+            if let Some(devices) = &bus.devices {
+                stack.extend(DeviceIter::sorted_rev(devices));
+            }
+        }
+
+        DeviceIter { stack }
+    }
+
     /// Returns reference to [`USBBus`] `number` if it exists in data
     pub fn get_bus(&self, number: u8) -> Option<&USBBus> {
         self.buses.iter().find(|b| b.get_bus_number() == number)
@@ -97,6 +156,39 @@ impl fmt::Display for SPUSBDataType {
     }
 }
 
+/// Depth-first iterator of [`USBDevice`] references and their port path, see [`SPUSBDataType::iter_devices`]
+#[derive(Debug)]
+pub struct DeviceIter<'a> {
+    stack: Vec<&'a USBDevice>,
+}
+
+impl<'a> DeviceIter<'a> {
+    /// `devices` sorted by `tree_positions` in reverse, so that popping from the stack yields them in order
+    fn sorted_rev(devices: &'a Vec<USBDevice>) -> Vec<&'a USBDevice> {
+        let mut sorted: Vec<&USBDevice> = devices.iter().collect();
+        sorted.sort_by(|a, b| {
+            b.location_id
+                .tree_positions
+                .cmp(&a.location_id.tree_positions)
+                .then(b.location_id.number.cmp(&a.location_id.number))
+        });
+        sorted
+    }
+}
+
+impl<'a> Iterator for DeviceIter<'a> {
+    type Item = (String, &'a USBDevice);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let device = self.stack.pop()?;
+        if let Some(devices) = &device.devices {
+            self.stack.extend(DeviceIter::sorted_rev(devices));
+        }
+
+        Some((device.port_path(), device))
+    }
+}
+
 /// USB bus JSON returned from system_profiler but now used for other platforms
 #[skip_serializing_none]
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -118,6 +210,9 @@ pub struct USBBus {
     /// Number of bus on system
     #[serde(default, deserialize_with = "deserialize_option_number_from_string")]
     pub usb_bus_number: Option<u8>,
+    /// Host controller driver from udev on Linux only
+    #[serde(default)]
+    pub driver: Option<String>,
     /// `USBDevices` on the `USBBus`. Since a device can have devices too, need to walk all down all
     #[serde(rename(deserialize = "_items"), alias = "devices")]
     pub devices: Option<Vec<USBDevice>>,
@@ -207,6 +302,18 @@ impl USBBus {
         self.get_node_mut(&self.interface())
     }
 
+    /// USB version supported by the bus controller; the `bcd_usb` of the root_hub device so only available when it is present (Linux)
+    ///
+    /// ```
+    /// let spusb = cyme::system_profiler::read_json_dump("./tests/data/cyme_libusb_linux_tree.json").unwrap();
+    /// let bus = spusb.get_bus(4).unwrap();
+    /// assert_eq!(bus.usb_version(), bus.get_root_hub_device().unwrap().bcd_usb);
+    /// assert!(bus.usb_version().is_some());
+    /// ```
+    pub fn usb_version(&self) -> Option<Version> {
+        self.get_root_hub_device().and_then(|d| d.bcd_usb)
+    }
+
     /// Search for [`USBDevice`] in branches of bus and return reference
     pub fn get_node(&self, port_path: &str) -> Option<&USBDevice> {
         if let Some(devices) = self.devices.as_ref() {
@@ -585,6 +692,28 @@ impl<'de> Deserialize<'de> for DeviceLocation {
     }
 }
 
+/// Where [`USBDevice::name`] came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NameSource {
+    /// Product string descriptor
+    Descriptor,
+    /// usb_ids vid:pid product name lookup since the product string descriptor was empty
+    UsbIds,
+    /// Neither the descriptor nor usb_ids had a name
+    Unknown,
+}
+
+impl fmt::Display for NameSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NameSource::Descriptor => write!(f, "desc"),
+            NameSource::UsbIds => write!(f, "ids"),
+            NameSource::Unknown => write!(f, "-"),
+        }
+    }
+}
+
 /// Used for macOS system_profiler dump. Speed is a snake_case string and in case we can't match to a [`Speed`], this allows the String to be stored and not panic
 #[derive(Debug, Clone, PartialEq, DeserializeFromStr, SerializeDisplay)]
 pub enum DeviceSpeed {
@@ -638,6 +767,9 @@ pub struct USBDevice {
     /// The device product name as reported in descriptor or using usb_ids if None
     #[serde(rename(deserialize = "_name"), alias = "name")]
     pub name: String,
+    /// Where `name` came from; None if not recorded such as from system_profiler
+    #[serde(default)]
+    pub name_source: Option<NameSource>,
     #[serde(default, deserialize_with = "deserialize_option_number_from_string")]
     /// Unique vendor identifier - purchased from USB IF
     pub vendor_id: Option<u16>,
@@ -648,6 +780,9 @@ pub struct USBDevice {
     pub location_id: DeviceLocation,
     /// Device serial number as reported by descriptor
     pub serial_num: Option<String>,
+    /// Whether `serial_num` has been masked with [`crate::display::mask_serial`]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub serial_masked: bool,
     /// The device manufacturer as provided in descriptor or using usb_ids if None
     pub manufacturer: Option<String>,
     #[serde(default, serialize_with = "version_serializer", deserialize_with = "deserialize_option_version_from_string")]
@@ -691,6 +826,11 @@ impl USBDevice {
         }
     }
 
+    /// Is the device of `class` or does it have an interface with `class`
+    pub fn has_class(&self, c: &ClassCode) -> bool {
+        self.class.as_ref().map_or(false, |dc| dc == c) || self.has_interface_class(c)
+    }
+
     /// Does the device have an interface with `class`
     pub fn has_interface_class(&self, c: &ClassCode) -> bool {
         if let Some(extra) = self.extra.as_ref() {
@@ -702,6 +842,37 @@ impl USBDevice {
         }
     }
 
+    /// Recursively searches the device configurations for an interface endpoint with [`TransferType`] `t`
+    ///
+    /// Will always be false if the device has no `extra` data since the endpoints are unknown
+    pub fn has_endpoint_transfer_type(&self, t: &TransferType) -> bool {
+        if let Some(extra) = self.extra.as_ref() {
+            extra.configurations.iter().any(|conf| {
+                conf.interfaces
+                    .iter()
+                    .any(|i| i.endpoints.iter().any(|e| e.transfer_type == *t))
+            })
+        } else {
+            false
+        }
+    }
+
+    /// Whether the device `extra.driver` or any of its interface drivers are `driver`
+    ///
+    /// Will always be false if the device has no `extra` data since drivers come from udev
+    pub fn has_driver(&self, driver: &str) -> bool {
+        if let Some(extra) = self.extra.as_ref() {
+            extra.driver.as_deref() == Some(driver)
+                || extra.configurations.iter().any(|conf| {
+                    conf.interfaces
+                        .iter()
+                        .any(|i| i.driver.as_deref() == Some(driver))
+                })
+        } else {
+            false
+        }
+    }
+
     /// Gets root_hub [`USBDevice`] if it is one
     ///
     /// root_hub returns `Some(Self)`
@@ -833,6 +1004,24 @@ impl USBDevice {
         *self.location_id.tree_positions.last().unwrap_or(&0)
     }
 
+    /// Returns the physical port number on the immediate parent hub, the last number in `tree_positions`, or the device number if a root device without `tree_positions`
+    ///
+    /// ```
+    /// let d = cyme::system_profiler::USBDevice{ name: String::from("Test device"), location_id: cyme::system_profiler::DeviceLocation { bus: 1, number: 4, tree_positions: vec![1, 2, 3] }, ..Default::default() };
+    /// assert_eq!(d.get_port_number(), 3);
+    ///
+    /// // root device uses device number
+    /// let d = cyme::system_profiler::USBDevice{ name: String::from("root_hub"), location_id: cyme::system_profiler::DeviceLocation { bus: 1, number: 1, tree_positions: vec![] }, ..Default::default() };
+    /// assert_eq!(d.get_port_number(), 1);
+    /// ```
+    pub fn get_port_number(&self) -> u8 {
+        *self
+            .location_id
+            .tree_positions
+            .last()
+            .unwrap_or(&self.location_id.number)
+    }
+
     /// The number of [`USBDevice`] deep; branch depth
     pub fn get_depth(&self) -> usize {
         self.location_id.tree_positions.len()
@@ -1151,26 +1340,44 @@ impl fmt::Display for USBDevice {
 /// Used to filter devices within buses
 ///
 /// The tree to a [`USBDevice`] is kept even if parent branches are not matches. To avoid this, one must flatten the devices first.
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct USBFilter {
     /// Retain only devices with vendor id matching this
     pub vid: Option<u16>,
     /// Retain only devices with product id matching this
     pub pid: Option<u16>,
+    /// Retain only devices with a (vendor id, product id) in this set, see [`USBFilter::vidpid_list_from_file`]
+    pub vidpid_list: Option<HashSet<(u16, u16)>>,
     /// Retain only devices on this bus
     pub bus: Option<u8>,
     /// Retain only devices with this device number
     pub number: Option<u8>,
     /// Retain only devices with name.contains(name)
     pub name: Option<String>,
+    /// Retain only devices with name, manufacturer or usb_ids product name matching regex
+    ///
+    /// Matching is case-insensitive when built with [`USBFilter::name_regex_from_str`] (as the CLI `--filter-name-regex` does); a [`Regex`] assigned directly will match as compiled
+    #[serde(skip)]
+    pub name_regex: Option<Regex>,
     /// retain only devices with serial.contains(serial)
     pub serial: Option<String>,
     /// retain only device of ClassCode class
     pub class: Option<ClassCode>,
+    /// retain only devices with an endpoint of TransferType transfer_type
+    pub transfer_type: Option<TransferType>,
+    /// retain only devices bound to udev driver, either the device driver or any interface driver
+    pub driver: Option<String>,
+    /// remove devices of ClassCode class or with an interface of it; their devices are moved up to take their place in the tree
+    pub exclude_class: Option<ClassCode>,
     /// Exlcude empty hubs in the tree
     pub exclude_empty_hub: bool,
+    /// Exclude all hubs, empty or not; for lists where hubs are not connected to their devices
+    pub exclude_hubs: bool,
     /// Don't exclude Linux root_hub devices - this is inverse because they are pseudo [`USBBus`]'s in the tree
     pub no_exclude_root_hub: bool,
+    /// Retain only devices matching this [`Select`] expression, see [`crate::select`]
+    #[serde(skip)]
+    pub select: Option<Select>,
 }
 
 /// Filter devices with name
@@ -1229,6 +1436,24 @@ pub struct USBFilter {
 /// assert_eq!(flattened.first().unwrap().name, "Black Magic Probe  v1.8.2");
 /// ```
 ///
+/// Filter devices with case-insensitive regex on name, manufacturer or product name
+///
+/// ```
+/// use cyme::system_profiler::*;
+///
+/// # let mut spusb = read_json_dump(&"./tests/data/cyme_libusb_linux_tree.json").unwrap();
+/// let filter = USBFilter {
+///     name_regex: Some(USBFilter::name_regex_from_str("^black magic").unwrap()),
+///     ..Default::default()
+/// };
+/// let mut flattened = spusb.flatten_devices();
+/// filter.retain_flattened_devices_ref(&mut flattened);
+/// assert_eq!(flattened.len(), 1);
+/// assert_eq!(flattened.first().unwrap().name, "Black Magic Probe  v1.8.2");
+/// // invalid regex is an error not a panic
+/// assert_eq!(USBFilter::name_regex_from_str("black[magic").is_err(), true);
+/// ```
+///
 /// Filter devices with class
 ///
 /// ```
@@ -1246,57 +1471,274 @@ pub struct USBFilter {
 /// assert_eq!(device.unwrap().name, "Black Magic Probe  v1.8.2");
 /// ```
 ///
+/// Filter devices with an endpoint transfer type
+///
+/// ```
+/// use cyme::system_profiler::*;
+///
+/// # let mut spusb = read_json_dump(&"./tests/data/cyme_libusb_linux_tree.json").unwrap();
+/// let filter = USBFilter {
+///     transfer_type: Some(cyme::usb::TransferType::Bulk),
+///     ..Default::default()
+/// };
+/// let mut flattened = spusb.flatten_devices();
+/// filter.retain_flattened_devices_ref(&mut flattened);
+/// assert!(flattened.len() > 0);
+/// assert!(flattened
+///     .iter()
+///     .all(|d| d.has_endpoint_transfer_type(&cyme::usb::TransferType::Bulk)));
+/// ```
+///
+/// Filter devices bound to a udev driver
+///
+/// ```
+/// use cyme::system_profiler::*;
+///
+/// # let mut spusb = read_json_dump(&"./tests/data/cyme_libusb_linux_tree.json").unwrap();
+/// let filter = USBFilter {
+///     driver: Some(String::from("cdc_acm")),
+///     ..Default::default()
+/// };
+/// let mut flattened = spusb.flatten_devices();
+/// filter.retain_flattened_devices_ref(&mut flattened);
+/// // black magic probe and j-link
+/// assert_eq!(flattened.len(), 2);
+/// assert!(flattened.iter().all(|d| d.has_driver("cdc_acm")));
+/// ```
+///
 impl USBFilter {
     /// Creates a new filter with defaults
     pub fn new() -> Self {
         Default::default()
     }
 
+    /// Compiles `s` into a case-insensitive [`Regex`] for use with `name_regex`
+    ///
+    /// Returns an `io::Error` with the regex syntax error rather than panicking if `s` is not a valid regex
+    pub fn name_regex_from_str(s: &str) -> Result<Regex, io::Error> {
+        regex::RegexBuilder::new(s)
+            .case_insensitive(true)
+            .build()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+    }
+
+    /// Reads a set of vid:pid for `vidpid_list` from `reader`; each line is a hex 'vid:pid' like '1d50:6018', optionally '0x' prefixed
+    ///
+    /// Blank lines and those starting with '#' are ignored, as is anything after a '#' on a line. Returns an `io::Error` with the line number if a line cannot be parsed.
+    ///
+    /// ```
+    /// use cyme::system_profiler::USBFilter;
+    ///
+    /// let list = USBFilter::vidpid_list_from_reader("# probes\n1d50:6018\n\n0x1366:0x0105 # J-Link\n".as_bytes()).unwrap();
+    /// assert!(list.contains(&(0x1d50, 0x6018)));
+    /// assert!(list.contains(&(0x1366, 0x0105)));
+    /// assert_eq!(list.len(), 2);
+    /// assert!(USBFilter::vidpid_list_from_reader("1d50".as_bytes()).is_err());
+    /// ```
+    pub fn vidpid_list_from_reader<R: io::BufRead>(
+        reader: R,
+    ) -> Result<HashSet<(u16, u16)>, io::Error> {
+        let parse_hex = |s: &str| u16::from_str_radix(s.trim().trim_start_matches("0x"), 16);
+        let mut ret = HashSet::new();
+
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            let entry = line.split('#').next().unwrap_or("").trim();
+            if entry.is_empty() {
+                continue;
+            }
+            let vidpid = entry
+                .split_once(':')
+                .and_then(|(vid, pid)| parse_hex(vid).ok().zip(parse_hex(pid).ok()));
+            match vidpid {
+                Some(vp) => {
+                    ret.insert(vp);
+                }
+                None => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("Invalid vid:pid '{}' on line {}", entry, i + 1),
+                    ))
+                }
+            }
+        }
+
+        Ok(ret)
+    }
+
+    /// [`USBFilter::vidpid_list_from_reader`] reading the file at `file_path`
+    pub fn vidpid_list_from_file(file_path: &str) -> Result<HashSet<(u16, u16)>, io::Error> {
+        let file = fs::File::open(file_path)?;
+        USBFilter::vidpid_list_from_reader(io::BufReader::new(file))
+    }
+
+    /// Checks whether any of `device` name, manufacturer or usb_ids product name match `name_regex`; true if no `name_regex`
+    pub fn is_name_regex_match(&self, device: &USBDevice) -> bool {
+        self.name_regex.as_ref().map_or(true, |r| {
+            r.is_match(&device.name)
+                || device
+                    .manufacturer
+                    .as_ref()
+                    .map_or(false, |m| r.is_match(m))
+                || device
+                    .extra
+                    .as_ref()
+                    .and_then(|e| e.product_name.as_ref())
+                    .map_or(false, |p| r.is_match(p))
+        })
+    }
+
     /// Checks whether `device` passes through filter
     pub fn is_match(&self, device: &USBDevice) -> bool {
         (Some(device.location_id.bus) == self.bus || self.bus.is_none())
             && (Some(device.location_id.number) == self.number || self.number.is_none())
             && (device.vendor_id == self.vid || self.vid.is_none())
             && (device.product_id == self.pid || self.pid.is_none())
+            && (self.vidpid_list.as_ref().map_or(true, |l| {
+                match (device.vendor_id, device.product_id) {
+                    (Some(v), Some(p)) => l.contains(&(v, p)),
+                    _ => false,
+                }
+            }))
             && (self
                 .name
                 .as_ref()
                 .map_or(true, |n| device.name.contains(n.as_str())))
+            && self.is_name_regex_match(device)
             && (self.serial.as_ref().map_or(true, |n| {
                 device
                     .serial_num
                     .as_ref()
                     .map_or(false, |s| s.contains(n.as_str()))
             }))
-            && (self.class.as_ref().map_or(true, |fc| {
-                device
-                    .class
-                    .as_ref()
-                    .map_or(false, |c| c == fc) || device.has_interface_class(fc)
-            }))
+            && (self.class.as_ref().map_or(true, |fc| device.has_class(fc)))
+            && (self
+                .exclude_class
+                .as_ref()
+                .map_or(true, |ec| !device.has_class(ec)))
+            && (self
+                .transfer_type
+                .as_ref()
+                .map_or(true, |t| device.has_endpoint_transfer_type(t)))
+            && (self
+                .driver
+                .as_ref()
+                .map_or(true, |dr| device.has_driver(dr)))
+            && (self.select.as_ref().map_or(true, |s| s.is_match(device)))
             && !(self.exclude_empty_hub && device.is_hub() && !device.has_devices())
+            && !(self.exclude_hubs && device.is_hub())
         && (!device.is_root_hub() || self.no_exclude_root_hub)
     }
 
     /// Recursively retain only `USBBus` in `buses` with `USBDevice` matching filter
-    pub fn retain_buses(&self, buses: &mut Vec<USBBus>) -> () {
+    ///
+    /// Returns the [`USBDevice::port_path`] of each retained device that matched the filter itself, so that those only retained as parents of a match can be identified
+    ///
+    /// ```
+    /// use cyme::system_profiler::*;
+    ///
+    /// # let mut spusb = read_json_dump(&"./tests/data/system_profiler_dump.json").unwrap();
+    /// let filter = USBFilter {
+    ///     name: Some(String::from("Black Magic Probe")),
+    ///     ..Default::default()
+    /// };
+    /// let matched = filter.retain_buses(&mut spusb.buses);
+    /// // parent hub retained but not matched
+    /// assert_eq!(spusb.flatten_devices().len(), 2);
+    /// assert_eq!(matched.len(), 1);
+    /// assert!(matched.contains("20-3.3"));
+    /// ```
+    pub fn retain_buses(&self, buses: &mut Vec<USBBus>) -> HashSet<String> {
+        let mut matched = HashSet::new();
         buses.retain(|b| {
             b.usb_bus_number == self.bus || self.bus.is_none() || b.usb_bus_number.is_none()
         });
 
         for bus in buses {
-            bus.devices.as_mut().map_or((), |d| self.retain_devices(d));
+            bus.devices.as_mut().map_or((), |d| {
+                self.exclude_devices(d);
+                self.retain_devices_matched(d, &mut matched);
+                self.prune_empty_hubs(d);
+            });
         }
+
+        matched
     }
 
     /// Recursively retain only `USBDevice` in `devices` matching filter
     ///
     /// Note that non-matching parents will still be retained if they have a matching `USBDevice` within their branches
     pub fn retain_devices(&self, devices: &mut Vec<USBDevice>) -> () {
+        self.exclude_devices(devices);
+        self.retain_devices_matched(devices, &mut HashSet::new());
+        self.prune_empty_hubs(devices);
+    }
+
+    /// Recursively removes hubs in `devices` left without any non-hub descendants if `exclude_empty_hub`
+    ///
+    /// Children are pruned first so that a hub holding only empty hubs is itself removed
+    pub fn prune_empty_hubs(&self, devices: &mut Vec<USBDevice>) {
+        if !self.exclude_empty_hub {
+            return;
+        }
+
+        for d in devices.iter_mut() {
+            if let Some(children) = d.devices.as_mut() {
+                self.prune_empty_hubs(children);
+            }
+        }
+        devices.retain(|d| !(d.is_hub() && !d.has_devices()));
+    }
+
+    /// Recursively removes `USBDevice` in `devices` of `exclude_class`, re-parenting their devices in their place so that the tree still connects
+    ///
+    /// ```
+    /// use cyme::system_profiler::*;
+    /// use cyme::usb::ClassCode;
+    ///
+    /// # let mut spusb = read_json_dump(&"./tests/data/cyme_libusb_linux_tree.json").unwrap();
+    /// let filter = USBFilter {
+    ///     exclude_class: Some(ClassCode::Hub),
+    ///     ..Default::default()
+    /// };
+    /// filter.retain_buses(&mut spusb.buses);
+    /// // devices on the hub now attached to bus in its place
+    /// let names: Vec<&str> = spusb.buses[1].devices.as_ref().unwrap().iter().map(|d| d.name.as_str()).collect();
+    /// assert_eq!(names, vec!["Black Magic Probe  v1.8.2", "J-Link"]);
+    /// assert!(spusb.flatten_devices().iter().all(|d| !d.has_class(&ClassCode::Hub)));
+    /// ```
+    pub fn exclude_devices(&self, devices: &mut Vec<USBDevice>) {
+        let ec = match self.exclude_class.as_ref() {
+            Some(c) => c,
+            None => return,
+        };
+
+        let mut ret = Vec::with_capacity(devices.len());
+        for mut d in devices.drain(..) {
+            if let Some(children) = d.devices.as_mut() {
+                self.exclude_devices(children);
+            }
+            if d.has_class(ec) {
+                log::debug!("Excluding {} of class {:?}", d, ec);
+                ret.extend(d.devices.take().unwrap_or_default());
+            } else {
+                ret.push(d);
+            }
+        }
+        *devices = ret;
+    }
+
+    /// As [`USBFilter::retain_devices`] but inserts the port path of each device which matched filter itself into `matched`
+    fn retain_devices_matched(&self, devices: &mut Vec<USBDevice>, matched: &mut HashSet<String>) {
         devices.retain(|d| self.exists_in_tree(d));
 
         for d in devices {
-            d.devices.as_mut().map_or((), |d| self.retain_devices(d));
+            if self.is_match(d) {
+                matched.insert(d.port_path());
+            }
+            d.devices
+                .as_mut()
+                .map_or((), |d| self.retain_devices_matched(d, matched));
         }
     }
 
@@ -1327,15 +1769,19 @@ impl USBFilter {
 ///
 /// Must be a full tree including buses
 pub fn read_json_dump(file_path: &str) -> Result<SPUSBDataType, io::Error> {
-    let mut file = fs::File::options().read(true).open(file_path)?;
+    let file = fs::File::options().read(true).open(file_path)?;
 
-    let mut data = String::new();
-    file.read_to_string(&mut data)?;
+    read_json_dump_from_reader(file)
+}
 
-    let json_dump: SPUSBDataType =
-        serde_json::from_str(&data).map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+/// Reads a json dump from `reader`, such as stdin, with serde deserializer - either from `system_profiler` or from `cyme --json`
+///
+/// Must be a full tree including buses. Invalid json is returned as an `InvalidData` error
+pub fn read_json_dump_from_reader<R: Read>(mut reader: R) -> Result<SPUSBDataType, io::Error> {
+    let mut data = String::new();
+    reader.read_to_string(&mut data)?;
 
-    Ok(json_dump)
+    SPUSBDataType::from_json(&data)
 }
 
 /// Runs the system_profiler command for SPUSBDataType and parses the json stdout into a [`SPUSBDataType`]
@@ -1503,6 +1949,49 @@ where
 mod tests {
     use super::*;
 
+    fn reverse_devices(devices: &mut Vec<USBDevice>) {
+        devices.reverse();
+        for d in devices.iter_mut() {
+            if let Some(children) = d.devices.as_mut() {
+                reverse_devices(children);
+            }
+        }
+    }
+
+    #[test]
+    fn test_iter_devices_parse_order() {
+        let spusb = read_json_dump("./tests/data/cyme_libusb_linux_tree.json").unwrap();
+        let mut reversed = read_json_dump("./tests/data/cyme_libusb_linux_tree.json").unwrap();
+        reversed.buses.reverse();
+        for bus in reversed.buses.iter_mut() {
+            if let Some(devices) = bus.devices.as_mut() {
+                reverse_devices(devices);
+            }
+        }
+
+        let expected: Vec<(String, String)> = spusb
+            .iter_devices()
+            .map(|(p, d)| (p, d.name.to_owned()))
+            .collect();
+        let got: Vec<(String, String)> = reversed
+            .iter_devices()
+            .map(|(p, d)| (p, d.name.to_owned()))
+            .collect();
+        assert_eq!(expected.len(), 9);
+        assert_eq!(expected, got);
+        assert_eq!(expected.len(), spusb.flatten_devices().len());
+    }
+
+    #[test]
+    fn test_read_json_dump_from_reader() {
+        let data = fs::read("./tests/data/cyme_libusb_linux_tree.json").unwrap();
+        let spusb = read_json_dump_from_reader(data.as_slice()).unwrap();
+        assert_eq!(spusb.buses.len(), 4);
+
+        let err = read_json_dump_from_reader("{\"buses\": [".as_bytes()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
     #[test]
     fn test_deserialize_device() {
         let device_json = "{
@@ -1568,4 +2057,81 @@ mod tests {
     fn test_json_dump_read_not_panic() {
         read_json_dump(&"./tests/data/system_profiler_dump.json").unwrap();
     }
+
+    #[test]
+    fn test_json_round_trip() {
+        for dump in [
+            "./tests/data/cyme_libusb_linux_tree.json",
+            "./tests/data/system_profiler_dump.json",
+        ] {
+            let spusb = read_json_dump(dump).unwrap();
+            let json = spusb.to_json_string().unwrap();
+            let round_trip = SPUSBDataType::from_json(&json).unwrap();
+            assert_eq!(round_trip.to_json_string().unwrap(), json);
+            assert_eq!(
+                round_trip.flatten_devices().len(),
+                spusb.flatten_devices().len()
+            );
+        }
+    }
+
+    #[test]
+    fn test_exclude_empty_hub_recursive() {
+        let hub = |port: u8, devices: Vec<USBDevice>| USBDevice {
+            name: String::from("Test Hub"),
+            class: Some(ClassCode::Hub),
+            location_id: DeviceLocation {
+                bus: 1,
+                number: port,
+                tree_positions: vec![port],
+            },
+            devices: Some(devices),
+            ..Default::default()
+        };
+        let probe = USBDevice {
+            name: String::from("Probe"),
+            location_id: DeviceLocation {
+                bus: 1,
+                number: 9,
+                tree_positions: vec![3, 1],
+            },
+            ..Default::default()
+        };
+        let mut devices = vec![
+            // hub of empty hubs of an empty hub
+            hub(1, vec![hub(2, vec![hub(4, vec![])]), hub(5, vec![])]),
+            // hub with a device and an empty hub
+            hub(3, vec![probe, hub(6, vec![])]),
+        ];
+
+        let filter = USBFilter {
+            exclude_empty_hub: true,
+            no_exclude_root_hub: true,
+            ..Default::default()
+        };
+        filter.retain_devices(&mut devices);
+
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].location_id.number, 3);
+        let children = devices[0].devices.as_ref().unwrap();
+        assert_eq!(children.len(), 1);
+        assert_eq!(children[0].name, "Probe");
+    }
+
+    #[test]
+    fn test_filter_vidpid_list() {
+        let mut spusb = read_json_dump("./tests/data/cyme_libusb_linux_tree.json").unwrap();
+        let list = USBFilter::vidpid_list_from_reader("1d50:6018\n1366:1050\n".as_bytes()).unwrap();
+        let filter = USBFilter {
+            vidpid_list: Some(list.clone()),
+            ..Default::default()
+        };
+        spusb.flatten();
+        filter.retain_buses(&mut spusb.buses);
+        let devices = spusb.flatten_devices();
+        assert_eq!(devices.len(), 2);
+        assert!(devices
+            .iter()
+            .all(|d| list.contains(&(d.vendor_id.unwrap(), d.product_id.unwrap()))));
+    }
 }
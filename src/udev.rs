@@ -1,6 +1,8 @@
 //! Utilities to get device information using udev - only supported on Linux. Requires 'udev' feature.
 use std::error::Error;
+use std::fs;
 use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
 use udev as udevlib;
 
 /// Get and assign `driver_ref` the driver and `syspath_ref` the syspath for device at the `port_path`
@@ -34,6 +36,118 @@ pub fn get_udev_info(
     Ok(())
 }
 
+/// Get the Unix timestamp in seconds when the device at `port_path` was initialized by udev; when it was connected or the system booted if present since
+///
+/// udev reports `USEC_INITIALIZED` as `CLOCK_MONOTONIC` time since boot so it is converted using the same clock now. Ok(None) if udev does not have the property
+///
+/// ```no_run
+/// use cyme::udev::get_udev_connected_since;
+///
+/// let since = get_udev_connected_since(&String::from("1-0:1.0")).unwrap();
+/// assert!(since.is_some());
+/// ```
+pub fn get_udev_connected_since(port_path: &String) -> Result<Option<u64>, Box<dyn Error>> {
+    let path: String = format!("/sys/bus/usb/devices/{}", port_path);
+    let device = udevlib::Device::from_syspath(&Path::new(&path))?;
+    let initialized = match device
+        .property_value("USEC_INITIALIZED")
+        .and_then(|v| v.to_str())
+        .and_then(|v| v.parse::<u64>().ok())
+    {
+        Some(v) => v / 1_000_000,
+        None => return Ok(None),
+    };
+    let uptime = monotonic_secs()?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    log::debug!("Got device initialized {}s after boot", initialized);
+
+    Ok(Some(now.saturating_sub(uptime.saturating_sub(initialized))))
+}
+
+/// Seconds of `CLOCK_MONOTONIC`, the clock udev uses for `USEC_INITIALIZED`; unlike /proc/uptime it does not include time suspended
+fn monotonic_secs() -> Result<u64, Box<dyn Error>> {
+    let mut ts = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    // SAFETY: `ts` is a valid timespec for clock_gettime to write to
+    if unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut ts) } != 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+
+    Ok(ts.tv_sec as u64)
+}
+
+/// Get the driver of the host controller for `bus` like 'xhci_hcd'; the driver of the parent of the bus root_hub
+///
+/// ```no_run
+/// use cyme::udev::get_udev_controller_driver;
+///
+/// let driver = get_udev_controller_driver(1).unwrap();
+/// assert!(driver.is_some());
+/// ```
+pub fn get_udev_controller_driver(bus: u8) -> Result<Option<String>, Box<dyn Error>> {
+    let path: String = format!("/sys/bus/usb/devices/usb{}", bus);
+    let device = udevlib::Device::from_syspath(&Path::new(&path))?;
+    let driver = device
+        .parent()
+        .and_then(|p| p.driver().map(|s| s.to_str().unwrap_or("").to_string()));
+    log::debug!("Got bus {} controller driver {:?}", bus, driver);
+
+    Ok(driver)
+}
+
+/// Get the udev tags of the device at `port_path` like 'uaccess' and 'seat'
+///
+/// udev reports tags as the ':' separated `TAGS` property such as ':seat:uaccess:'. Ok(None) if udev does not have the property
+///
+/// ```no_run
+/// use cyme::udev::get_udev_tags;
+///
+/// let tags = get_udev_tags(&String::from("1-0:1.0")).unwrap();
+/// assert!(tags.is_some());
+/// ```
+pub fn get_udev_tags(port_path: &String) -> Result<Option<Vec<String>>, Box<dyn Error>> {
+    let path: String = format!("/sys/bus/usb/devices/{}", port_path);
+    let device = udevlib::Device::from_syspath(&Path::new(&path))?;
+    let tags = device
+        .property_value("TAGS")
+        .and_then(|v| v.to_str())
+        .map(|v| {
+            v.split(':')
+                .filter(|t| !t.is_empty())
+                .map(String::from)
+                .collect::<Vec<String>>()
+        });
+    log::debug!("Got device tags {:?}", tags);
+
+    Ok(tags)
+}
+
+/// Get the runtime power management status of the device at `port_path` from sysfs `power/runtime_status`
+///
+/// The status is returned as is, such as 'active' or 'suspended'. It is not the link state, an 'active' device may be in a low power link state like U1/U2 or L1. Ok(None) if runtime power management is not exposed
+///
+/// ```no_run
+/// use cyme::udev::get_sysfs_power_state;
+///
+/// let state = get_sysfs_power_state(&String::from("1-0:1.0")).unwrap();
+/// assert!(state.is_some());
+/// ```
+pub fn get_sysfs_power_state(port_path: &String) -> Result<Option<String>, Box<dyn Error>> {
+    let path = Path::new("/sys/bus/usb/devices")
+        .join(port_path)
+        .join("power/runtime_status");
+    match fs::read_to_string(path) {
+        Ok(s) => {
+            log::debug!("Got device runtime_status {}", s.trim());
+            Ok(Some(s.trim().to_string()))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -6,6 +6,7 @@
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
+use std::cmp;
 use std::fmt;
 use std::str::FromStr;
 use std::io;
@@ -53,6 +54,14 @@ impl Version {
         Version(major, minor, sub_minor)
     }
 
+    /// Friendly marketing style category for a bcdUSB version like "USB 3.2 Gen 2"; None if not in lookup
+    pub fn usb_category(self) -> Option<&'static str> {
+        USB_VERSION_CATEGORIES
+            .iter()
+            .find(|(v, _)| *v == (self.major(), self.minor()))
+            .map(|(_, c)| *c)
+    }
+
     /// Returns the major version.
     pub fn major(self) -> u8 {
         let Version(major, _, _) = self;
@@ -72,6 +81,18 @@ impl Version {
     }
 }
 
+/// Lookup of bcdUSB (major, minor) to marketing category used by [`Version::usb_category`]
+const USB_VERSION_CATEGORIES: [((u8, u8), &'static str); 8] = [
+    ((1, 0), "USB 1.0"),
+    ((1, 1), "USB 1.1"),
+    ((2, 0), "USB 2.0"),
+    ((2, 1), "USB 2.0 LPM"),
+    ((3, 0), "USB 3.2 Gen 1"),
+    ((3, 1), "USB 3.2 Gen 2"),
+    ((3, 2), "USB 3.2 Gen 2x2"),
+    ((4, 0), "USB4"),
+];
+
 impl std::fmt::Display for Version {
     /// Output is a base16 encoding of Major.MinorSub
     ///
@@ -317,6 +338,42 @@ impl ClassCode {
 
     }
 
+    /// Full descriptive name of the class as listed in usb.ids
+    /// ```
+    /// # use cyme::usb::ClassCode;
+    ///
+    /// assert_eq!(ClassCode::HID.to_descriptive_string(), "Human Interface Device");
+    /// assert_eq!(ClassCode::SmartCart.to_descriptive_string(), "Chip/SmartCard");
+    /// ```
+    pub fn to_descriptive_string(&self) -> String {
+        match self {
+            ClassCode::UseInterfaceDescriptor => "(Defined at Interface level)",
+            ClassCode::Audio => "Audio",
+            ClassCode::CDCCommunications => "Communications",
+            ClassCode::HID => "Human Interface Device",
+            ClassCode::Physical => "Physical Interface Device",
+            ClassCode::Image => "Imaging",
+            ClassCode::Printer => "Printer",
+            ClassCode::MassStorage => "Mass Storage",
+            ClassCode::Hub => "Hub",
+            ClassCode::CDCData => "CDC Data",
+            ClassCode::SmartCart => "Chip/SmartCard",
+            ClassCode::ContentSecurity => "Content Security",
+            ClassCode::Video => "Video",
+            ClassCode::PersonalHealthcare => "Personal Healthcare",
+            ClassCode::AudioVideo => "Audio/Video Devices",
+            ClassCode::Billboard => "Billboard Device",
+            ClassCode::USBTypeCBridge => "USB Type-C Bridge",
+            ClassCode::I3CDevice => "I3C Device",
+            ClassCode::Diagnostic => "Diagnostic",
+            ClassCode::WirelessController => "Wireless",
+            ClassCode::Miscellaneous => "Miscellaneous Device",
+            ClassCode::ApplicationSpecificInterface => "Application Specific Interface",
+            ClassCode::VendorSpecificClass => "Vendor Specific Class",
+        }
+        .into()
+    }
+
     /// Converts Pascal case enum to space separated on capitals
     /// ```
     /// # use cyme::usb::ClassCode;
@@ -397,8 +454,8 @@ impl fmt::Display for Speed {
                 Speed::SuperSpeed => "super_speed",
                 Speed::HighSpeed | Speed::HighBandwidth => "high_speed",
                 Speed::FullSpeed => "full_speed",
+                Speed::LowSpeed => "low_speed",
                 Speed::Unknown => "unknown",
-                _ => todo!("Unsupported speed"),
             }
         )
     }
@@ -442,6 +499,40 @@ impl From<&Speed> for NumericalUnit<f32> {
 }
 
 impl Speed {
+    /// Theoretical maximum [`Speed`] implied by a device `bcd_usb` [`Version`]; None if it can't be derived
+    ///
+    /// ```
+    /// # use cyme::usb::{Speed, Version};
+    ///
+    /// assert_eq!(Speed::max_for_version(&Version::from_bcd(0x0320)), Some(Speed::SuperSpeedPlus));
+    /// assert_eq!(Speed::max_for_version(&Version::from_bcd(0x0300)), Some(Speed::SuperSpeed));
+    /// assert_eq!(Speed::max_for_version(&Version::from_bcd(0x0210)), Some(Speed::HighSpeed));
+    /// assert_eq!(Speed::max_for_version(&Version::from_bcd(0x0110)), Some(Speed::FullSpeed));
+    /// assert_eq!(Speed::max_for_version(&Version(0, 0, 0)), None);
+    /// ```
+    pub fn max_for_version(version: &Version) -> Option<Speed> {
+        match (version.major(), version.minor()) {
+            (3, 0) => Some(Speed::SuperSpeed),
+            (3.., _) => Some(Speed::SuperSpeedPlus),
+            (2, _) => Some(Speed::HighSpeed),
+            (1, _) => Some(Speed::FullSpeed),
+            _ => None,
+        }
+    }
+
+    /// Short speed without the '/s' suffix, such as "480Mb" or "5Gb"
+    ///
+    /// ```
+    /// # use cyme::usb::Speed;
+    ///
+    /// assert_eq!(Speed::SuperSpeed.to_short_string(), "5Gb");
+    /// assert_eq!(Speed::LowSpeed.to_short_string(), "1.5Mb");
+    /// ```
+    pub fn to_short_string(&self) -> String {
+        let dv = NumericalUnit::<f32>::from(self);
+        format!("{}{}", dv.value, dv.unit.trim_end_matches("/s"))
+    }
+
     /// lsusb speed is always in Mb/s and shown just a M prefix
     ///
     /// ```
@@ -477,7 +568,7 @@ impl fmt::Display for Direction {
 }
 
 /// Transfer type  for [`USBEndpoint`]
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, ValueEnum, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TransferType {
     /// Control endpoint.
     Control,
@@ -559,6 +650,57 @@ pub struct USBEndpoint {
     pub max_packet_size: u16,
     /// Interval for polling endpoint data transfers. Value in frame counts. Ignored for Bulk & Control Endpoints. Isochronous must equal 1 and field may range from 1 to 255 for interrupt endpoints.
     pub interval: u8,
+    /// Raw endpoint descriptor bytes including any class specific descriptors following it; only captured with libusb
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw: Option<Vec<u8>>,
+    /// Speed of the device the endpoint is on, which sets the unit of `interval`; only set when profiling with libusb
+    #[serde(skip)]
+    pub device_speed: Option<Speed>,
+    /// SuperSpeed endpoint companion descriptor following the endpoint descriptor of USB 3 devices; only with libusb
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub companion: Option<EndpointCompanion>,
+}
+
+/// SuperSpeed Endpoint Companion Descriptor which follows each endpoint descriptor of a USB 3 device
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EndpointCompanion {
+    /// Number of additional packets the endpoint can send or receive as part of a burst, bMaxBurst 0..15
+    pub max_burst: u8,
+    /// Max streams for bulk or Mult for isochronous endpoints, bmAttributes
+    pub attributes: u8,
+    /// Total bytes transferred every service interval for periodic endpoints, wBytesPerInterval
+    pub bytes_per_interval: u16,
+}
+
+impl EndpointCompanion {
+    /// Parses a raw SuperSpeed Endpoint Companion Descriptor `desc`; None if it is not one or is too short
+    ///
+    /// ```
+    /// use cyme::usb::EndpointCompanion;
+    ///
+    /// let companion = EndpointCompanion::from_descriptor(&[0x06, 0x30, 0x0f, 0x00, 0x00, 0x04]).unwrap();
+    /// assert_eq!(companion.max_burst, 15);
+    /// assert_eq!(companion.bytes_per_interval, 1024);
+    /// assert_eq!(EndpointCompanion::from_descriptor(&[0x07, 0x05, 0x81, 0x02, 0x00, 0x04, 0x00]), None);
+    /// ```
+    pub fn from_descriptor(desc: &[u8]) -> Option<Self> {
+        if desc.len() < 6 || desc[1] != DT_SS_ENDPOINT_COMPANION {
+            return None;
+        }
+
+        Some(EndpointCompanion {
+            max_burst: desc[2],
+            attributes: desc[3],
+            bytes_per_interval: u16::from_le_bytes([desc[4], desc[5]]),
+        })
+    }
+
+    /// First companion descriptor in concatenated descriptors `raw`, such as the extra bytes following an endpoint descriptor
+    pub fn from_descriptors(raw: &[u8]) -> Option<Self> {
+        raw_descriptors(raw)
+            .into_iter()
+            .find_map(EndpointCompanion::from_descriptor)
+    }
 }
 
 impl USBEndpoint {
@@ -578,6 +720,9 @@ impl USBEndpoint {
     ///     usage_type: UsageType::Data,
     ///     max_packet_size: 0xfff1,
     ///     interval: 3,
+    ///     raw: None,
+    ///     device_speed: None,
+    ///     companion: None,
     /// };
     /// assert_eq!(ep.max_packet_string(), "4x 2033");
     /// ep.max_packet_size = 0x0064;
@@ -590,6 +735,171 @@ impl USBEndpoint {
             self.max_packet_size & 0x7ff
         )
     }
+
+    /// [`USBEndpoint::max_packet_string`] with the number of bytes in binary units (B/KiB)
+    ///
+    /// ```
+    /// # use cyme::usb::*;
+    ///
+    /// let mut ep = USBEndpoint {
+    ///     address: EndpointAddress {
+    ///         address: 0,
+    ///         number: 0,
+    ///         direction: Direction::In
+    ///     },
+    ///     transfer_type: TransferType::Isochronous,
+    ///     sync_type: SyncType::None,
+    ///     usage_type: UsageType::Data,
+    ///     max_packet_size: 0xfff1,
+    ///     interval: 1,
+    ///     raw: None,
+    ///     device_speed: None,
+    ///     companion: None,
+    /// };
+    /// assert_eq!(ep.max_packet_human_string(), "4x 1.99 KiB");
+    /// ep.max_packet_size = 0x0400;
+    /// assert_eq!(ep.max_packet_human_string(), "1x 1.00 KiB");
+    /// ep.max_packet_size = 0x0064;
+    /// assert_eq!(ep.max_packet_human_string(), "1x 100 B");
+    /// ```
+    pub fn max_packet_human_string(&self) -> String {
+        let bytes = self.max_packet_size & 0x7ff;
+        let size = if bytes >= 1024 {
+            format!("{:.2} KiB", bytes as f32 / 1024.0)
+        } else {
+            format!("{} B", bytes)
+        };
+        format!("{}x {}", ((self.max_packet_size >> 11) & 3) + 1, size)
+    }
+
+    /// Estimated bytes per ms the endpoint can transfer: packet size × transactions per interval over the polling period from [`USBEndpoint::interval_us`] with `device_speed`; `None` for Bulk and Control endpoints or if the device speed is unknown
+    ///
+    /// ```
+    /// # use cyme::usb::*;
+    ///
+    /// let mut ep = USBEndpoint {
+    ///     address: EndpointAddress {
+    ///         address: 0x81,
+    ///         number: 1,
+    ///         direction: Direction::In
+    ///     },
+    ///     transfer_type: TransferType::Isochronous,
+    ///     sync_type: SyncType::Asynchronous,
+    ///     usage_type: UsageType::Data,
+    ///     max_packet_size: 0x1400,
+    ///     interval: 1,
+    ///     raw: None,
+    ///     device_speed: Some(Speed::HighSpeed),
+    ///     companion: None,
+    /// };
+    /// // 3072 bytes every 125 µs microframe
+    /// assert_eq!(ep.bytes_per_interval(), Some(24576));
+    /// ep.interval = 4;
+    /// assert_eq!(ep.bytes_per_interval(), Some(3072));
+    /// ep.device_speed = None;
+    /// assert_eq!(ep.bytes_per_interval(), None);
+    /// ep.device_speed = Some(Speed::HighSpeed);
+    /// ep.transfer_type = TransferType::Bulk;
+    /// assert_eq!(ep.bytes_per_interval(), None);
+    /// ```
+    pub fn bytes_per_interval(&self) -> Option<u32> {
+        let interval_us = self.interval_us(self.device_speed.as_ref()?)?;
+        let transactions = (((self.max_packet_size >> 11) & 3) + 1) as u64;
+        let size = (self.max_packet_size & 0x7ff) as u64;
+        Some((size * transactions * 1000 / interval_us as u64) as u32)
+    }
+
+    /// Polling interval in microseconds decoded from `interval` for the device `speed`; `None` for bulk and control endpoints or if speed is unknown
+    ///
+    /// High speed and faster count 125 µs microframes as `2^(interval-1)`. Full and low speed count 1 ms frames, linearly for interrupt and as `2^(interval-1)` for isochronous.
+    ///
+    /// ```
+    /// # use cyme::usb::*;
+    ///
+    /// let mut ep = USBEndpoint {
+    ///     address: EndpointAddress {
+    ///         address: 0x81,
+    ///         number: 1,
+    ///         direction: Direction::In
+    ///     },
+    ///     transfer_type: TransferType::Interrupt,
+    ///     sync_type: SyncType::None,
+    ///     usage_type: UsageType::Data,
+    ///     max_packet_size: 0x0040,
+    ///     interval: 4,
+    ///     raw: None,
+    ///     device_speed: None,
+    ///     companion: None,
+    /// };
+    /// assert_eq!(ep.interval_us(&Speed::HighSpeed), Some(1000));
+    /// assert_eq!(ep.interval_us(&Speed::FullSpeed), Some(4000));
+    /// ep.transfer_type = TransferType::Isochronous;
+    /// assert_eq!(ep.interval_us(&Speed::FullSpeed), Some(8000));
+    /// ep.transfer_type = TransferType::Bulk;
+    /// assert_eq!(ep.interval_us(&Speed::HighSpeed), None);
+    /// ```
+    pub fn interval_us(&self, speed: &Speed) -> Option<u32> {
+        if self.interval == 0 {
+            return None;
+        }
+        // exponent is limited to 1..16 by the spec
+        let exponential = 1u32 << (cmp::min(self.interval, 16) - 1);
+
+        match (&self.transfer_type, speed) {
+            (TransferType::Bulk | TransferType::Control, _) | (_, Speed::Unknown) => None,
+            (
+                _,
+                Speed::HighSpeed | Speed::HighBandwidth | Speed::SuperSpeed | Speed::SuperSpeedPlus,
+            ) => Some(125 * exponential),
+            (TransferType::Interrupt, _) => Some(1000 * self.interval as u32),
+            (TransferType::Isochronous, _) => Some(1000 * exponential),
+        }
+    }
+
+    /// Convert decoded transfer, sync and usage types back to bmAttributes reg value
+    ///
+    /// ```
+    /// # use cyme::usb::*;
+    ///
+    /// let ep = USBEndpoint {
+    ///     address: EndpointAddress {
+    ///         address: 0x81,
+    ///         number: 1,
+    ///         direction: Direction::In
+    ///     },
+    ///     transfer_type: TransferType::Isochronous,
+    ///     sync_type: SyncType::Adaptive,
+    ///     usage_type: UsageType::Feedback,
+    ///     max_packet_size: 0x0064,
+    ///     interval: 1,
+    ///     raw: None,
+    ///     device_speed: None,
+    ///     companion: None,
+    /// };
+    /// assert_eq!(ep.attributes_value(), 0x19);
+    /// ```
+    pub fn attributes_value(&self) -> u8 {
+        let transfer_type: u8 = match self.transfer_type {
+            TransferType::Control => 0,
+            TransferType::Isochronous => 1,
+            TransferType::Bulk => 2,
+            TransferType::Interrupt => 3,
+        };
+        let sync_type: u8 = match self.sync_type {
+            SyncType::None => 0,
+            SyncType::Asynchronous => 1,
+            SyncType::Adaptive => 2,
+            SyncType::Synchronous => 3,
+        };
+        let usage_type: u8 = match self.usage_type {
+            UsageType::Data => 0,
+            UsageType::Feedback => 1,
+            UsageType::FeedbackData => 2,
+            UsageType::Reserved => 3,
+        };
+
+        transfer_type | (sync_type << 2) | (usage_type << 4)
+    }
 }
 
 /// Interface within a [`USBConfiguration`]
@@ -618,6 +928,15 @@ pub struct USBInterface {
     pub syspath: Option<String>,
     /// An interface can have many endpoints
     pub endpoints: Vec<USBEndpoint>,
+    /// Raw interface descriptor bytes including any class specific descriptors following it; only captured with libusb
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw: Option<Vec<u8>>,
+    /// Interface Association Descriptor of the function the interface is part of, if any; only captured with libusb
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub association: Option<InterfaceAssociation>,
+    /// Number of alternate settings of the interface `number` in the parent [`USBConfiguration`] including this one; set from the sibling interfaces when the configuration is built or read, see [`set_num_alt_settings`]
+    #[serde(skip)]
+    pub num_alt_settings: usize,
 }
 
 impl USBInterface {
@@ -625,6 +944,56 @@ impl USBInterface {
     pub fn path(&self, bus: u8, ports: &Vec<u8>, config: u8) -> String {
         get_interface_path(bus, ports, config, self.number)
     }
+
+    /// Function class and first interface number of the [`InterfaceAssociation`] the interface is part of such as "Video/0"; "-" if not part of one
+    pub fn association_id(&self) -> String {
+        self.association.as_ref().map_or(String::from("-"), |a| {
+            format!("{}/{}", a.function_class, a.first_interface)
+        })
+    }
+
+    /// Compact count of endpoints by [`Direction`] such as "2IN 1OUT"; directions without endpoints are omitted and "-" if there are no endpoints
+    pub fn endpoint_summary(&self) -> String {
+        let ins = self
+            .endpoints
+            .iter()
+            .filter(|e| e.address.direction == Direction::In)
+            .count();
+        let outs = self.endpoints.len() - ins;
+        let mut ret = Vec::new();
+        if ins > 0 {
+            ret.push(format!("{}IN", ins));
+        }
+        if outs > 0 {
+            ret.push(format!("{}OUT", outs));
+        }
+        if ret.is_empty() {
+            "-".into()
+        } else {
+            ret.join(" ")
+        }
+    }
+}
+
+/// Sets [`USBInterface::num_alt_settings`] of each of `interfaces` to the number of `interfaces` sharing its interface number
+pub fn set_num_alt_settings(interfaces: &mut Vec<USBInterface>) {
+    let counts: Vec<usize> = interfaces
+        .iter()
+        .map(|i| interfaces.iter().filter(|s| s.number == i.number).count())
+        .collect();
+    for (interface, count) in interfaces.iter_mut().zip(counts) {
+        interface.num_alt_settings = count;
+    }
+}
+
+/// Deserializes [`USBConfiguration::interfaces`] then sets their [`USBInterface::num_alt_settings`] since it is not serialized
+fn deserialize_interfaces<'de, D>(deserializer: D) -> Result<Vec<USBInterface>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let mut interfaces = Vec::<USBInterface>::deserialize(deserializer)?;
+    set_num_alt_settings(&mut interfaces);
+    Ok(interfaces)
 }
 
 /// Devices can have multiple configurations, each with different attributes and interfaces
@@ -638,11 +1007,18 @@ pub struct USBConfiguration {
     /// Number of config, bConfigurationValue; value to set to enable to configuration
     pub number: u8,
     /// Interfaces available for this configuruation
+    #[serde(deserialize_with = "deserialize_interfaces")]
     pub interfaces: Vec<USBInterface>,
     /// Attributes of configuration, bmAttributes - was a HashSet since attributes should be unique but caused issues printing out of order
     pub attributes: Vec<ConfigAttributes>,
     /// Maximum power consumption in mA
     pub max_power: NumericalUnit<u32>,
+    /// Whether this is the currently active configuration of the device
+    #[serde(default)]
+    pub active: bool,
+    /// Raw configuration descriptor bytes up to the first interface descriptor; only captured with libusb
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw: Option<Vec<u8>>,
 }
 
 impl USBConfiguration {
@@ -663,11 +1039,192 @@ impl USBConfiguration {
 
         ret
     }
+
+    /// wTotalLength of the configuration: the size in bytes of the full configuration descriptor including all interface, endpoint and class specific descriptors; only available when `raw` was captured
+    pub fn total_length(&self) -> Option<u16> {
+        self.raw
+            .as_ref()
+            .filter(|r| r.len() >= 4)
+            .map(|r| u16::from_le_bytes([r[2], r[3]]))
+    }
+
+    /// Splits the full configuration descriptor `raw` as returned by GET_DESCRIPTOR into the `raw` of this configuration, its interfaces and their endpoints
+    ///
+    /// Interfaces are matched by number and alternate setting, endpoints by order within the interface. Class specific descriptors are kept with the standard descriptor they follow. Interfaces within an Interface Association Descriptor also get its `association`
+    pub fn set_raw_descriptors(&mut self, raw: &[u8]) {
+        for iad in raw_descriptors(raw)
+            .into_iter()
+            .filter_map(InterfaceAssociation::from_descriptor)
+        {
+            for i in self
+                .interfaces
+                .iter_mut()
+                .filter(|i| iad.contains(i.number))
+            {
+                i.association = Some(iad.clone());
+            }
+        }
+
+        let mut descriptors = split_descriptors(raw).into_iter();
+        self.raw = descriptors.next();
+        let mut interface: Option<&mut USBInterface> = None;
+        let mut endpoint_index = 0;
+
+        for desc in descriptors {
+            match desc[1] {
+                DT_INTERFACE if desc.len() >= 4 => {
+                    interface = self
+                        .interfaces
+                        .iter_mut()
+                        .find(|i| i.number == desc[2] && i.alt_setting == desc[3]);
+                    endpoint_index = 0;
+                    if let Some(i) = interface.as_mut() {
+                        i.raw = Some(desc);
+                    }
+                }
+                DT_ENDPOINT => {
+                    if let Some(e) = interface
+                        .as_mut()
+                        .and_then(|i| i.endpoints.get_mut(endpoint_index))
+                    {
+                        e.raw = Some(desc);
+                    }
+                    endpoint_index += 1;
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+const DT_INTERFACE: u8 = 0x04;
+const DT_ENDPOINT: u8 = 0x05;
+const DT_INTERFACE_ASSOCIATION: u8 = 0x0b;
+const DT_SS_ENDPOINT_COMPANION: u8 = 0x30;
+
+/// Interface Association Descriptor (IAD) grouping consecutive interfaces of a configuration into one function, such as UVC video control and streaming
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InterfaceAssociation {
+    /// Number of the first interface in the function, bFirstInterface
+    pub first_interface: u8,
+    /// Number of consecutive interfaces in the function, bInterfaceCount
+    pub interface_count: u8,
+    /// Class of the function, bFunctionClass
+    pub function_class: ClassCode,
+    /// Sub-class of the function, bFunctionSubClass
+    pub function_sub_class: u8,
+    /// Protocol of the function, bFunctionProtocol
+    pub function_protocol: u8,
+}
+
+impl InterfaceAssociation {
+    /// Parses a raw Interface Association Descriptor `desc`; None if it is not one or is too short
+    ///
+    /// ```
+    /// use cyme::usb::{ClassCode, InterfaceAssociation};
+    ///
+    /// let iad = InterfaceAssociation::from_descriptor(&[0x08, 0x0b, 0x00, 0x02, 0x0e, 0x03, 0x00, 0x05]).unwrap();
+    /// assert_eq!(iad.function_class, ClassCode::Video);
+    /// assert!(iad.contains(1));
+    /// assert!(!iad.contains(2));
+    /// ```
+    pub fn from_descriptor(desc: &[u8]) -> Option<Self> {
+        if desc.len() < 7 || desc[1] != DT_INTERFACE_ASSOCIATION {
+            return None;
+        }
+
+        Some(InterfaceAssociation {
+            first_interface: desc[2],
+            interface_count: desc[3],
+            function_class: ClassCode::from(desc[4]),
+            function_sub_class: desc[5],
+            function_protocol: desc[6],
+        })
+    }
+
+    /// Whether interface `number` is part of the function
+    pub fn contains(&self, number: u8) -> bool {
+        number >= self.first_interface
+            && (number as u16) < self.first_interface as u16 + self.interface_count as u16
+    }
+}
+
+/// Splits concatenated descriptors `raw` at each configuration, interface and endpoint descriptor; other descriptors are appended to the one before them. Stops at a truncated or zero length descriptor
+///
+/// ```
+/// use cyme::usb::split_descriptors;
+///
+/// let raw = [0x09, 0x02, 0x19, 0x00, 0x01, 0x01, 0x00, 0x80, 0x32,
+///            0x09, 0x04, 0x00, 0x00, 0x01, 0x09, 0x00, 0x00, 0x00,
+///            0x07, 0x05, 0x81, 0x03, 0x02, 0x00, 0xff];
+/// let split = split_descriptors(&raw);
+/// assert_eq!(split.len(), 3);
+/// assert_eq!(split[2], vec![0x07, 0x05, 0x81, 0x03, 0x02, 0x00, 0xff]);
+/// ```
+pub fn split_descriptors(raw: &[u8]) -> Vec<Vec<u8>> {
+    let mut ret: Vec<Vec<u8>> = Vec::new();
+
+    for desc in raw_descriptors(raw) {
+        match (desc[1], ret.last_mut()) {
+            (0x02 | DT_INTERFACE | DT_ENDPOINT, _) | (_, None) => ret.push(desc.to_vec()),
+            (_, Some(last)) => last.extend_from_slice(desc),
+        }
+    }
+
+    ret
+}
+
+/// Each descriptor in concatenated descriptors `raw` by bLength; stops at a truncated or zero length descriptor
+fn raw_descriptors(raw: &[u8]) -> Vec<&[u8]> {
+    let mut ret = Vec::new();
+    let mut offset = 0;
+
+    while offset + 2 <= raw.len() {
+        let len = raw[offset] as usize;
+        if len < 2 || offset + len > raw.len() {
+            break;
+        }
+        ret.push(&raw[offset..offset + len]);
+        offset += len;
+    }
+
+    ret
+}
+
+/// Printed in place of a hex dump when raw descriptor bytes were not captured, such as from macOS system_profiler
+pub const HEX_UNAVAILABLE: &str = "Hex dump unavailable: raw descriptor not captured";
+
+/// Formats `bytes` as hex rows of 16 bytes prefixed with the offset of the first byte in the row
+///
+/// ```
+/// use cyme::usb::hex_dump_lines;
+///
+/// let bytes: Vec<u8> = (0..18).collect();
+/// assert_eq!(
+///     hex_dump_lines(&bytes),
+///     vec![
+///         "0000: 00 01 02 03 04 05 06 07 08 09 0a 0b 0c 0d 0e 0f",
+///         "0010: 10 11",
+///     ]
+/// );
+/// ```
+pub fn hex_dump_lines(bytes: &[u8]) -> Vec<String> {
+    bytes
+        .chunks(16)
+        .enumerate()
+        .map(|(i, c)| {
+            format!(
+                "{:04x}: {}",
+                i * 16,
+                c.iter().map(|b| format!("{:02x}", b)).join(" ")
+            )
+        })
+        .collect()
 }
 
 /// Extra USB device data for verbose printing
 #[skip_serializing_none]
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct USBDeviceExtra {
     /// Maximum packet size in bytes
     pub max_packet_size: u8,
@@ -684,6 +1241,12 @@ pub struct USBDeviceExtra {
     pub string_indexes: (u8, u8, u8),
     /// USB devices can be have a number of configurations
     pub configurations: Vec<USBConfiguration>,
+    /// Unix timestamp in seconds when the device was initialized by udev; Linux only
+    pub connected_since: Option<u64>,
+    /// Runtime power management status like 'active' or 'suspended' from sysfs `power/runtime_status`, not the link state; Linux only
+    pub power_state: Option<String>,
+    /// udev tags like 'uaccess' and 'seat'; Linux only
+    pub tags: Option<Vec<String>>,
 }
 
 /// Builds a replica of sysfs path; excludes config.interface
@@ -798,4 +1361,29 @@ mod tests {
         assert_eq!(Version::try_from(2.01).unwrap(), Version(2, 0, 1));
         assert_eq!(Version::try_from(2.31).unwrap(), Version(2, 1, 15));
     }
+
+    #[test]
+    fn test_version_usb_category() {
+        assert_eq!(Version::from_bcd(0x0110).usb_category(), Some("USB 1.1"));
+        assert_eq!(Version::from_bcd(0x0200).usb_category(), Some("USB 2.0"));
+        assert_eq!(Version::from_bcd(0x0201).usb_category(), Some("USB 2.0"));
+        assert_eq!(
+            Version::from_bcd(0x0210).usb_category(),
+            Some("USB 2.0 LPM")
+        );
+        assert_eq!(
+            Version::from_bcd(0x0300).usb_category(),
+            Some("USB 3.2 Gen 1")
+        );
+        assert_eq!(
+            Version::from_bcd(0x0310).usb_category(),
+            Some("USB 3.2 Gen 2")
+        );
+        assert_eq!(
+            Version::from_bcd(0x0320).usb_category(),
+            Some("USB 3.2 Gen 2x2")
+        );
+        assert_eq!(Version::from_bcd(0x0400).usb_category(), Some("USB4"));
+        assert_eq!(Version::from_bcd(0x0990).usb_category(), None);
+    }
 }
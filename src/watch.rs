@@ -0,0 +1,366 @@
+//! Watch for USB hotplug events and re-render the [`display`] output on each change
+//!
+//! On Linux with the 'udev' feature a udev monitor on the 'usb' subsystem is used to trigger a re-profile. Other targets re-profile on a timer and only re-render if the profile has changed.
+//!
+//! [`repeat`] instead re-profiles and re-renders on a fixed interval regardless of changes.
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crate::display;
+use crate::system_profiler;
+
+/// Time to wait for no further events before re-rendering so a single plug does not cause many redraws
+pub const DEBOUNCE: Duration = Duration::from_millis(250);
+/// Interval to re-profile the system when hotplug events are not available
+pub const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Interval to check for events and whether Ctrl-C has been pressed
+const TICK: Duration = Duration::from_millis(50);
+
+const CLEAR_SCREEN: &str = "\x1B[2J\x1B[H";
+const HIDE_CURSOR: &str = "\x1B[?25l";
+const SHOW_CURSOR: &str = "\x1B[?25h";
+const BELL: &str = "\x07";
+
+/// Hides the cursor on creation and restores it when dropped, including on early return with error
+struct CursorGuard;
+
+impl CursorGuard {
+    fn new() -> Self {
+        print!("{}", HIDE_CURSOR);
+        io::stdout().flush().unwrap_or(());
+        CursorGuard
+    }
+}
+
+impl Drop for CursorGuard {
+    fn drop(&mut self) {
+        print!("{}", SHOW_CURSOR);
+        io::stdout().flush().unwrap_or(());
+    }
+}
+
+/// Source of change events which trigger a re-profile
+enum EventSource {
+    /// udev monitor socket for the 'usb' subsystem
+    #[cfg(all(target_os = "linux", feature = "udev"))]
+    Udev(::udev::MonitorSocket),
+    /// Re-profile every [`POLL_INTERVAL`]
+    Poll,
+}
+
+impl EventSource {
+    #[cfg(all(target_os = "linux", feature = "udev"))]
+    fn new() -> Self {
+        match ::udev::MonitorBuilder::new()
+            .and_then(|b| b.match_subsystem("usb"))
+            .and_then(|b| b.listen())
+        {
+            Ok(socket) => EventSource::Udev(socket),
+            Err(e) => {
+                log::warn!(
+                    "Failed to create udev monitor, falling back to polling: Error({})",
+                    e
+                );
+                EventSource::Poll
+            }
+        }
+    }
+
+    #[cfg(not(all(target_os = "linux", feature = "udev")))]
+    fn new() -> Self {
+        EventSource::Poll
+    }
+
+    /// Drains any pending events, returning whether there were any
+    fn pending(&self) -> bool {
+        match self {
+            #[cfg(all(target_os = "linux", feature = "udev"))]
+            EventSource::Udev(socket) => socket.iter().fold(false, |_, e| {
+                log::debug!("udev {:?} event for {:?}", e.event_type(), e.syspath());
+                true
+            }),
+            EventSource::Poll => true,
+        }
+    }
+
+    /// Blocks until there is an event or `running` is cleared; returns `true` if there was an event
+    fn wait(&self, running: &AtomicBool) -> bool {
+        match self {
+            #[cfg(all(target_os = "linux", feature = "udev"))]
+            EventSource::Udev(_) => {
+                while running.load(Ordering::SeqCst) {
+                    if self.pending() {
+                        return true;
+                    }
+                    thread::sleep(TICK);
+                }
+                false
+            }
+            EventSource::Poll => sleep_while_running(POLL_INTERVAL, running),
+        }
+    }
+
+    /// Waits until no events have arrived for [`DEBOUNCE`]
+    #[cfg_attr(
+        not(all(target_os = "linux", feature = "udev")),
+        allow(unused_variables)
+    )]
+    fn debounce(&self, running: &AtomicBool) {
+        match self {
+            #[cfg(all(target_os = "linux", feature = "udev"))]
+            EventSource::Udev(_) => {
+                let mut quiet = Duration::ZERO;
+                while running.load(Ordering::SeqCst) && quiet < DEBOUNCE {
+                    thread::sleep(TICK);
+                    if self.pending() {
+                        quiet = Duration::ZERO;
+                    } else {
+                        quiet += TICK;
+                    }
+                }
+            }
+            // profile is compared with the previous so no need to debounce
+            EventSource::Poll => (),
+        }
+    }
+}
+
+/// Sleeps for `duration` in [`TICK`]s, returning early if `running` is cleared; returns whether still running
+fn sleep_while_running(duration: Duration, running: &AtomicBool) -> bool {
+    let mut waited = Duration::ZERO;
+    while running.load(Ordering::SeqCst) && waited < duration {
+        thread::sleep(TICK);
+        waited += TICK;
+    }
+    running.load(Ordering::SeqCst)
+}
+
+/// Flag which is cleared when Ctrl-C is pressed
+fn interrupt_flag() -> Result<Arc<AtomicBool>, io::Error> {
+    let running = Arc::new(AtomicBool::new(true));
+    let r = running.clone();
+    ctrlc::set_handler(move || r.store(false, Ordering::SeqCst))
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+
+    Ok(running)
+}
+
+/// Clears the screen and prints `spusb` after [`display::prepare`] with `filter` and `settings`; returns the prepared `spusb` printed
+fn render(
+    mut spusb: system_profiler::SPUSBDataType,
+    filter: &Option<system_profiler::USBFilter>,
+    settings: &display::PrintSettings,
+) -> Result<system_profiler::SPUSBDataType, io::Error> {
+    display::prepare(&mut spusb, filter.clone(), settings);
+    let mut stdout = io::stdout().lock();
+    write!(stdout, "{}", CLEAR_SCREEN)?;
+    display::print(&spusb, settings, &mut stdout)?;
+
+    Ok(spusb)
+}
+
+/// Names of devices added and removed in `current` compared to `previous`, identified by [`system_profiler::USBDevice::port_path`]
+///
+/// Devices which have changed but are at the same port path are neither. Names are sorted by port path.
+///
+/// ```
+/// use cyme::system_profiler::*;
+/// use cyme::watch::device_changes;
+///
+/// let previous = read_json_dump("./tests/data/cyme_libusb_linux_tree.json").unwrap();
+/// let mut current = read_json_dump("./tests/data/cyme_libusb_linux_tree.json").unwrap();
+/// current.buses[1].devices = None;
+/// let (added, removed) = device_changes(&previous, &current);
+/// assert!(added.is_empty());
+/// assert!(!removed.is_empty());
+/// ```
+pub fn device_changes(
+    previous: &system_profiler::SPUSBDataType,
+    current: &system_profiler::SPUSBDataType,
+) -> (Vec<String>, Vec<String>) {
+    let paths = |spusb: &system_profiler::SPUSBDataType| -> HashMap<String, String> {
+        spusb
+            .flatten_devices()
+            .into_iter()
+            .map(|d| (d.port_path(), d.name.to_owned()))
+            .collect()
+    };
+    let (previous, current) = (paths(previous), paths(current));
+    let only_in = |a: &HashMap<String, String>, b: &HashMap<String, String>| -> Vec<String> {
+        let mut ret: Vec<(&String, &String)> =
+            a.iter().filter(|(p, _)| !b.contains_key(*p)).collect();
+        ret.sort();
+        ret.into_iter().map(|(_, name)| name.to_owned()).collect()
+    };
+
+    (only_in(&current, &previous), only_in(&previous, &current))
+}
+
+/// Notifies with `notify` of devices added or removed in `current` compared to `previous`, see [`device_changes`]; does nothing if there are none
+///
+/// Desktop notifications are best effort: a failure to run the notifier is only logged.
+pub fn notify_changes(
+    previous: &system_profiler::SPUSBDataType,
+    current: &system_profiler::SPUSBDataType,
+    notify: display::Notify,
+) -> Result<(), io::Error> {
+    let (added, removed) = device_changes(previous, current);
+    if added.is_empty() && removed.is_empty() {
+        return Ok(());
+    }
+
+    let mut stdout = io::stdout().lock();
+    write!(stdout, "{}", BELL)?;
+    stdout.flush()?;
+
+    if notify == display::Notify::Desktop {
+        let messages = added
+            .iter()
+            .map(|n| format!("Added: {}", n))
+            .chain(removed.iter().map(|n| format!("Removed: {}", n)));
+        for message in messages {
+            desktop_notification(&message).unwrap_or_else(|e| {
+                log::warn!("Failed to send desktop notification: Error({})", e)
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Sends `message` as a desktop notification with `notify-send`
+#[cfg(not(target_os = "macos"))]
+fn desktop_notification(message: &str) -> Result<(), io::Error> {
+    Command::new("notify-send")
+        .args(["cyme", message])
+        .status()
+        .map(|_| ())
+}
+
+/// Sends `message` as a desktop notification with `osascript`
+#[cfg(target_os = "macos")]
+fn desktop_notification(message: &str) -> Result<(), io::Error> {
+    let script = format!(
+        "display notification \"{}\" with title \"cyme\"",
+        message.replace('\\', "\\\\").replace('"', "\\\"")
+    );
+    Command::new("osascript")
+        .args(["-e", &script])
+        .status()
+        .map(|_| ())
+}
+
+/// Profile with `get_spusb` and print using `filter` and `settings` then re-profile and re-print each time the USB devices change until Ctrl-C is pressed
+///
+/// `get_spusb` is called for each re-profile so that the same profiler used for a one-shot print can be used. The screen is cleared before each print and the cursor hidden until Ctrl-C, when it is restored.
+///
+/// ```no_run
+/// use std::io::{Error, ErrorKind};
+/// use cyme::display::PrintSettings;
+/// use cyme::lsusb::profiler;
+/// use cyme::watch::watch;
+///
+/// let settings = PrintSettings {
+///     tree: true,
+///     ..Default::default()
+/// };
+/// watch(
+///     || profiler::get_spusb().map_err(|e| Error::new(ErrorKind::Other, e)),
+///     None,
+///     &settings,
+/// )
+/// .unwrap();
+/// ```
+pub fn watch<F>(
+    get_spusb: F,
+    filter: Option<system_profiler::USBFilter>,
+    settings: &display::PrintSettings,
+) -> Result<(), io::Error>
+where
+    F: Fn() -> Result<system_profiler::SPUSBDataType, io::Error>,
+{
+    let running = interrupt_flag()?;
+    let _cursor = CursorGuard::new();
+    let source = EventSource::new();
+
+    let spusb = get_spusb()?;
+    // compare the raw profile rather than the prepared one as masking serials may scramble
+    let mut last = serde_json::to_string(&spusb)?;
+    let mut shown = render(spusb, &filter, settings)?;
+
+    while source.wait(&running) {
+        source.debounce(&running);
+        if !running.load(Ordering::SeqCst) {
+            break;
+        }
+
+        let spusb = get_spusb()?;
+        let snapshot = serde_json::to_string(&spusb)?;
+        if snapshot != last {
+            log::info!("USB devices changed, re-rendering");
+            last = snapshot;
+            let current = render(spusb, &filter, settings)?;
+            if let Some(notify) = settings.notify {
+                notify_changes(&shown, &current, notify)?;
+            }
+            shown = current;
+        }
+    }
+
+    println!();
+
+    Ok(())
+}
+
+/// Profile with `get_spusb` and print using `filter` and `settings` every `interval` until Ctrl-C is pressed
+///
+/// Unlike [`watch`] the devices are re-printed each `interval` whether they have changed or not, like `watch cyme`, so it works the same on all targets. The screen is cleared before each print.
+///
+/// ```no_run
+/// use std::io::{Error, ErrorKind};
+/// use std::time::Duration;
+/// use cyme::display::PrintSettings;
+/// use cyme::lsusb::profiler;
+/// use cyme::watch::repeat;
+///
+/// repeat(
+///     || profiler::get_spusb().map_err(|e| Error::new(ErrorKind::Other, e)),
+///     None,
+///     &PrintSettings::default(),
+///     Duration::from_secs(2),
+/// )
+/// .unwrap();
+/// ```
+pub fn repeat<F>(
+    get_spusb: F,
+    filter: Option<system_profiler::USBFilter>,
+    settings: &display::PrintSettings,
+    interval: Duration,
+) -> Result<(), io::Error>
+where
+    F: Fn() -> Result<system_profiler::SPUSBDataType, io::Error>,
+{
+    let running = interrupt_flag()?;
+    let _cursor = CursorGuard::new();
+    let mut shown: Option<system_profiler::SPUSBDataType> = None;
+
+    loop {
+        let current = render(get_spusb()?, &filter, settings)?;
+        if let (Some(notify), Some(previous)) = (settings.notify, shown.as_ref()) {
+            notify_changes(previous, &current, notify)?;
+        }
+        shown = Some(current);
+        if !sleep_while_running(interval, &running) {
+            break;
+        }
+    }
+
+    println!();
+
+    Ok(())
+}
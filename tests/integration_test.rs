@@ -21,6 +21,44 @@ fn test_list() {
     );
 }
 
+#[test]
+fn test_list_yaml() {
+    let te = common::TestEnv::new();
+
+    let mut comp_sp = common::sp_data_from_libusb_linux();
+    comp_sp.flatten();
+    let devices = comp_sp.flatten_devices();
+    let comp = serde_yaml::to_string(&devices).unwrap();
+
+    te.assert_output(
+        Some(common::CYME_LIBUSB_LINUX_TREE_DUMP),
+        &["--yaml"],
+        &comp,
+        false,
+    );
+}
+
+#[test]
+fn test_list_ndjson() {
+    let te = common::TestEnv::new();
+
+    let mut comp_sp = common::sp_data_from_libusb_linux();
+    comp_sp.flatten();
+    let devices = comp_sp.flatten_devices();
+    let comp = devices
+        .iter()
+        .map(|d| serde_json::to_string(d).unwrap())
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    te.assert_output(
+        Some(common::CYME_LIBUSB_LINUX_TREE_DUMP),
+        &["--ndjson"],
+        &comp,
+        false,
+    );
+}
+
 #[test]
 fn test_list_filtering() {
     let te = common::TestEnv::new();
@@ -50,6 +88,20 @@ fn test_list_filtering() {
         false,
     );
 
+    te.assert_output(
+        Some(common::CYME_LIBUSB_LINUX_TREE_DUMP),
+        &["--json", "--vidpid", "1d50", "--only-matching"],
+        &comp,
+        false,
+    );
+
+    te.assert_output(
+        Some(common::CYME_LIBUSB_LINUX_TREE_DUMP),
+        &["--json", "--vidpid", "1d50", "--exclude-empty-hub", "--only-matching"],
+        &comp,
+        false,
+    );
+
     te.assert_output(
         Some(common::CYME_LIBUSB_LINUX_TREE_DUMP),
         &["--json", "--vidpid", "1d50:6018"],
@@ -69,6 +121,18 @@ fn test_list_filtering() {
         false,
     );
 
+    te.assert_output(
+        Some(common::CYME_LIBUSB_LINUX_TREE_DUMP),
+        &["--json", "--filter-name-regex", "^black magic"],
+        &comp,
+        false,
+    );
+
+    te.assert_failure(
+        Some(common::CYME_LIBUSB_LINUX_TREE_DUMP),
+        &["--json", "--filter-name-regex", "black[magic"],
+    );
+
     let mut comp_sp = common::sp_data_from_libusb_linux();
     let mut filter = cyme::system_profiler::USBFilter {
         bus: Some(2),
@@ -107,6 +171,46 @@ fn test_list_filtering() {
         Some(common::CYME_LIBUSB_LINUX_TREE_DUMP),
         &["--json", "--show", "blah"],
     );
+    let mut comp_sp = common::sp_data_from_libusb_linux();
+    let filter = cyme::system_profiler::USBFilter {
+        transfer_type: Some(cyme::usb::TransferType::Bulk),
+        no_exclude_root_hub: true,
+        ..Default::default()
+    };
+    comp_sp.flatten();
+    let mut devices = comp_sp.flatten_devices();
+    filter.retain_flattened_devices_ref(&mut devices);
+    let comp = serde_json::to_string_pretty(&devices).unwrap();
+
+    te.assert_output(
+        Some(common::CYME_LIBUSB_LINUX_TREE_DUMP),
+        &["--json", "--filter-transfer-type", "bulk"],
+        &comp,
+        false,
+    );
+
+    te.assert_failure(
+        Some(common::CYME_LIBUSB_LINUX_TREE_DUMP),
+        &["--json", "--filter-transfer-type", "bogus"],
+    );
+
+    let mut comp_sp = common::sp_data_from_libusb_linux();
+    let filter = cyme::system_profiler::USBFilter {
+        driver: Some("cdc_acm".into()),
+        no_exclude_root_hub: true,
+        ..Default::default()
+    };
+    comp_sp.flatten();
+    let mut devices = comp_sp.flatten_devices();
+    filter.retain_flattened_devices_ref(&mut devices);
+    let comp = serde_json::to_string_pretty(&devices).unwrap();
+
+    te.assert_output(
+        Some(common::CYME_LIBUSB_LINUX_TREE_DUMP),
+        &["--json", "--filter-driver", "cdc_acm"],
+        &comp,
+        false,
+    );
 }
 
 #[test]
@@ -142,3 +246,197 @@ fn test_tree_filtering() {
     );
 }
 
+
+#[test]
+fn test_block_args() {
+    let te = common::TestEnv::new();
+
+    te.assert_failure(
+        Some(common::CYME_LIBUSB_LINUX_TREE_DUMP),
+        &["--blocks", "+sys-path,-not-a-block"],
+    );
+}
+
+#[test]
+fn test_count() {
+    let te = common::TestEnv::new();
+
+    te.assert_output(
+        Some(common::CYME_LIBUSB_LINUX_TREE_DUMP),
+        &["--filter-driver", "cdc_acm", "--count"],
+        "2\n",
+        false,
+    );
+
+    // count replaces all other output including the summary
+    te.assert_output(
+        Some(common::CYME_LIBUSB_LINUX_TREE_DUMP),
+        &["--filter-driver", "cdc_acm", "--count", "--summary"],
+        "2\n",
+        false,
+    );
+}
+
+#[test]
+fn test_list_exclude_empty_hub() {
+    let te = common::TestEnv::new();
+
+    // hubs are pruned on the tree before flattening so the hub with devices is kept
+    let output = te.assert_success_and_get_output(
+        Some(common::CYME_LIBUSB_LINUX_TREE_DUMP),
+        &["--json", "--exclude-empty-hub"],
+    );
+    let devices: Vec<cyme::system_profiler::USBDevice> =
+        serde_json::from_slice(&output.stdout).unwrap();
+    assert!(devices.iter().any(|d| d.name == "Virtual USB1.1 HUB"));
+    assert!(devices.iter().any(|d| d.name == "J-Link"));
+
+    // but hiding hubs when listing removes them regardless
+    let output = te.assert_success_and_get_output(
+        Some(common::CYME_LIBUSB_LINUX_TREE_DUMP),
+        &["--json", "--hide-hubs"],
+    );
+    let devices: Vec<cyme::system_profiler::USBDevice> =
+        serde_json::from_slice(&output.stdout).unwrap();
+    assert!(devices.iter().all(|d| !d.is_hub()));
+    assert!(devices.iter().any(|d| d.name == "J-Link"));
+}
+
+#[test]
+fn test_json_compact() {
+    let te = common::TestEnv::new();
+
+    let comp_sp = common::sp_data_from_libusb_linux();
+    te.assert_output(
+        Some(common::CYME_LIBUSB_LINUX_TREE_DUMP),
+        &["--json", "--json-compact", "--tree"],
+        &serde_json::to_string(&comp_sp).unwrap(),
+        false,
+    );
+
+    let mut comp_sp = comp_sp;
+    comp_sp.flatten();
+    te.assert_output(
+        Some(common::CYME_LIBUSB_LINUX_TREE_DUMP),
+        &["--json", "--json-compact"],
+        &serde_json::to_string(&comp_sp.flatten_devices()).unwrap(),
+        false,
+    );
+}
+
+#[test]
+fn test_flatten_json() {
+    let te = common::TestEnv::new();
+
+    let num_devices = common::sp_data_from_libusb_linux().flatten_devices().len();
+
+    // always the flat array with --flatten-json, for tree and groups
+    for args in [
+        vec!["--json", "--flatten-json"],
+        vec!["--json", "--flatten-json", "--tree"],
+        vec!["--json", "--flatten-json", "--group-devices", "bus"],
+        vec!["--json", "--flatten-json", "--group-devices", "vendor"],
+    ] {
+        let output =
+            te.assert_success_and_get_output(Some(common::CYME_LIBUSB_LINUX_TREE_DUMP), &args);
+        let flat: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(flat.len(), num_devices, "cyme {}", args.join(" "));
+    }
+}
+
+#[test]
+fn test_group_devices_json() {
+    let te = common::TestEnv::new();
+
+    let comp_sp = common::sp_data_from_libusb_linux();
+
+    let output = te.assert_success_and_get_output(
+        Some(common::CYME_LIBUSB_LINUX_TREE_DUMP),
+        &["--json", "--group-devices", "bus"],
+    );
+    let grouped: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let map = grouped.as_object().unwrap();
+    assert_eq!(map.len(), comp_sp.buses.len());
+    for bus in comp_sp.buses.iter() {
+        let devices = map[&format!("Bus {:03}", bus.get_bus_number())]
+            .as_array()
+            .unwrap();
+        assert_eq!(devices.len(), bus.flattened_devices().len());
+    }
+
+    let output = te.assert_success_and_get_output(
+        Some(common::CYME_LIBUSB_LINUX_TREE_DUMP),
+        &["--json", "--group-devices", "vendor", "--fields", "name"],
+    );
+    let grouped: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let total: usize = grouped
+        .as_object()
+        .unwrap()
+        .values()
+        .map(|v| v.as_array().unwrap().len())
+        .sum();
+    assert_eq!(total, comp_sp.flatten_devices().len());
+}
+
+#[test]
+fn test_json_fields() {
+    let te = common::TestEnv::new();
+
+    let comp_sp = common::sp_data_from_libusb_linux();
+
+    let output = te.assert_success_and_get_output(
+        Some(common::CYME_LIBUSB_LINUX_TREE_DUMP),
+        &["--json", "--fields", "port-path,name"],
+    );
+    let flat: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(flat.len(), comp_sp.flatten_devices().len());
+    for v in flat.iter() {
+        let map = v.as_object().unwrap();
+        assert_eq!(map.len(), 2);
+        assert!(comp_sp.get_node(map["port-path"].as_str().unwrap()).is_some());
+    }
+
+    let output = te.assert_success_and_get_output(
+        Some(common::CYME_LIBUSB_LINUX_TREE_DUMP),
+        &["--json", "--tree", "--fields", "port-path,name"],
+    );
+    let tree: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let buses = tree["buses"].as_array().unwrap();
+    assert_eq!(buses.len(), comp_sp.buses.len());
+    assert!(buses
+        .iter()
+        .filter_map(|b| b["devices"].as_array())
+        .flatten()
+        .all(|d| d.get("port-path").is_some() && d.get("vendor-id").is_none()));
+}
+
+#[test]
+fn test_path() {
+    let te = common::TestEnv::new();
+
+    let comp_sp = common::sp_data_from_libusb_linux();
+    let device = comp_sp
+        .flatten_devices()
+        .into_iter()
+        .find(|d| d.has_devices() && !d.is_root_hub())
+        .unwrap();
+
+    let output = te.assert_success_and_get_output(
+        Some(common::CYME_LIBUSB_LINUX_TREE_DUMP),
+        &[
+            "--path",
+            &device.port_path(),
+            "--blocks",
+            "port-path",
+            "--no-padding",
+        ],
+    );
+    let out = String::from_utf8_lossy(&output.stdout);
+
+    // device and each descendant only
+    assert_eq!(
+        out.lines().count(),
+        cyme::system_profiler::get_all_devices(&vec![device.to_owned()]).len()
+    );
+    assert!(out.lines().next().unwrap().contains(&device.port_path()));
+}